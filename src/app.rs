@@ -1,34 +1,392 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Instant,
+};
+
 use egui_notify::Toasts;
 
 use crate::{
-    backend::mtc_decoder::MtcTimecodeDecoder,
-    components::{select_timecode_type::SelectTimecodeType, timecode_display::TimecodeDisplay},
+    backend::{
+        cue_list::CueList,
+        flywheel::SignalState,
+        frame_rate::FrameRate,
+        mmc_sender::{MmcCommand, MmcSender},
+        timecode_decoder::{PlaybackDirection, TimecodeDecoder as _, TimecodePosition},
+    },
+    components::{
+        comparison_panel::ComparisonPanel, failover_panel::FailoverPanel, input_panel::InputPanel,
+        ltc_file_analysis::LtcFileAnalysisPanel, mmc_panel::MmcPanel, output_panel::OutputPanel,
+        routing_panel::RoutingPanel,
+    },
+    devices::DeviceLists,
+    engine::{self, Engine},
+    failover::{Failover, FailoverSettings, FailoverSide, InputSignal},
+    input::{Decoder, Input, InputId},
+    output::{Output, OutputId, OutputSender},
+    routing::RoutingMatrix,
+    timecode_type::{TimecodeOutput, TimecodeSource, TimecodeType},
 };
 
 pub struct GlobalState {
-    pub mtc_decoder: MtcTimecodeDecoder,
+    /// Every timecode source being read, in the order they were added
+    pub inputs: BTreeMap<InputId, Input>,
+    /// Everywhere timecode is being sent, in the order they were added
+    pub outputs: BTreeMap<OutputId, Output>,
+    /// None if MIDI couldn't be initialised, E.g: on a machine without any MIDI support
+    pub mmc_sender: Option<MmcSender>,
+    /// The source MMC cues are played by
+    pub cue_source: Option<TimecodeSource>,
+    /// Played here rather than by the UI, so cues still fire while the window is hidden
+    pub mmc_cues: CueList<MmcCommand>,
+    pub failover: Failover,
+    pub failover_settings: FailoverSettings,
+    /// Which source goes to each output, the UI copies its routing in here whenever it redraws
+    pub routing: RoutingMatrix,
     pub toasts: Toasts,
+    /// The last position each input gave, however long ago that was. Reading a decoder uses its position up,
+    /// so it is read once here and everything that wants it looks here instead
+    latest_positions: HashMap<InputId, TimecodePosition>,
+}
+
+impl GlobalState {
+    /// Makes the inputs match the ones the UI has, creating a decoder for new inputs or ones that have changed type
+    /// and disconnecting the decoders of ones that have gone
+    pub fn sync_inputs<'a>(
+        &mut self,
+        inputs: impl IntoIterator<Item = (InputId, &'a str, TimecodeType)>,
+    ) {
+        let mut seen = Vec::new();
+
+        for (id, name, timecode_type) in inputs {
+            seen.push(id);
+
+            match self.inputs.get_mut(&id) {
+                Some(input) if input.decoder.timecode_type() == timecode_type => {
+                    if input.name != name {
+                        name.clone_into(&mut input.name);
+                    }
+                }
+                _ => {
+                    let replaced = self.inputs.insert(
+                        id,
+                        Input {
+                            name: name.to_owned(),
+                            decoder: Decoder::new(timecode_type),
+                        },
+                    );
+                    if let Some(mut input) = replaced {
+                        input.decoder.disconnect();
+                    }
+                    self.latest_positions.remove(&id);
+                }
+            }
+        }
+
+        self.inputs.retain(|id, input| {
+            let keep = seen.contains(id);
+            if !keep {
+                input.decoder.disconnect();
+            }
+            keep
+        });
+        self.latest_positions.retain(|id, _| seen.contains(id));
+    }
+
+    /// Makes the outputs match the ones the UI has, creating a sender for new outputs or ones that have changed type
+    /// and dropping (and so stopping) the senders of ones that have gone
+    pub fn sync_outputs<'a>(
+        &mut self,
+        outputs: impl IntoIterator<Item = (OutputId, &'a str, TimecodeOutput)>,
+    ) {
+        let mut seen = Vec::new();
+
+        for (id, name, output_type) in outputs {
+            seen.push(id);
+
+            match self.outputs.get_mut(&id) {
+                Some(output) if output.sender.output_type() == output_type => {
+                    if output.name != name {
+                        name.clone_into(&mut output.name);
+                    }
+                }
+                _ => {
+                    self.outputs.insert(
+                        id,
+                        Output {
+                            name: name.to_owned(),
+                            sender: OutputSender::new(output_type),
+                        },
+                    );
+                }
+            }
+        }
+
+        self.outputs.retain(|id, _| seen.contains(id));
+    }
+
+    /// Reads the inputs, switches the failover if it needs to and feeds the outputs.
+    /// The engine calls this every couple of milliseconds, see [`crate::engine::Engine`]
+    pub fn tick(&mut self) {
+        self.poll_decoders();
+        self.update_failover();
+        self.fire_mmc_cues();
+        self.feed_outputs();
+    }
+
+    /// Reads every decoder once, before anything asks for a position
+    fn poll_decoders(&mut self) {
+        for (id, input) in &mut self.inputs {
+            if let Some(position) = input.decoder.get_current_position() {
+                self.latest_positions.insert(*id, position);
+            }
+        }
+    }
+
+    /// Switches the failover between its inputs if it is time to, logging and toasting when it does.
+    /// Call this after [`Self::poll_decoders`] so it sees the latest signal states
+    fn update_failover(&mut self) {
+        let settings = self.failover_settings;
+        // A side that hasn't been chosen is never looked at, see `Failover::update`
+        let signal = |side| {
+            settings
+                .input(side)
+                .and_then(|id| self.inputs.get(&id))
+                .map_or(SignalState::Lost.into(), |input| {
+                    InputSignal::from(input.decoder.status())
+                })
+        };
+        let primary = signal(FailoverSide::Primary);
+        let backup = signal(FailoverSide::Backup);
+
+        let Some(side) = self
+            .failover
+            .update(settings, primary, backup, Instant::now())
+        else {
+            return;
+        };
+
+        let name = settings.input(side).map_or_else(String::new, |id| {
+            self.source_name(TimecodeSource::Input(id))
+        });
+        let message = format!(
+            "Failover switched to {} ({name})",
+            side.to_string().to_lowercase()
+        );
+
+        log::warn!("{message}, primary is {primary} and backup is {backup}");
+        self.toasts.warning(message);
+    }
+
+    /// Sends the commands of any cues the cue source has played through.
+    /// Call this after [`Self::update_failover`] so cues following the failover follow the right input
+    fn fire_mmc_cues(&mut self) {
+        if !self.mmc_sender.as_ref().is_some_and(MmcSender::connected) {
+            return;
+        }
+
+        // A freewheeling source is only guessing, so don't fire anything it may never really reach
+        if self
+            .cue_source
+            .and_then(|source| self.source_signal_state(source))
+            == Some(SignalState::Freewheeling)
+        {
+            return;
+        }
+
+        let Some((position, _)) = self
+            .cue_source
+            .and_then(|source| self.source_position(source))
+        else {
+            return;
+        };
+
+        for command in self.mmc_cues.update(position.timecode) {
+            self.send_mmc(command);
+        }
+    }
+
+    /// Sends an MMC command, reporting any failure
+    pub fn send_mmc(&mut self, command: MmcCommand) {
+        log::info!("Sending MMC {command}");
+
+        let Self {
+            mmc_sender: Some(sender),
+            toasts,
+            ..
+        } = self
+        else {
+            return;
+        };
+
+        if let Err(e) = sender.send(command) {
+            toasts.error(format!("Failed to send MMC: {e}"));
+        }
+    }
+
+    /// Stops playing MMC cues from a source that is being removed
+    pub fn forget_cue_source(&mut self, source: TimecodeSource) {
+        if self.cue_source == Some(source) {
+            self.cue_source = None;
+            self.mmc_cues.reset();
+        }
+    }
+
+    /// The last position an input gave, None if it hasn't given one since it was created
+    pub fn latest_position(&self, id: InputId) -> Option<TimecodePosition> {
+        self.latest_positions.get(&id).copied()
+    }
+
+    /// Every source, inputs first then the outputs that can say what they are sending
+    pub fn sources(&self) -> Vec<TimecodeSource> {
+        let outputs = self
+            .outputs
+            .iter()
+            .filter(|(_, output)| matches!(output.sender, OutputSender::Ltc(_)))
+            .map(|(id, _)| TimecodeSource::Output(*id));
+
+        self.inputs
+            .keys()
+            .map(|id| TimecodeSource::Input(*id))
+            .chain([TimecodeSource::Failover])
+            .chain(outputs)
+            .collect()
+    }
+
+    /// What to call a source in the UI
+    pub fn source_name(&self, source: TimecodeSource) -> String {
+        match source {
+            TimecodeSource::Input(id) => self
+                .inputs
+                .get(&id)
+                .map_or_else(|| "Removed input".to_owned(), |input| input.name.clone()),
+            TimecodeSource::Failover => "Failover".to_owned(),
+            TimecodeSource::Output(id) => self
+                .outputs
+                .get(&id)
+                .map_or_else(|| "Removed output".to_owned(), |output| output.name.clone()),
+        }
+    }
+
+    /// How the input behind a source is doing, None for outputs as they always know what they are sending
+    pub fn source_signal_state(&self, source: TimecodeSource) -> Option<SignalState> {
+        match source {
+            TimecodeSource::Input(id) => Some(self.inputs.get(&id)?.decoder.signal_state()),
+            TimecodeSource::Failover => {
+                self.source_signal_state(TimecodeSource::Input(self.failover.active_input()?))
+            }
+            TimecodeSource::Output(_) => None,
+        }
+    }
+
+    /// The latest position from a source and the direction it is playing in, None if it hasn't given one
+    pub fn source_position(
+        &mut self,
+        source: TimecodeSource,
+    ) -> Option<(TimecodePosition, Option<PlaybackDirection>)> {
+        match source {
+            TimecodeSource::Input(id) => {
+                let position = self.latest_position(id)?;
+                let direction = self.inputs.get(&id)?.decoder.status().direction;
+                Some((position, direction))
+            }
+            TimecodeSource::Failover => {
+                let id = self.failover.active_input()?;
+                self.source_position(TimecodeSource::Input(id))
+            }
+            TimecodeSource::Output(id) => {
+                let OutputSender::Ltc(generator) = &mut self.outputs.get_mut(&id)?.sender else {
+                    return None;
+                };
+                let timecode = generator.current_timecode()?;
+                Some((timecode.into(), Some(PlaybackDirection::Forward)))
+            }
+        }
+    }
+
+    /// Returns true if an output is sending
+    pub fn output_running(&self, id: OutputId) -> bool {
+        self.outputs
+            .get(&id)
+            .is_some_and(|output| output.sender.running())
+    }
+
+    /// The rate an output is sending at, None if it sends at whatever rate it is given or isn't running
+    fn output_rate(&self, id: OutputId) -> Option<FrameRate> {
+        self.outputs.get(&id)?.sender.rate()
+    }
+
+    /// Gives every running output the latest position from the source routed to it.
+    /// Outputs ignore a position they have already been given, so a source that hasn't moved is fine
+    fn feed_outputs(&mut self) {
+        for route in self.routing.routes().to_vec() {
+            if !self.output_running(route.output) {
+                continue;
+            }
+
+            let Some((position, direction)) = self.source_position(route.source) else {
+                continue;
+            };
+            let position = route.apply(position, self.output_rate(route.output));
+
+            if let Some(output) = self.outputs.get_mut(&route.output) {
+                output.sender.update(position, direction);
+            }
+        }
+    }
 }
 
 impl Default for GlobalState {
     fn default() -> Self {
         Self {
-            mtc_decoder: MtcTimecodeDecoder::new()
-                .expect("Catastropically failed to initialise MIDI backend"),
+            inputs: BTreeMap::new(),
+            outputs: BTreeMap::new(),
+            mmc_sender: MmcSender::new()
+                .map_err(|e| log::error!("Failed to initialise MIDI for MMC: {e}"))
+                .ok(),
+            cue_source: None,
+            mmc_cues: CueList::default(),
+            failover: Failover::default(),
+            failover_settings: FailoverSettings::default(),
+            routing: RoutingMatrix::default(),
             toasts: Default::default(),
+            latest_positions: HashMap::new(),
         }
     }
 }
 
 // We derive Deserialize/Serialize so we can persist app state on shutdown.
-#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct App {
-    select_timecode_type_component: SelectTimecodeType,
-    timecode_display: TimecodeDisplay,
+    inputs: Vec<InputPanel>,
+    comparison: ComparisonPanel,
+    failover: FailoverPanel,
+    ltc_file_analysis: LtcFileAnalysisPanel,
+    outputs: Vec<OutputPanel>,
+    mmc: MmcPanel,
+    routing: RoutingPanel,
 
     #[serde(skip)]
-    global_state: GlobalState,
+    engine: Engine,
+    #[serde(skip)]
+    devices: DeviceLists,
+}
+
+impl Default for App {
+    /// Starts with a single MTC input, as that is what most people have, and nowhere to send it
+    fn default() -> Self {
+        Self {
+            inputs: vec![InputPanel::new(InputId(0), TimecodeType::Mtc)],
+            comparison: ComparisonPanel::default(),
+            failover: FailoverPanel::default(),
+            ltc_file_analysis: LtcFileAnalysisPanel::default(),
+            outputs: Vec::new(),
+            mmc: MmcPanel::default(),
+            routing: RoutingPanel::default(),
+            engine: Engine::default(),
+            devices: DeviceLists::default(),
+        }
+    }
 }
 
 impl App {
@@ -39,17 +397,22 @@ impl App {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
+        let app: Self = if let Some(storage) = cc.storage {
             eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
         } else {
             Default::default()
-        }
+        };
+
+        app.mmc.restore_cues(&mut engine::lock(&app.engine.state()));
+
+        app
     }
 }
 
 impl eframe::App for App {
     /// Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.mmc.store_cues(&engine::lock(&self.engine.state()));
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
@@ -72,21 +435,246 @@ impl eframe::App for App {
             });
         });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            // The central panel the region left after adding TopPanel's and SidePanel's
-            ui.heading("Technus Timecode");
+        {
+            let state = self.engine.state();
+            let mut global_state = engine::lock(&state);
 
-            self.select_timecode_type_component
-                .add(ctx, ui, &mut self.global_state);
+            global_state.sync_inputs(self.inputs.iter().map(InputPanel::config));
+            global_state.sync_outputs(self.outputs.iter().map(OutputPanel::config));
+            global_state.failover_settings = self.failover.settings;
+            global_state.routing.clone_from(&self.routing.routing);
 
-            self.timecode_display.add(ctx, ui, &mut self.global_state);
+            if !self.engine.ticking() {
+                global_state.tick();
+                ctx.request_repaint();
+            }
 
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                egui::warn_if_debug_build(ui);
+            egui::CentralPanel::default().show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.add_panels(ctx, ui, &mut global_state);
+                });
             });
+
+            // Display toasts, do this after everything else
+            global_state.toasts.show(ctx);
+        }
+
+        // Asking the system for its devices can be slow, so only once the engine can carry on without us
+        if self.devices.refresh() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+impl App {
+    fn add_panels(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        global_state: &mut GlobalState,
+    ) {
+        // The central panel the region left after adding TopPanel's and SidePanel's
+        ui.heading("Technus Timecode");
+
+        self.add_inputs(ctx, ui, global_state);
+
+        self.comparison.add(ui, global_state);
+
+        self.failover.add(ui, global_state);
+
+        ui.separator();
+
+        self.ltc_file_analysis.add(ctx, ui, global_state);
+
+        self.add_outputs(ctx, ui, global_state);
+
+        self.routing.add(ui, global_state);
+
+        self.mmc.add(ctx, ui, global_state, &mut self.devices);
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+            egui::warn_if_debug_build(ui);
         });
+    }
+
+    /// Displays a panel for every input and a button to add another
+    fn add_inputs(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        global_state: &mut GlobalState,
+    ) {
+        let mut removed = None;
+
+        for input in &mut self.inputs {
+            if input.add(ctx, ui, global_state, &mut self.devices) {
+                removed = Some(input.id);
+            }
+        }
+
+        if let Some(id) = removed {
+            self.inputs.retain(|input| input.id != id);
+            self.failover.forget_input(id);
+            global_state.forget_cue_source(TimecodeSource::Input(id));
+            self.routing
+                .routing
+                .disconnect_source(TimecodeSource::Input(id));
+        }
+
+        if ui.button("Add input").clicked() {
+            let id = self
+                .inputs
+                .iter()
+                .map(|input| input.id.0 + 1)
+                .max()
+                .unwrap_or_default();
+
+            self.inputs
+                .push(InputPanel::new(InputId(id), TimecodeType::Mtc));
+        }
+    }
+
+    /// Displays a panel for every output and a button to add another
+    fn add_outputs(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        global_state: &mut GlobalState,
+    ) {
+        let mut removed = None;
+
+        for output in &mut self.outputs {
+            if output.add(ctx, ui, global_state, &mut self.devices) {
+                removed = Some(output.id);
+            }
+        }
+
+        if let Some(id) = removed {
+            self.outputs.retain(|output| output.id != id);
+            global_state.forget_cue_source(TimecodeSource::Output(id));
+            self.routing.routing.disconnect(id);
+            self.routing
+                .routing
+                .disconnect_source(TimecodeSource::Output(id));
+        }
+
+        if ui.button("Add output").clicked() {
+            let id = self
+                .outputs
+                .iter()
+                .map(|output| output.id.0 + 1)
+                .max()
+                .unwrap_or_default();
+
+            self.outputs
+                .push(OutputPanel::new(OutputId(id), TimecodeOutput::default()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn input_types(state: &GlobalState) -> Vec<(InputId, &str, TimecodeType)> {
+        state
+            .inputs
+            .iter()
+            .map(|(id, input)| (*id, input.name.as_str(), input.decoder.timecode_type()))
+            .collect()
+    }
+
+    #[test]
+    fn test_sync_inputs() {
+        let mut state = GlobalState::default();
+
+        state.sync_inputs([
+            (InputId(0), "Main", TimecodeType::Generator),
+            (InputId(1), "Backup", TimecodeType::Artnet),
+        ]);
+        assert_eq!(
+            input_types(&state),
+            [
+                (InputId(0), "Main", TimecodeType::Generator),
+                (InputId(1), "Backup", TimecodeType::Artnet)
+            ]
+        );
+
+        if let Some(Decoder::Generator(generator)) = state
+            .inputs
+            .get_mut(&InputId(0))
+            .map(|input| &mut input.decoder)
+        {
+            generator.play(Instant::now());
+        }
+        state.tick();
+        assert!(state.latest_position(InputId(0)).is_some());
+
+        // Renaming keeps the decoder, and what it last gave
+        state.sync_inputs([
+            (InputId(0), "Stage", TimecodeType::Generator),
+            (InputId(1), "Backup", TimecodeType::Artnet),
+        ]);
+        assert_eq!(
+            state.source_name(TimecodeSource::Input(InputId(0))),
+            "Stage"
+        );
+        assert!(state.latest_position(InputId(0)).is_some());
+
+        // Changing the type starts again with a new decoder
+        state.sync_inputs([
+            (InputId(0), "Stage", TimecodeType::Osc),
+            (InputId(1), "Backup", TimecodeType::Artnet),
+        ]);
+        assert_eq!(
+            input_types(&state),
+            [
+                (InputId(0), "Stage", TimecodeType::Osc),
+                (InputId(1), "Backup", TimecodeType::Artnet)
+            ]
+        );
+        assert_eq!(state.latest_position(InputId(0)), None);
+
+        state.sync_inputs([(InputId(1), "Backup", TimecodeType::Artnet)]);
+        assert_eq!(
+            input_types(&state),
+            [(InputId(1), "Backup", TimecodeType::Artnet)]
+        );
+    }
+
+    #[test]
+    fn test_sync_outputs() {
+        let mut state = GlobalState::default();
+        let output_types = |state: &GlobalState| {
+            state
+                .outputs
+                .iter()
+                .map(|(id, output)| (*id, output.sender.output_type()))
+                .collect::<Vec<_>>()
+        };
+
+        // Any number of outputs can be the same type
+        state.sync_outputs([
+            (OutputId(0), "Desk", TimecodeOutput::ArtnetSender),
+            (OutputId(1), "Backup desk", TimecodeOutput::ArtnetSender),
+        ]);
+        assert_eq!(
+            output_types(&state),
+            [
+                (OutputId(0), TimecodeOutput::ArtnetSender),
+                (OutputId(1), TimecodeOutput::ArtnetSender)
+            ]
+        );
 
-        // Display toasts, do this after everything else
-        self.global_state.toasts.show(ctx);
+        state.sync_outputs([(OutputId(1), "Media server", TimecodeOutput::OscSender)]);
+        assert_eq!(
+            output_types(&state),
+            [(OutputId(1), TimecodeOutput::OscSender)]
+        );
+        assert_eq!(
+            state.source_name(TimecodeSource::Output(OutputId(1))),
+            "Media server"
+        );
     }
 }
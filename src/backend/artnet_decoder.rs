@@ -0,0 +1,383 @@
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::backend::{
+    artnet_timecode::{ARTNET_PORT, ArtTimeCode},
+    flywheel::Flywheel,
+    network_interface::{NetworkInterface, bind_shared_udp},
+    packet_tracker::PacketTracker,
+    timecode_decoder::{
+        ConnectionState, DecoderStatus, ErrorCounters, PlaybackDirection, TimecodeDecoder,
+        TimecodePosition,
+    },
+};
+
+/// How often the network thread stops waiting for packets to check if it should stop
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What the network thread tells the decoder about
+enum ArtnetEvent {
+    Position {
+        position: TimecodePosition,
+        direction: Option<PlaybackDirection>,
+        measured_fps: Option<f64>,
+        stream_id: u8,
+    },
+    /// A packet that wasn't Art-Net or was a broken timecode packet
+    Malformed,
+    /// Frames were skipped between packets
+    Dropout,
+}
+
+/// Reads Art-Net `ArtTimeCode` packets from the network
+pub struct ArtnetTimecodeDecoder {
+    /// Only take timecode that came in on this interface, None to take it from all of them
+    pub interface: Option<NetworkInterface>,
+    /// Only take timecode from this address, for when there is more than one desk on the network
+    pub source_filter: Option<IpAddr>,
+    /// Keeps the timecode counting if packets stop arriving
+    pub flywheel: Flywheel,
+    /// Positions and errors from the network thread
+    timecode_rx: Option<Receiver<ArtnetEvent>>,
+    /// Everything about the signal that isn't tracked by the flywheel
+    status: DecoderStatus,
+    stream_id: Option<u8>,
+    /// Tells the network thread to finish
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ArtnetTimecodeDecoder {
+    /// Creates a new, disconnected, Art-Net decoder listening on all interfaces
+    pub fn new() -> Self {
+        Self {
+            interface: None,
+            source_filter: None,
+            flywheel: Flywheel::default(),
+            timecode_rx: None,
+            status: DecoderStatus::default(),
+            stream_id: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Start listening for Art-Net timecode on the selected interface.
+    ///
+    /// # Errors
+    /// Fails if the Art-Net port could not be opened, E.g: if another program is using it without sharing
+    pub fn connect(&mut self) -> Result<()> {
+        let socket = open_socket(ARTNET_PORT)
+            .map_err(|e| anyhow!("Failed to listen on port {ARTNET_PORT}: {e}"))?;
+
+        // Create a channel to send the timecode values from the network thread back to our function to get current timecode
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let filter = PacketFilter {
+            interface: self.interface.clone(),
+            source: self.source_filter,
+        };
+
+        let thread_stop = Arc::clone(&stop);
+        let thread = thread::Builder::new()
+            .name("Art-Net timecode".to_owned())
+            .spawn(move || receive(&socket, &filter, &thread_stop, &tx))?;
+
+        self.stop = stop;
+        self.thread = Some(thread);
+        self.timecode_rx = Some(rx);
+        self.status.errors = ErrorCounters::default();
+
+        Ok(())
+    }
+
+    /// Stops listening for Art-Net
+    ///
+    /// # Errors
+    /// Result will be Err if there is no connection currently open
+    pub fn disconnect(&mut self) -> Result<()> {
+        let thread = self
+            .thread
+            .take()
+            .ok_or_else(|| anyhow!("No connection is open, hence you cannot close it"))?;
+
+        self.stop.store(true, Ordering::Relaxed);
+        thread
+            .join()
+            .map_err(|_panic| anyhow!("The Art-Net thread panicked"))?;
+
+        self.timecode_rx = None;
+        self.status.direction = None;
+        self.status.measured_fps = None;
+        self.stream_id = None;
+        self.flywheel.reset();
+
+        Ok(())
+    }
+
+    /// Returns true if there has been a connection made.
+    pub fn connected(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    /// The stream ID of the last packet, 0 is the master stream
+    pub fn stream_id(&self) -> Option<u8> {
+        self.stream_id
+    }
+}
+
+impl Default for ArtnetTimecodeDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ArtnetTimecodeDecoder {
+    /// The network thread doesn't notice the decoder has gone, so tell it to finish
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Opens the Art-Net port on every interface.
+///
+/// A socket bound to one interface's own address never gets broadcasts, which is how desks send timecode,
+/// so the interface is picked by [`PacketFilter`] instead
+fn open_socket(port: u16) -> std::io::Result<UdpSocket> {
+    let socket = bind_shared_udp(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port))?;
+    socket.set_read_timeout(Some(STOP_CHECK_INTERVAL))?;
+
+    Ok(socket)
+}
+
+/// Which packets the network thread keeps
+struct PacketFilter {
+    /// Only packets from this interface's subnet
+    interface: Option<NetworkInterface>,
+    /// Only packets from this address
+    source: Option<IpAddr>,
+}
+
+impl PacketFilter {
+    fn accepts(&self, source: IpAddr) -> bool {
+        self.interface
+            .as_ref()
+            .is_none_or(|interface| interface.contains(source))
+            && self.source.is_none_or(|filter| filter == source)
+    }
+}
+
+/// Reads packets until told to stop, runs on its own thread
+fn receive(socket: &UdpSocket, filter: &PacketFilter, stop: &AtomicBool, tx: &Sender<ArtnetEvent>) {
+    let mut tracker = PacketTracker::default();
+    let mut buffer = [0; 1024];
+
+    while !stop.load(Ordering::Relaxed) {
+        let (length, source) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            // Nothing arrived in time, go round and check if we should stop
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => {
+                log::error!("Art-Net receive failed: {e}");
+                return;
+            }
+        };
+
+        if !filter.accepts(source.ip()) {
+            continue;
+        }
+
+        let event = match ArtTimeCode::from_bytes(buffer.get(..length).unwrap_or_default()) {
+            // Other Art-Net traffic like DMX is none of our business
+            Ok(None) => continue,
+            Ok(Some(packet)) => {
+                if let Ok(timecode) = packet.timecode() {
+                    if tracker.update(timecode, Instant::now()) {
+                        tx.send(ArtnetEvent::Dropout).ok();
+                    }
+
+                    ArtnetEvent::Position {
+                        position: timecode.into(),
+                        direction: tracker.direction,
+                        measured_fps: tracker.measured_fps,
+                        stream_id: packet.stream_id,
+                    }
+                } else {
+                    ArtnetEvent::Malformed
+                }
+            }
+            Err(_) => ArtnetEvent::Malformed,
+        };
+
+        if tx.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+impl TimecodeDecoder for ArtnetTimecodeDecoder {
+    /// Gets the current position from Art-Net. If no new value is available then it returns None,
+    /// unless the flywheel is counting on over a dropout
+    fn get_current_position(&mut self) -> Option<TimecodePosition> {
+        let rx = self.timecode_rx.as_ref()?;
+        let now = Instant::now();
+
+        let mut latest = None;
+        for event in rx.try_iter() {
+            match event {
+                ArtnetEvent::Position {
+                    position,
+                    direction,
+                    measured_fps,
+                    stream_id,
+                } => {
+                    latest = Some((position, direction));
+                    self.status.measured_fps = measured_fps;
+                    self.stream_id = Some(stream_id);
+                }
+                ArtnetEvent::Malformed => self.status.errors.malformed += 1,
+                ArtnetEvent::Dropout => self.status.errors.dropouts += 1,
+            }
+        }
+
+        let Some((position, direction)) = latest else {
+            return self.flywheel.poll(now);
+        };
+
+        self.status.direction = direction;
+        self.status.rate = Some(position.timecode.rate());
+        self.flywheel.update(position, direction, now);
+
+        Some(position)
+    }
+
+    fn status(&self) -> DecoderStatus {
+        DecoderStatus {
+            connection: if self.connected() {
+                ConnectionState::Connected
+            } else {
+                ConnectionState::Disconnected
+            },
+            signal: self.flywheel.state(),
+            last_received: self.flywheel.last_received(),
+            ..self.status
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{
+        artnet_timecode::ArtnetTimecodeType, frame_rate::FrameRate, timecode::Timecode,
+    };
+
+    #[test]
+    fn test_receives_from_socket() {
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("Free port on localhost");
+        socket
+            .set_read_timeout(Some(STOP_CHECK_INTERVAL))
+            .expect("Valid timeout");
+        let address = socket.local_addr().expect("Bound socket");
+
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let filter = PacketFilter {
+            interface: None,
+            source: None,
+        };
+        let thread = thread::spawn(move || receive(&socket, &filter, &thread_stop, &tx));
+
+        let sender = UdpSocket::bind("127.0.0.1:0").expect("Free port on localhost");
+        let packet = ArtTimeCode {
+            hours: 10,
+            timecode_type: ArtnetTimecodeType::Ebu,
+            ..Default::default()
+        };
+        sender
+            .send_to(&packet.to_bytes(), address)
+            .expect("Sent to localhost");
+        sender
+            .send_to(b"Not Art-Net", address)
+            .expect("Sent to localhost");
+
+        let first = rx.recv_timeout(Duration::from_secs(1));
+        assert!(
+            matches!(
+                first,
+                Ok(ArtnetEvent::Position { position, stream_id: 0, .. })
+                    if position.timecode == Timecode::new(10, 0, 0, 0, FrameRate::Fps25)
+            ),
+            "Timecode packet should be read"
+        );
+        assert!(
+            matches!(
+                rx.recv_timeout(Duration::from_secs(1)),
+                Ok(ArtnetEvent::Malformed)
+            ),
+            "Garbage should be counted"
+        );
+
+        stop.store(true, Ordering::Relaxed);
+        thread.join().expect("Thread finishes when stopped");
+    }
+
+    #[test]
+    fn test_receives_broadcast_on_selected_interface() {
+        let socket = open_socket(0).expect("Free port");
+        let port = socket.local_addr().expect("Bound socket").port();
+
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let filter = PacketFilter {
+            interface: Some(NetworkInterface {
+                name: "lo".to_owned(),
+                address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                netmask: IpAddr::V4(Ipv4Addr::new(255, 0, 0, 0)),
+                broadcast: Some(IpAddr::V4(Ipv4Addr::new(127, 255, 255, 255))),
+            }),
+            source: None,
+        };
+        let thread = thread::spawn(move || receive(&socket, &filter, &thread_stop, &tx));
+
+        let sender = UdpSocket::bind("127.0.0.1:0").expect("Free port on localhost");
+        sender.set_broadcast(true).expect("Broadcast allowed");
+        let packet = ArtTimeCode {
+            hours: 10,
+            timecode_type: ArtnetTimecodeType::Ebu,
+            ..Default::default()
+        };
+        sender
+            .send_to(
+                &packet.to_bytes(),
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 255, 255, 255)), port),
+            )
+            .expect("Broadcast on localhost");
+
+        assert!(
+            matches!(
+                rx.recv_timeout(Duration::from_secs(1)),
+                Ok(ArtnetEvent::Position { position, .. })
+                    if position.timecode == Timecode::new(10, 0, 0, 0, FrameRate::Fps25)
+            ),
+            "Broadcast should reach an input listening on one interface"
+        );
+
+        stop.store(true, Ordering::Relaxed);
+        thread.join().expect("Thread finishes when stopped");
+    }
+}
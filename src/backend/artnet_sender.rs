@@ -0,0 +1,172 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+    artnet_timecode::{ARTNET_PORT, ArtTimeCode, ArtnetTimecodeType},
+    frame_rate::FrameRate,
+    frame_sender::FrameSender,
+    timecode_decoder::{PlaybackDirection, TimecodePosition},
+};
+
+/// Where to send timecode and what it should look like
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtnetSenderSettings {
+    /// A broadcast address to reach a whole subnet, or a single desk's address
+    pub target: IpAddr,
+    /// The rate to send at, None to pick the one closest to the source
+    pub rate: Option<ArtnetTimecodeType>,
+    /// 0 is the master stream, use others to send more than one timecode on the same network
+    pub stream_id: u8,
+}
+
+impl Default for ArtnetSenderSettings {
+    /// Broadcasts to the primary Art-Net subnet
+    fn default() -> Self {
+        Self {
+            target: IpAddr::V4(Ipv4Addr::new(2, 255, 255, 255)),
+            rate: None,
+            stream_id: 0,
+        }
+    }
+}
+
+/// Sends Art-Net `ArtTimeCode` packets, one for every frame of the source
+pub struct ArtnetTimecodeSender {
+    sender: FrameSender,
+}
+
+impl ArtnetTimecodeSender {
+    /// Creates a new, stopped, sender
+    pub fn new() -> Self {
+        Self {
+            sender: FrameSender::new("Art-Net timecode sender"),
+        }
+    }
+
+    /// Start sending timecode. Nothing is sent until the first call to [`Self::update`]
+    ///
+    /// # Errors
+    /// Fails if a network socket could not be opened
+    pub fn start(&mut self, settings: ArtnetSenderSettings) -> Result<()> {
+        self.start_sending_to(settings, SocketAddr::new(settings.target, ARTNET_PORT))
+    }
+
+    /// Like [`Self::start`], but to any port rather than the Art-Net one
+    fn start_sending_to(
+        &mut self,
+        settings: ArtnetSenderSettings,
+        target: SocketAddr,
+    ) -> Result<()> {
+        let socket = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))?;
+        socket.set_broadcast(true)?;
+
+        self.sender.start(
+            settings.rate.map(ArtnetTimecodeType::rate),
+            move |position, _| packet_for(settings, position),
+            move |packet: &ArtTimeCode| {
+                if let Err(e) = socket.send_to(&packet.to_bytes(), target) {
+                    log::error!("Failed to send Art-Net timecode to {target}: {e}");
+                }
+            },
+        )
+    }
+
+    /// Stops sending timecode
+    ///
+    /// # Errors
+    /// Result will be Err if the sender isn't running
+    pub fn stop(&mut self) -> Result<()> {
+        self.sender.stop()
+    }
+
+    /// Returns true if timecode is being sent
+    pub fn running(&self) -> bool {
+        self.sender.running()
+    }
+
+    /// The rate timecode is being sent at, None if it follows the source or the sender isn't running
+    pub fn rate(&self) -> Option<FrameRate> {
+        self.sender.rate()
+    }
+
+    /// Give the sender the latest position from the source, `direction` is None if the source isn't playing
+    pub fn update(&mut self, position: TimecodePosition, direction: Option<PlaybackDirection>) {
+        self.sender.update(position, direction);
+    }
+}
+
+impl Default for ArtnetTimecodeSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The packet to send for a position
+fn packet_for(settings: ArtnetSenderSettings, position: TimecodePosition) -> ArtTimeCode {
+    ArtTimeCode::new(
+        position.timecode,
+        settings
+            .rate
+            .unwrap_or_else(|| ArtnetTimecodeType::from_rate(position.timecode.rate())),
+        settings.stream_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::backend::timecode::Timecode;
+
+    #[test]
+    fn test_packet_for_picks_closest_type() {
+        let position = TimecodePosition::from(Timecode::new(1, 0, 0, 2, FrameRate::Fps25));
+
+        let packet = packet_for(ArtnetSenderSettings::default(), position);
+        assert_eq!(packet.frames, 2);
+        assert_eq!(packet.timecode_type, ArtnetTimecodeType::Ebu);
+    }
+
+    #[test]
+    fn test_sends_to_target() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").expect("Free port on localhost");
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .expect("Valid timeout");
+        let target = receiver.local_addr().expect("Bound socket");
+
+        let settings = ArtnetSenderSettings {
+            target: target.ip(),
+            rate: Some(ArtnetTimecodeType::Smpte),
+            stream_id: 7,
+        };
+
+        // Art-Net always goes to the same port, so point the sender at the test receiver by hand
+        let mut sender = ArtnetTimecodeSender::new();
+        sender
+            .start_sending_to(settings, target)
+            .expect("Socket opens");
+
+        let position = TimecodePosition::from(Timecode::new(10, 0, 0, 0, FrameRate::Fps29_97Ndf));
+        sender.update(position, None);
+
+        let mut buffer = [0; 64];
+        let (length, _) = receiver.recv_from(&mut buffer).expect("Packet arrives");
+        let received = ArtTimeCode::from_bytes(buffer.get(..length).unwrap_or_default())
+            .ok()
+            .flatten();
+
+        assert_eq!(received.map(|packet| packet.stream_id), Some(7));
+        assert_eq!(
+            received.and_then(|packet| packet.timecode().ok()),
+            Some(Timecode::new(10, 0, 0, 0, FrameRate::Fps30))
+        );
+
+        sender.stop().expect("Sender was running");
+    }
+}
@@ -0,0 +1,302 @@
+use std::fmt::Display;
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{frame_rate::FrameRate, timecode::Timecode};
+
+/// The UDP port all Art-Net traffic is sent to
+pub const ARTNET_PORT: u16 = 6454;
+
+/// Every Art-Net packet starts with this
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+
+/// The opcode of an `ArtTimeCode` packet, sent low byte first
+const OP_TIME_CODE: u16 = 0x9700;
+
+/// The version of Art-Net we speak, Art-Net 4
+const PROTOCOL_VERSION: u16 = 14;
+
+/// The length of an `ArtTimeCode` packet
+pub const PACKET_LENGTH: usize = 19;
+
+/// The rate an `ArtTimeCode` packet says it is running at, Art-Net only has four of them
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ArtnetTimecodeType {
+    /// 24fps
+    Film,
+    /// 25fps
+    Ebu,
+    /// 29.97fps drop-frame
+    DropFrame,
+    /// 30fps
+    #[default]
+    Smpte,
+}
+
+impl ArtnetTimecodeType {
+    /// Every type, in the order of their Type field. Handy for filling combo boxes
+    pub const ALL: [Self; 4] = [Self::Film, Self::Ebu, Self::DropFrame, Self::Smpte];
+
+    /// The type that best matches a frame rate, E.g: 50fps gives EBU as it counts in the same seconds
+    pub fn from_rate(rate: FrameRate) -> Self {
+        if rate.is_drop_frame() {
+            return Self::DropFrame;
+        }
+
+        match rate.nominal_fps() {
+            24 | 48 => Self::Film,
+            25 | 50 => Self::Ebu,
+            _ => Self::Smpte,
+        }
+    }
+
+    /// Reads the Type field of a packet, None if it isn't one Art-Net defines
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Film),
+            1 => Some(Self::Ebu),
+            2 => Some(Self::DropFrame),
+            3 => Some(Self::Smpte),
+            _ => None,
+        }
+    }
+
+    /// The value of the Type field for this rate
+    pub fn byte(self) -> u8 {
+        match self {
+            Self::Film => 0,
+            Self::Ebu => 1,
+            Self::DropFrame => 2,
+            Self::Smpte => 3,
+        }
+    }
+
+    /// The frame rate timecode of this type counts in
+    pub fn rate(self) -> FrameRate {
+        match self {
+            Self::Film => FrameRate::Fps24,
+            Self::Ebu => FrameRate::Fps25,
+            Self::DropFrame => FrameRate::Fps29_97Df,
+            Self::Smpte => FrameRate::Fps30,
+        }
+    }
+}
+
+impl Display for ArtnetTimecodeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Film => write!(f, "Film (24fps)"),
+            Self::Ebu => write!(f, "EBU (25fps)"),
+            Self::DropFrame => write!(f, "DF (29.97fps)"),
+            Self::Smpte => write!(f, "SMPTE (30fps)"),
+        }
+    }
+}
+
+/// An Art-Net `ArtTimeCode` packet, which lighting desks use to share timecode over a network
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ArtTimeCode {
+    /// Tells apart several timecode streams on the same network, 0 is the master stream
+    pub stream_id: u8,
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+    pub timecode_type: ArtnetTimecodeType,
+}
+
+impl ArtTimeCode {
+    /// Creates a packet for a timecode, converting it to the rate of `timecode_type` if it isn't already.
+    /// Rates that count the same number of frames a second keep their labels, E.g: 29.97fps NDF sent as SMPTE
+    pub fn new(timecode: Timecode, timecode_type: ArtnetTimecodeType, stream_id: u8) -> Self {
+        let timecode = timecode.relabel(timecode_type.rate());
+
+        Self {
+            stream_id,
+            hours: timecode.hours(),
+            minutes: timecode.minutes(),
+            seconds: timecode.seconds(),
+            frames: timecode.frames(),
+            timecode_type,
+        }
+    }
+
+    /// Reads a packet received on the Art-Net port. Returns None for Art-Net packets that aren't timecode,
+    /// so they can be skipped over.
+    ///
+    /// # Errors
+    /// Fails if the packet isn't Art-Net at all, or is a timecode packet that is too short or has an unknown type
+    pub fn from_bytes(bytes: &[u8]) -> Result<Option<Self>> {
+        if bytes.get(..ARTNET_ID.len()) != Some(ARTNET_ID.as_slice()) {
+            return Err(anyhow!("Not an Art-Net packet"));
+        }
+
+        let byte = |index: usize| {
+            bytes
+                .get(index)
+                .copied()
+                .ok_or_else(|| anyhow!("ArtTimeCode packet is only {} bytes long", bytes.len()))
+        };
+
+        if u16::from_le_bytes([byte(8)?, byte(9)?]) != OP_TIME_CODE {
+            return Ok(None);
+        }
+
+        let type_byte = byte(18)?;
+
+        Ok(Some(Self {
+            stream_id: byte(13)?,
+            frames: byte(14)?,
+            seconds: byte(15)?,
+            minutes: byte(16)?,
+            hours: byte(17)?,
+            timecode_type: ArtnetTimecodeType::from_byte(type_byte)
+                .ok_or_else(|| anyhow!("Unknown ArtTimeCode type {type_byte}"))?,
+        }))
+    }
+
+    /// Gets the bytes to send for this packet
+    pub fn to_bytes(&self) -> [u8; PACKET_LENGTH] {
+        let [opcode_low, opcode_high] = OP_TIME_CODE.to_le_bytes();
+        let [version_high, version_low] = PROTOCOL_VERSION.to_be_bytes();
+        let fields = [
+            opcode_low,
+            opcode_high,
+            version_high,
+            version_low,
+            0, // Filler
+            self.stream_id,
+            self.frames,
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.timecode_type.byte(),
+        ];
+
+        let mut bytes = [0; PACKET_LENGTH];
+        for (byte, value) in bytes.iter_mut().zip(ARTNET_ID.iter().chain(&fields)) {
+            *byte = *value;
+        }
+
+        bytes
+    }
+
+    /// The timecode in the packet.
+    ///
+    /// # Errors
+    /// Fails if any of the values are out of range for the rate
+    pub fn timecode(&self) -> Result<Timecode> {
+        Ok(Timecode::try_new(
+            self.hours,
+            self.minutes,
+            self.seconds,
+            self.frames,
+            self.timecode_type.rate(),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let bytes = [
+            b'A', b'r', b't', b'-', b'N', b'e', b't', 0, 0x00, 0x97, 0, 14, 0, 3, 4, 3, 2, 1, 2,
+        ];
+
+        let packet = ArtTimeCode::from_bytes(&bytes)
+            .expect("Valid Art-Net packet")
+            .expect("Timecode packet");
+
+        assert_eq!(packet.stream_id, 3);
+        assert_eq!(packet.timecode_type, ArtnetTimecodeType::DropFrame);
+        assert_eq!(
+            packet.timecode().ok(),
+            Some(Timecode::new(1, 2, 3, 4, FrameRate::Fps29_97Df))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let packet = ArtTimeCode {
+            stream_id: 0,
+            hours: 23,
+            minutes: 59,
+            seconds: 59,
+            frames: 24,
+            timecode_type: ArtnetTimecodeType::Ebu,
+        };
+
+        assert_eq!(
+            ArtTimeCode::from_bytes(&packet.to_bytes()).ok(),
+            Some(Some(packet))
+        );
+    }
+
+    #[test]
+    fn test_new_keeps_labels_at_same_speed() {
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps29_97Ndf);
+
+        let packet = ArtTimeCode::new(timecode, ArtnetTimecodeType::Smpte, 1);
+
+        assert_eq!(
+            packet.timecode().ok(),
+            Some(Timecode::new(1, 2, 3, 4, FrameRate::Fps30))
+        );
+        assert_eq!(packet.stream_id, 1);
+    }
+
+    #[test]
+    fn test_new_converts_rate() {
+        let timecode = Timecode::new(1, 0, 0, 48, FrameRate::Fps50);
+
+        let packet = ArtTimeCode::new(timecode, ArtnetTimecodeType::Ebu, 0);
+
+        assert_eq!(
+            packet.timecode().ok(),
+            Some(Timecode::new(1, 0, 0, 24, FrameRate::Fps25))
+        );
+        assert_eq!(
+            ArtnetTimecodeType::from_rate(FrameRate::Fps59_94Df),
+            ArtnetTimecodeType::DropFrame
+        );
+    }
+
+    #[test]
+    fn test_other_opcode_is_skipped() {
+        // An ArtPoll
+        let bytes = [
+            b'A', b'r', b't', b'-', b'N', b'e', b't', 0, 0x00, 0x20, 0, 14, 0, 0,
+        ];
+
+        assert_eq!(ArtTimeCode::from_bytes(&bytes).ok(), Some(None));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let mut bytes = ArtTimeCode::default().to_bytes();
+
+        assert!(
+            ArtTimeCode::from_bytes(bytes.get(..15).unwrap_or_default()).is_err(),
+            "Too short"
+        );
+        assert!(ArtTimeCode::from_bytes(b"Not Art-Net").is_err(), "Wrong ID");
+
+        if let Some(type_byte) = bytes.last_mut() {
+            *type_byte = 4;
+        }
+        assert!(ArtTimeCode::from_bytes(&bytes).is_err(), "Unknown type");
+
+        // 30 is out of range for 25fps
+        let packet = ArtTimeCode {
+            frames: 30,
+            timecode_type: ArtnetTimecodeType::Ebu,
+            ..Default::default()
+        };
+        assert!(packet.timecode().is_err(), "Frames out of range");
+    }
+}
@@ -0,0 +1,356 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    time::Instant,
+};
+
+use anyhow::Result;
+use midi_msg::MidiMsg;
+
+use crate::backend::{
+    flywheel::SignalState,
+    frame_rate::FrameRate,
+    midi_input::MidiInputListener,
+    midi_transport::{CLOCK_TIMEOUT, CLOCKS_PER_BEAT, ClockTempo, TransportEvent, is_clock},
+    timecode::Timecode,
+    timecode_decoder::{
+        ConnectionState, DecoderStatus, ErrorCounters, PlaybackDirection, TimecodeDecoder,
+        TimecodePosition,
+    },
+};
+
+/// Song Position Pointer counts in sixteenth notes, which are six clocks
+const CLOCKS_PER_SIXTEENTH: u32 = 6;
+
+/// What the MIDI thread tells the decoder about
+enum ClockEvent {
+    /// A beat clock tick, and when it arrived in microseconds
+    Tick(u64),
+    Transport(TransportEvent),
+    /// A MIDI message that could not be parsed
+    Malformed,
+}
+
+/// Follows MIDI beat clock, Song Position Pointer and Start/Stop/Continue to work out how far into a song we are
+#[derive(Debug, Default)]
+pub struct BeatClock {
+    /// How many clocks since the start of the song
+    clocks: u32,
+    /// How long since the start of the song, in seconds. Counted up tick by tick so tempo changes are followed
+    seconds: f64,
+    playing: bool,
+    /// Start and Continue take effect on the next clock, which lands on the position they start from
+    waiting_for_first_clock: bool,
+    tempo: ClockTempo,
+    /// The measured tempo in BPM, None until two ticks have been seen
+    bpm: Option<f64>,
+}
+
+impl BeatClock {
+    /// Takes in a clock tick, `timestamp` being when it arrived in microseconds.
+    /// `fallback_bpm` is used until the tempo has been measured. Returns true if the song moved on
+    pub fn tick(&mut self, timestamp: u64, fallback_bpm: f64) -> bool {
+        self.bpm = self.tempo.tick(timestamp);
+
+        if !self.playing {
+            return false;
+        }
+
+        if self.waiting_for_first_clock {
+            self.waiting_for_first_clock = false;
+            return true;
+        }
+
+        self.clocks += 1;
+        self.seconds += seconds_per_clock(self.bpm.unwrap_or(fallback_bpm));
+
+        true
+    }
+
+    /// Takes in a transport event. Song positions are turned into time at the current tempo,
+    /// as there is no way of knowing what the tempo was earlier in the song
+    pub fn transport(&mut self, event: TransportEvent, fallback_bpm: f64) {
+        match event {
+            TransportEvent::Start => {
+                self.clocks = 0;
+                self.seconds = 0.0;
+                self.playing = true;
+                self.waiting_for_first_clock = true;
+            }
+            TransportEvent::Continue => {
+                self.playing = true;
+                self.waiting_for_first_clock = true;
+            }
+            TransportEvent::Stop => self.playing = false,
+            TransportEvent::SongPosition(sixteenths) => {
+                self.clocks = u32::from(sixteenths) * CLOCKS_PER_SIXTEENTH;
+                self.seconds =
+                    f64::from(self.clocks) * seconds_per_clock(self.bpm.unwrap_or(fallback_bpm));
+            }
+            _ => {}
+        }
+    }
+
+    /// How long since the start of the song, in seconds
+    pub fn seconds(&self) -> f64 {
+        self.seconds
+    }
+
+    /// How many quarter notes since the start of the song
+    pub fn beats(&self) -> f64 {
+        f64::from(self.clocks) / f64::from(CLOCKS_PER_BEAT)
+    }
+
+    pub fn playing(&self) -> bool {
+        self.playing
+    }
+
+    /// The measured tempo in BPM, None until two ticks have been seen
+    pub fn bpm(&self) -> Option<f64> {
+        self.bpm
+    }
+}
+
+fn seconds_per_clock(bpm: f64) -> f64 {
+    60.0 / (bpm * f64::from(CLOCKS_PER_BEAT))
+}
+
+/// Turns MIDI beat clock and song position into timecode, for playback rigs that don't send MTC
+pub struct BeatClockDecoder {
+    pub input: MidiInputListener,
+    /// The timecode at the very start of the song, which also sets the rate. E.g: 01:00:00:00
+    pub start: Timecode,
+    /// The tempo used until the clock has been measured, E.g: to place a song position sent before the clock starts
+    pub fallback_bpm: f64,
+    beat_clock: BeatClock,
+    /// Ticks and transport from the MIDI thread
+    events_rx: Option<Receiver<ClockEvent>>,
+    /// When the last tick arrived
+    last_tick: Option<Instant>,
+    /// Whether the position has changed since it was last read
+    changed: bool,
+    errors: ErrorCounters,
+}
+
+impl BeatClockDecoder {
+    /// Creates a new, disconnected, beat clock decoder.
+    ///
+    /// # Errors
+    /// Fails if the system MIDI backend could not be initialised
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            input: MidiInputListener::new("technus timecode beat clock input")?,
+            start: Timecode::new(1, 0, 0, 0, FrameRate::Fps25),
+            fallback_bpm: 120.0,
+            beat_clock: BeatClock::default(),
+            events_rx: None,
+            last_tick: None,
+            changed: false,
+            errors: ErrorCounters::default(),
+        })
+    }
+
+    /// Connect to the selected MIDI port and start following the clock.
+    ///
+    /// # Errors
+    /// Fails if no port has been selected or the connection could not be opened
+    pub fn connect(&mut self) -> Result<()> {
+        let (tx, rx) = mpsc::channel();
+
+        self.input.connect(move |timestamp, message| {
+            let send = |event| {
+                tx.send(event).ok();
+            };
+
+            let parsed_message = MidiMsg::from_midi(message).ok().map(|(parsed, _)| parsed);
+
+            if parsed_message.as_ref().is_some_and(is_clock) {
+                send(ClockEvent::Tick(timestamp));
+                return;
+            }
+
+            let transport = TransportEvent::from_midi(message, parsed_message.as_ref());
+            if transport.is_empty() && parsed_message.is_none() {
+                send(ClockEvent::Malformed);
+            }
+            for event in transport {
+                send(ClockEvent::Transport(event));
+            }
+        })?;
+
+        self.events_rx = Some(rx);
+        self.beat_clock = BeatClock::default();
+        self.errors = ErrorCounters::default();
+
+        Ok(())
+    }
+
+    /// Closes the currently open connection
+    ///
+    /// # Errors
+    /// Result will be Err if there is no connection currently open
+    pub fn disconnect(&mut self) -> Result<()> {
+        self.input.disconnect()?;
+
+        self.events_rx = None;
+        self.last_tick = None;
+
+        Ok(())
+    }
+
+    /// Returns true if there has been a connection made.
+    pub fn connected(&self) -> bool {
+        self.input.connected()
+    }
+
+    pub fn beat_clock(&self) -> &BeatClock {
+        &self.beat_clock
+    }
+
+    /// Moves the start of the song, which changes the position even if the clock isn't running
+    pub fn set_start(&mut self, start: Timecode) {
+        self.start = start;
+        self.changed = true;
+    }
+
+    /// Whether the clock has ticked recently
+    fn clock_running(&self, now: Instant) -> bool {
+        self.last_tick
+            .is_some_and(|last_tick| now.saturating_duration_since(last_tick) <= CLOCK_TIMEOUT)
+    }
+
+    /// The position a number of seconds into the song
+    fn position(&self) -> TimecodePosition {
+        position_at(self.start, self.beat_clock.seconds())
+    }
+}
+
+/// The position `seconds` on from `start`, wrapping at 24 hours
+fn position_at(start: Timecode, seconds: f64) -> TimecodePosition {
+    let rate = start.rate();
+    let frames = (f64::from(start.frame_count()) + seconds * rate.as_f64())
+        .rem_euclid(f64::from(rate.frames_per_day()));
+
+    TimecodePosition {
+        timecode: Timecode::from_frame_count(frames.floor() as u32, rate),
+        subframe: frames.fract(),
+    }
+}
+
+impl TimecodeDecoder for BeatClockDecoder {
+    /// Gets where the song is. Only returns a position when it has moved
+    fn get_current_position(&mut self) -> Option<TimecodePosition> {
+        let rx = self.events_rx.as_ref()?;
+        let now = Instant::now();
+
+        let events: Vec<_> = rx.try_iter().collect();
+        for event in events {
+            match event {
+                ClockEvent::Tick(timestamp) => {
+                    self.last_tick = Some(now);
+                    self.changed |= self.beat_clock.tick(timestamp, self.fallback_bpm);
+                }
+                ClockEvent::Transport(event) => {
+                    self.beat_clock.transport(event, self.fallback_bpm);
+                    self.changed |= matches!(
+                        event,
+                        TransportEvent::Start | TransportEvent::SongPosition(_)
+                    );
+                }
+                ClockEvent::Malformed => self.errors.malformed += 1,
+            }
+        }
+
+        if !self.changed {
+            return None;
+        }
+
+        self.changed = false;
+        Some(self.position())
+    }
+
+    fn status(&self) -> DecoderStatus {
+        let now = Instant::now();
+        let playing = self.beat_clock.playing() && self.clock_running(now);
+
+        DecoderStatus {
+            connection: if self.connected() {
+                ConnectionState::Connected
+            } else {
+                ConnectionState::Disconnected
+            },
+            signal: match (self.clock_running(now), playing) {
+                (false, _) => SignalState::Lost,
+                (true, true) => SignalState::Locked,
+                (true, false) => SignalState::Stopped,
+            },
+            last_received: self.last_tick,
+            rate: Some(self.start.rate()),
+            measured_fps: None,
+            direction: playing.then_some(PlaybackDirection::Forward),
+            errors: self.errors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// A tick every 20.8ms is 120 BPM
+    const TICK_MICROS: u64 = 500_000 / 24;
+
+    #[test]
+    fn test_start_counts_from_zero() {
+        let mut clock = BeatClock::default();
+
+        clock.tick(0, 100.0);
+        clock.transport(TransportEvent::Start, 100.0);
+
+        // The first tick after Start is the downbeat
+        assert!(clock.tick(TICK_MICROS, 100.0));
+        assert_eq!(clock.beats(), 0.0);
+
+        for tick in 2..=25 {
+            clock.tick(tick * TICK_MICROS, 100.0);
+        }
+        assert_eq!(clock.beats(), 1.0);
+        assert!((clock.seconds() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_song_position_and_continue() {
+        let mut clock = BeatClock::default();
+
+        // Bar 3 at the fallback tempo, as the clock isn't running yet
+        clock.transport(TransportEvent::SongPosition(32), 120.0);
+        assert_eq!(clock.beats(), 8.0);
+        assert!((clock.seconds() - 4.0).abs() < 1e-6);
+
+        // Nothing moves while stopped
+        assert!(!clock.tick(0, 120.0));
+        assert_eq!(clock.beats(), 8.0);
+
+        clock.transport(TransportEvent::Continue, 120.0);
+        clock.tick(TICK_MICROS, 120.0);
+        assert_eq!(clock.beats(), 8.0);
+        clock.tick(TICK_MICROS * 2, 120.0);
+        assert_eq!(clock.beats(), 8.0 + 1.0 / 24.0);
+
+        clock.transport(TransportEvent::Stop, 120.0);
+        assert!(!clock.playing());
+    }
+
+    #[test]
+    fn test_position_at() {
+        let start = Timecode::new(1, 0, 0, 0, FrameRate::Fps25);
+
+        assert_eq!(
+            position_at(start, 2.5),
+            TimecodePosition {
+                timecode: Timecode::new(1, 0, 2, 12, FrameRate::Fps25),
+                subframe: 0.5,
+            }
+        );
+    }
+}
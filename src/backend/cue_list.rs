@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use crate::backend::timecode::Timecode;
+
+/// How far a source can move between two positions and still be playing through the cues in between, in seconds.
+/// Anything further is a locate, which shouldn't fire everything it skipped over
+const MAX_PLAY_THROUGH_SECONDS: u32 = 2;
+
+/// Something to do when timecode reaches a point
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Cue<T> {
+    pub at: Timecode,
+    pub action: T,
+}
+
+/// Cues to fire as a source plays through them
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CueList<T> {
+    pub cues: Vec<Cue<T>>,
+    /// Where the source was last time, not worth keeping between runs
+    #[serde(skip)]
+    last_timecode: Option<Timecode>,
+}
+
+impl<T> Default for CueList<T> {
+    fn default() -> Self {
+        Self {
+            cues: Vec::new(),
+            last_timecode: None,
+        }
+    }
+}
+
+impl<T: Copy> CueList<T> {
+    /// Takes the latest timecode from the source, returning the actions of every cue it has played through
+    /// since last time. Cues at another rate are converted to the source's
+    pub fn update(&mut self, timecode: Timecode) -> Vec<T> {
+        let Some(last) = self.last_timecode.replace(timecode) else {
+            return Vec::new();
+        };
+
+        let rate = timecode.rate();
+        let frames_per_day = rate.frames_per_day();
+        let last = last.relabel(rate).frame_count();
+        // How far the source has moved on from `last`, going forwards through midnight if it has to
+        let ahead_of_last =
+            |frame_count: u32| (frame_count + frames_per_day - last) % frames_per_day;
+        let moved = ahead_of_last(timecode.frame_count());
+
+        // Only playing forwards fires cues. Going backwards looks like moving most of a day forwards, so it is too far
+        if moved == 0 || moved > MAX_PLAY_THROUGH_SECONDS * u32::from(rate.nominal_fps()) {
+            return Vec::new();
+        }
+
+        self.cues
+            .iter()
+            .filter(|cue| {
+                let at = ahead_of_last(cue.at.relabel(rate).frame_count());
+                0 < at && at <= moved
+            })
+            .map(|cue| cue.action)
+            .collect()
+    }
+
+    /// Forgets where the source was, so nothing fires until it has moved on from its next position
+    pub fn reset(&mut self) {
+        self.last_timecode = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::frame_rate::FrameRate;
+
+    fn timecode(seconds: u8, frames: u8) -> Timecode {
+        Timecode::new(1, 0, seconds, frames, FrameRate::Fps25)
+    }
+
+    #[test]
+    fn test_fires_cues_played_through() {
+        let mut cues = CueList {
+            cues: vec![
+                Cue {
+                    at: timecode(1, 0),
+                    action: 'a',
+                },
+                Cue {
+                    at: timecode(1, 5),
+                    action: 'b',
+                },
+                // Cues at another rate are converted
+                Cue {
+                    at: Timecode::new(1, 0, 2, 0, FrameRate::Fps30),
+                    action: 'c',
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert!(cues.update(timecode(0, 20)).is_empty());
+        assert_eq!(cues.update(timecode(1, 0)), vec!['a']);
+        assert!(cues.update(timecode(1, 0)).is_empty());
+        assert_eq!(cues.update(timecode(1, 10)), vec!['b']);
+
+        // Going backwards doesn't fire anything, but playing through again does
+        assert!(cues.update(timecode(0, 24)).is_empty());
+        assert_eq!(cues.update(timecode(1, 6)), vec!['a', 'b']);
+        assert_eq!(cues.update(timecode(2, 0)), vec!['c']);
+    }
+
+    #[test]
+    fn test_locate_skips_cues() {
+        let mut cues = CueList {
+            cues: vec![Cue {
+                at: timecode(30, 0),
+                action: (),
+            }],
+            ..Default::default()
+        };
+
+        cues.update(timecode(0, 0));
+        assert!(cues.update(timecode(40, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_fires_cues_played_through_midnight() {
+        let mut cues = CueList {
+            cues: vec![
+                Cue {
+                    at: Timecode::new(23, 59, 59, 24, FrameRate::Fps25),
+                    action: 'a',
+                },
+                Cue {
+                    at: Timecode::new(0, 0, 0, 0, FrameRate::Fps25),
+                    action: 'b',
+                },
+                Cue {
+                    at: Timecode::new(0, 0, 0, 10, FrameRate::Fps25),
+                    action: 'c',
+                },
+            ],
+            ..Default::default()
+        };
+
+        cues.update(Timecode::new(23, 59, 59, 20, FrameRate::Fps25));
+        assert_eq!(
+            cues.update(Timecode::new(0, 0, 0, 5, FrameRate::Fps25)),
+            vec!['a', 'b']
+        );
+        assert_eq!(
+            cues.update(Timecode::new(0, 0, 0, 10, FrameRate::Fps25)),
+            vec!['c']
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use crate::backend::timecode_decoder::{PlaybackDirection, TimecodePosition};
+
+/// How many frames can pass without a new value before we decide the signal has gone
+const LOSS_THRESHOLD_FRAMES: f64 = 2.0;
+
+/// How long a source can send nothing before it counts as gone rather than parked, E.g: an unplugged cable.
+/// Most sources go quiet when they stop, so this has to be longer than a pause in the show is likely to be
+const LOST_AFTER: Duration = Duration::from_secs(10);
+
+/// How a timecode source is doing, as seen through a [`Flywheel`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SignalState {
+    /// Nothing has been received yet, or nothing for so long that the source has probably gone
+    #[default]
+    Lost,
+    /// Timecode is arriving normally
+    Locked,
+    /// Timecode stopped arriving while it was playing, so we are counting on by ourselves
+    Freewheeling,
+    /// Timecode stopped arriving and we have given up counting, the last value is held
+    Stopped,
+}
+
+impl Display for SignalState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Lost => write!(f, "No signal"),
+            Self::Locked => write!(f, "Locked"),
+            Self::Freewheeling => write!(f, "Freewheeling"),
+            Self::Stopped => write!(f, "Stopped"),
+        }
+    }
+}
+
+/// Keeps a timecode running for a while after its source drops out, so a short glitch doesn't freeze the display
+pub struct Flywheel {
+    /// How many frames to keep counting for after the signal is lost, 0 to stop straight away
+    pub freewheel_frames: u32,
+    /// The last value received, the direction it was playing in and when it arrived
+    last_received: Option<(TimecodePosition, Option<PlaybackDirection>, Instant)>,
+    state: SignalState,
+}
+
+impl Flywheel {
+    pub fn new(freewheel_frames: u32) -> Self {
+        Self {
+            freewheel_frames,
+            last_received: None,
+            state: SignalState::Lost,
+        }
+    }
+
+    /// Forget everything received so far, E.g: after disconnecting
+    pub fn reset(&mut self) {
+        self.last_received = None;
+        self.state = SignalState::Lost;
+    }
+
+    /// Record a new value from the source, `direction` is None if the source isn't known to be playing
+    pub fn update(
+        &mut self,
+        position: TimecodePosition,
+        direction: Option<PlaybackDirection>,
+        now: Instant,
+    ) {
+        self.last_received = Some((position, direction, now));
+        self.state = SignalState::Locked;
+    }
+
+    /// Checks whether the source has dropped out. While freewheeling this returns a new position every time,
+    /// and once freewheeling ends it returns the last value received so the source parks where it really stopped.
+    /// Otherwise it returns None as there is nothing new to show
+    pub fn poll(&mut self, now: Instant) -> Option<TimecodePosition> {
+        let (position, direction, received_at) = self.last_received?;
+        let was_freewheeling = self.state == SignalState::Freewheeling;
+
+        let rate = position.timecode.rate().as_f64();
+        let elapsed_frames = now.saturating_duration_since(received_at).as_secs_f64() * rate;
+
+        if elapsed_frames <= LOSS_THRESHOLD_FRAMES {
+            return None;
+        }
+
+        if now.saturating_duration_since(received_at) > LOST_AFTER {
+            self.state = SignalState::Lost;
+            return was_freewheeling.then_some(position);
+        }
+
+        // Only a source that was playing can be counted on, one that was parked after a locate has just stopped
+        let freewheel_limit = LOSS_THRESHOLD_FRAMES + f64::from(self.freewheel_frames);
+        let Some(direction) = direction.filter(|_| elapsed_frames <= freewheel_limit) else {
+            self.state = SignalState::Stopped;
+            return was_freewheeling.then_some(position);
+        };
+
+        self.state = SignalState::Freewheeling;
+
+        Some(match direction {
+            PlaybackDirection::Forward => position.offset_by_frames(elapsed_frames),
+            PlaybackDirection::Reverse => position.offset_by_frames(-elapsed_frames),
+        })
+    }
+
+    pub fn state(&self) -> SignalState {
+        self.state
+    }
+
+    /// When the source last sent a value, None if nothing has been received since the last reset
+    pub fn last_received(&self) -> Option<Instant> {
+        self.last_received.map(|(_, _, received_at)| received_at)
+    }
+}
+
+impl Default for Flywheel {
+    /// Freewheels for a second at 25fps, enough to ride out a dropped cable or a busy MIDI interface
+    fn default() -> Self {
+        Self::new(25)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::backend::{frame_rate::FrameRate, timecode::Timecode};
+
+    fn timecode(frames: u8) -> Timecode {
+        Timecode::new(1, 0, 0, frames, FrameRate::Fps25)
+    }
+
+    #[test]
+    fn test_lost_until_first_value() {
+        let mut flywheel = Flywheel::new(25);
+
+        assert_eq!(flywheel.poll(Instant::now()), None);
+        assert_eq!(flywheel.state(), SignalState::Lost);
+    }
+
+    #[test]
+    fn test_locked_while_values_arrive() {
+        let mut flywheel = Flywheel::new(25);
+        let start = Instant::now();
+
+        flywheel.update(timecode(0).into(), Some(PlaybackDirection::Forward), start);
+
+        assert_eq!(flywheel.poll(start + Duration::from_millis(40)), None);
+        assert_eq!(flywheel.state(), SignalState::Locked);
+    }
+
+    #[test]
+    fn test_freewheels_after_signal_loss() {
+        let mut flywheel = Flywheel::new(25);
+        let start = Instant::now();
+
+        flywheel.update(timecode(0).into(), Some(PlaybackDirection::Forward), start);
+
+        assert_eq!(
+            flywheel.poll(start + Duration::from_millis(200)),
+            Some(timecode(5).into())
+        );
+        assert_eq!(flywheel.state(), SignalState::Freewheeling);
+    }
+
+    #[test]
+    fn test_freewheels_backwards_in_reverse() {
+        let mut flywheel = Flywheel::new(25);
+        let start = Instant::now();
+
+        flywheel.update(timecode(10).into(), Some(PlaybackDirection::Reverse), start);
+
+        assert_eq!(
+            flywheel.poll(start + Duration::from_millis(200)),
+            Some(timecode(5).into())
+        );
+    }
+
+    #[test]
+    fn test_stops_after_freewheel_runs_out() {
+        let mut flywheel = Flywheel::new(25);
+        let start = Instant::now();
+
+        flywheel.update(timecode(0).into(), Some(PlaybackDirection::Forward), start);
+
+        assert_eq!(flywheel.poll(start + Duration::from_secs(2)), None);
+        assert_eq!(flywheel.state(), SignalState::Stopped);
+    }
+
+    #[test]
+    fn test_stopping_after_freewheel_returns_to_last_value() {
+        let mut flywheel = Flywheel::new(25);
+        let start = Instant::now();
+
+        flywheel.update(timecode(0).into(), Some(PlaybackDirection::Forward), start);
+        flywheel.poll(start + Duration::from_millis(200));
+        assert_eq!(flywheel.state(), SignalState::Freewheeling);
+
+        assert_eq!(
+            flywheel.poll(start + Duration::from_secs(2)),
+            Some(timecode(0).into()),
+            "The frame the source never played shouldn't be left showing"
+        );
+        assert_eq!(flywheel.state(), SignalState::Stopped);
+
+        assert_eq!(flywheel.poll(start + Duration::from_secs(3)), None);
+    }
+
+    #[test]
+    fn test_parked_source_stops_without_freewheeling() {
+        let mut flywheel = Flywheel::new(25);
+        let start = Instant::now();
+
+        flywheel.update(timecode(0).into(), None, start);
+
+        assert_eq!(flywheel.poll(start + Duration::from_millis(200)), None);
+        assert_eq!(flywheel.state(), SignalState::Stopped);
+    }
+
+    #[test]
+    fn test_lost_after_timeout() {
+        let mut flywheel = Flywheel::new(25);
+        let start = Instant::now();
+
+        flywheel.update(timecode(0).into(), None, start);
+        flywheel.poll(start + Duration::from_secs(1));
+        assert_eq!(flywheel.state(), SignalState::Stopped);
+
+        assert_eq!(
+            flywheel.poll(start + LOST_AFTER + Duration::from_secs(1)),
+            None
+        );
+        assert_eq!(flywheel.state(), SignalState::Lost);
+
+        // Coming back is the same as locking for the first time
+        flywheel.update(timecode(1).into(), None, start + Duration::from_secs(20));
+        assert_eq!(flywheel.state(), SignalState::Locked);
+    }
+}
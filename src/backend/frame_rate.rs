@@ -0,0 +1,199 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// The frame rates that a [`Timecode`](crate::backend::timecode::Timecode) can be counted in.
+///
+/// Fractional rates (23.976, 29.97, 59.94) run at exactly `n * 1000 / 1001` frames per second, the drop-frame
+/// variants skip frame numbers at the start of most minutes so the label stays in step with wall-clock time.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, Serialize, Deserialize,
+)]
+pub enum FrameRate {
+    Fps23_976,
+    Fps24,
+    Fps25,
+    Fps29_97Df,
+    Fps29_97Ndf,
+    #[default]
+    Fps30,
+    Fps48,
+    Fps50,
+    Fps59_94Df,
+    Fps59_94Ndf,
+    Fps60,
+}
+
+impl FrameRate {
+    /// Every supported frame rate, in ascending order. Handy for filling combo boxes
+    pub const ALL: [Self; 11] = [
+        Self::Fps23_976,
+        Self::Fps24,
+        Self::Fps25,
+        Self::Fps29_97Df,
+        Self::Fps29_97Ndf,
+        Self::Fps30,
+        Self::Fps48,
+        Self::Fps50,
+        Self::Fps59_94Df,
+        Self::Fps59_94Ndf,
+        Self::Fps60,
+    ];
+
+    /// The number of frame labels in one second of timecode, E.g: 30 for 29.97 DF.
+    /// This is the value the frames field counts up to, not the real speed of the rate
+    pub fn nominal_fps(self) -> u8 {
+        match self {
+            Self::Fps23_976 | Self::Fps24 => 24,
+            Self::Fps25 => 25,
+            Self::Fps29_97Df | Self::Fps29_97Ndf | Self::Fps30 => 30,
+            Self::Fps48 => 48,
+            Self::Fps50 => 50,
+            Self::Fps59_94Df | Self::Fps59_94Ndf | Self::Fps60 => 60,
+        }
+    }
+
+    /// Returns true if this rate skips frame numbers to stay in line with wall-clock time
+    pub fn is_drop_frame(self) -> bool {
+        matches!(self, Self::Fps29_97Df | Self::Fps59_94Df)
+    }
+
+    /// The drop-frame rate that runs at the same nominal speed as this one, if there is one.
+    /// E.g: 30fps and 29.97fps NDF both give 29.97fps DF
+    pub fn drop_frame_variant(self) -> Option<Self> {
+        match self {
+            Self::Fps29_97Df | Self::Fps29_97Ndf | Self::Fps30 => Some(Self::Fps29_97Df),
+            Self::Fps59_94Df | Self::Fps59_94Ndf | Self::Fps60 => Some(Self::Fps59_94Df),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this rate runs at `nominal * 1000 / 1001` frames per second
+    pub fn is_fractional(self) -> bool {
+        matches!(
+            self,
+            Self::Fps23_976
+                | Self::Fps29_97Df
+                | Self::Fps29_97Ndf
+                | Self::Fps59_94Df
+                | Self::Fps59_94Ndf
+        )
+    }
+
+    /// The exact speed of this rate as a `(numerator, denominator)` pair of frames per second,
+    /// E.g: `(30000, 1001)` for 29.97
+    pub fn as_ratio(self) -> (u32, u32) {
+        let nominal = u32::from(self.nominal_fps());
+        if self.is_fractional() {
+            (nominal * 1000, 1001)
+        } else {
+            (nominal, 1)
+        }
+    }
+
+    /// The speed of this rate in frames per second. Use [`FrameRate::as_ratio`] when the result needs to be exact
+    pub fn as_f64(self) -> f64 {
+        let (numerator, denominator) = self.as_ratio();
+        f64::from(numerator) / f64::from(denominator)
+    }
+
+    /// How many frame numbers are skipped at the start of each minute that isn't a multiple of ten.
+    /// Zero for non drop-frame rates
+    pub fn dropped_frames_per_minute(self) -> u8 {
+        match self {
+            Self::Fps29_97Df => 2,
+            Self::Fps59_94Df => 4,
+            _ => 0,
+        }
+    }
+
+    /// The number of distinct frame labels in one minute of timecode
+    pub fn frames_per_minute(self) -> u32 {
+        u32::from(self.nominal_fps()) * 60 - u32::from(self.dropped_frames_per_minute())
+    }
+
+    /// The number of distinct frame labels in ten minutes of timecode, the cycle after which drop-frame repeats itself
+    pub fn frames_per_ten_minutes(self) -> u32 {
+        u32::from(self.nominal_fps()) * 600 - u32::from(self.dropped_frames_per_minute()) * 9
+    }
+
+    /// The number of distinct frame labels between 00:00:00:00 and 24:00:00:00
+    pub fn frames_per_day(self) -> u32 {
+        self.frames_per_ten_minutes() * 6 * 24
+    }
+}
+
+impl Display for FrameRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Fps23_976 => write!(f, "23.976fps"),
+            Self::Fps24 => write!(f, "24fps"),
+            Self::Fps25 => write!(f, "25fps"),
+            Self::Fps29_97Df => write!(f, "29.97fps DF"),
+            Self::Fps29_97Ndf => write!(f, "29.97fps NDF"),
+            Self::Fps30 => write!(f, "30fps"),
+            Self::Fps48 => write!(f, "48fps"),
+            Self::Fps50 => write!(f, "50fps"),
+            Self::Fps59_94Df => write!(f, "59.94fps DF"),
+            Self::Fps59_94Ndf => write!(f, "59.94fps NDF"),
+            Self::Fps60 => write!(f, "60fps"),
+        }
+    }
+}
+
+impl From<midi_msg::TimeCodeType> for FrameRate {
+    /// MTC can only describe four rates, so 23.976 arrives as 24 and 29.97 NDF arrives as 30.
+    /// The DF30 type is only ever used for 29.97 drop-frame
+    fn from(value: midi_msg::TimeCodeType) -> Self {
+        match value {
+            midi_msg::TimeCodeType::FPS24 => Self::Fps24,
+            midi_msg::TimeCodeType::FPS25 => Self::Fps25,
+            midi_msg::TimeCodeType::DF30 => Self::Fps29_97Df,
+            midi_msg::TimeCodeType::NDF30 => Self::Fps30,
+        }
+    }
+}
+
+impl From<FrameRate> for midi_msg::TimeCodeType {
+    /// The MTC type that counts the same number of frames a second, E.g: 23.976 goes as 24 and 50 goes as 25
+    fn from(value: FrameRate) -> Self {
+        if value.is_drop_frame() {
+            return Self::DF30;
+        }
+
+        match value.nominal_fps() {
+            24 | 48 => Self::FPS24,
+            25 | 50 => Self::FPS25,
+            _ => Self::NDF30,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_frames_per_ten_minutes_29_97_df() {
+        assert_eq!(FrameRate::Fps29_97Df.frames_per_ten_minutes(), 17982);
+    }
+
+    #[test]
+    fn test_frames_per_ten_minutes_59_94_df() {
+        assert_eq!(FrameRate::Fps59_94Df.frames_per_ten_minutes(), 35964);
+    }
+
+    #[test]
+    fn test_ratio_23_976() {
+        assert_eq!(FrameRate::Fps23_976.as_ratio(), (24000, 1001));
+    }
+
+    #[test]
+    fn test_mtc_df30_is_29_97_df() {
+        assert_eq!(
+            FrameRate::from(midi_msg::TimeCodeType::DF30),
+            FrameRate::Fps29_97Df
+        );
+    }
+}
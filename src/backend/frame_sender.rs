@@ -0,0 +1,210 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    timecode_decoder::{PlaybackDirection, TimecodePosition},
+};
+
+/// How often the sending thread checks whether the frame has changed
+const TICK_INTERVAL: Duration = Duration::from_millis(2);
+
+/// How often to send the same packet again while the source is parked, so anything that starts listening late
+/// still sees it
+const REPEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The latest position from the source and the direction it is playing in
+type SourcePosition = (TimecodePosition, Option<PlaybackDirection>);
+
+/// Sends a packet every time the frame changes, counting on by itself between the positions it is given.
+///
+/// Network outputs build on this and only decide what the packets are and where they go
+pub struct FrameSender {
+    /// What the thread is called, E.g: "Art-Net timecode sender"
+    name: &'static str,
+    /// Positions for the sending thread, dropping this stops it
+    position_tx: Option<Sender<SourcePosition>>,
+    last_position: Option<TimecodePosition>,
+    rate: Option<FrameRate>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FrameSender {
+    /// Creates a new, stopped, sender
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            position_tx: None,
+            last_position: None,
+            rate: None,
+            thread: None,
+        }
+    }
+
+    /// Start sending. `packet` works out what to send for a position, and `send` sends it whenever it is different
+    /// to the last one. `rate` is the rate being sent at, None if it follows the source
+    ///
+    /// # Errors
+    /// Fails if the thread could not be started
+    pub fn start<P>(
+        &mut self,
+        rate: Option<FrameRate>,
+        packet: impl Fn(TimecodePosition, Option<PlaybackDirection>) -> P + Send + 'static,
+        send: impl FnMut(&P) + Send + 'static,
+    ) -> Result<()>
+    where
+        P: PartialEq + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name(self.name.to_owned())
+            .spawn(move || transmit(&rx, packet, send))?;
+
+        self.position_tx = Some(tx);
+        self.rate = rate;
+        self.thread = Some(thread);
+
+        Ok(())
+    }
+
+    /// Stops sending
+    ///
+    /// # Errors
+    /// Result will be Err if the sender isn't running
+    pub fn stop(&mut self) -> Result<()> {
+        let thread = self
+            .thread
+            .take()
+            .ok_or_else(|| anyhow!("The sender isn't running, hence you cannot stop it"))?;
+
+        // Hanging up on the thread tells it to finish
+        self.position_tx = None;
+        self.last_position = None;
+        self.rate = None;
+        thread
+            .join()
+            .map_err(|_panic| anyhow!("The {} thread panicked", self.name))?;
+
+        Ok(())
+    }
+
+    /// Returns true if timecode is being sent
+    pub fn running(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    /// The rate timecode is being sent at, None if it follows the source or the sender isn't running
+    pub fn rate(&self) -> Option<FrameRate> {
+        self.rate
+    }
+
+    /// Give the sender the latest position from the source, `direction` is None if the source isn't playing.
+    /// The same position twice is ignored, so it is fine to call this however often the source is read
+    pub fn update(&mut self, position: TimecodePosition, direction: Option<PlaybackDirection>) {
+        if self.last_position == Some(position) {
+            return;
+        }
+        self.last_position = Some(position);
+
+        if let Some(tx) = &self.position_tx
+            && tx.send((position, direction)).is_err()
+        {
+            log::error!("{} thread has stopped unexpectedly", self.name);
+        }
+    }
+}
+
+/// Sends a packet whenever it changes until the sender hangs up, runs on its own thread
+fn transmit<P: PartialEq>(
+    rx: &Receiver<SourcePosition>,
+    packet: impl Fn(TimecodePosition, Option<PlaybackDirection>) -> P,
+    mut send: impl FnMut(&P),
+) {
+    let mut latest = None;
+    let mut last_sent: Option<(P, Instant)> = None;
+
+    loop {
+        match rx.recv_timeout(TICK_INTERVAL) {
+            Ok((position, direction)) => latest = Some((position, direction, Instant::now())),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        let Some((position, direction, received_at)) = latest else {
+            continue;
+        };
+
+        let now = Instant::now();
+        let position = position.extrapolate(direction, now.saturating_duration_since(received_at));
+        let next = packet(position, direction);
+
+        let due = last_sent.as_ref().is_none_or(|(last_packet, sent_at)| {
+            *last_packet != next || now.saturating_duration_since(*sent_at) >= REPEAT_INTERVAL
+        });
+        if !due {
+            continue;
+        }
+
+        send(&next);
+        last_sent = Some((next, now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::timecode::Timecode;
+
+    #[test]
+    fn test_counts_on_while_playing() {
+        let position = TimecodePosition::from(Timecode::new(1, 0, 0, 0, FrameRate::Fps25));
+        let forward = Some(PlaybackDirection::Forward);
+
+        let playing = position.extrapolate(forward, Duration::from_millis(90));
+        assert_eq!(playing.timecode.frames(), 2);
+
+        // Counting on is limited, a source that has gone quiet shouldn't run away
+        let quiet = position.extrapolate(forward, Duration::from_secs(10));
+        assert_eq!(quiet.timecode.frames(), 5);
+
+        let parked = position.extrapolate(None, Duration::from_millis(90));
+        assert_eq!(parked.timecode.frames(), 0);
+    }
+
+    #[test]
+    fn test_sends_on_frame_change() {
+        let (packet_tx, packet_rx) = mpsc::channel();
+
+        let mut sender = FrameSender::new("Test sender");
+        sender
+            .start(
+                None,
+                |position, _| position.timecode,
+                move |timecode| {
+                    packet_tx.send(*timecode).ok();
+                },
+            )
+            .expect("Thread starts");
+
+        let timecode = Timecode::new(1, 0, 0, 0, FrameRate::Fps25);
+        for _ in 0..2 {
+            sender.update(timecode.into(), None);
+        }
+
+        assert_eq!(
+            packet_rx.recv_timeout(Duration::from_secs(1)).ok(),
+            Some(timecode)
+        );
+        // A parked source isn't sent again until the repeat is due
+        assert!(packet_rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        sender.stop().expect("Sender was running");
+        assert!(!sender.running());
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::VecDeque;
+
+use crate::backend::{
+    ltc_frame::{LTC_FRAME_BITS, LtcFrame, REVERSE_SYNC_WORD, SYNC_WORD, reverse_frame_bits},
+    timecode_decoder::PlaybackDirection,
+};
+
+/// How much each new bit counts towards the tracked bit period
+const PERIOD_SMOOTHING: f64 = 0.1;
+
+/// The fraction of the peak level the signal has to swing past zero to count as a transition,
+/// so that noise around zero doesn't
+const HYSTERESIS: f32 = 0.25;
+
+/// Anything quieter than this is treated as silence
+const MIN_LEVEL: f32 = 0.01;
+
+/// How quickly the tracked peak level falls away, per sample
+const ENVELOPE_DECAY: f32 = 0.999;
+
+/// Where to start tracking the bit rate from, 25fps LTC. Everything from 23.976 to 30fps is close
+/// enough to lock straight away, anything else takes a few bits
+const INITIAL_BITS_PER_SECOND: f64 = 2000.0;
+
+/// The slowest bit rate followed, about a tenth of play speed. Any longer gap is a dropout
+const MIN_BITS_PER_SECOND: f64 = 190.0;
+
+/// A frame read by the [`BiphaseDemodulator`]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DemodulatedFrame {
+    pub frame: LtcFrame,
+    pub direction: PlaybackDirection,
+    /// The sample the frame started at, counting from the first sample given to the demodulator
+    pub start_sample: f64,
+    /// How many samples the frame lasted
+    pub length_samples: f64,
+}
+
+/// Turns audio into LTC frames.
+///
+/// LTC is biphase-mark coded, every bit starts with a transition and a 1 has another one half way through.
+/// The length of a bit is tracked as it goes, so the tape can speed up and slow down.
+/// Frames are recognised by their sync word, which tells us which way the tape is playing
+pub struct BiphaseDemodulator {
+    sample_rate: f64,
+    /// How many samples have been processed so far
+    sample_index: u64,
+    previous_sample: f32,
+    /// Whether the signal last crossed above or below zero
+    high: bool,
+    /// The peak level of the signal, falling away over time
+    envelope: f32,
+    /// When the last transition happened, in samples
+    last_transition: Option<f64>,
+    /// The tracked length of one bit, in samples
+    bit_period: f64,
+    /// When the current bit started, if only the first half of a 1 has been seen
+    half_bit_start: Option<f64>,
+    /// The last 80 bits received, the newest at bit 79
+    bits: u128,
+    /// When each of the bits making up the current frame started, oldest first
+    bit_starts: VecDeque<f64>,
+    /// How many times we lost track of the bits part way through a frame
+    bit_errors: u32,
+}
+
+impl BiphaseDemodulator {
+    pub fn new(sample_rate: u32) -> Self {
+        let sample_rate = f64::from(sample_rate);
+
+        Self {
+            sample_rate,
+            sample_index: 0,
+            previous_sample: 0.0,
+            high: false,
+            envelope: 0.0,
+            last_transition: None,
+            bit_period: sample_rate / INITIAL_BITS_PER_SECOND,
+            half_bit_start: None,
+            bits: 0,
+            bit_starts: VecDeque::with_capacity(LTC_FRAME_BITS as usize + 1),
+            bit_errors: 0,
+        }
+    }
+
+    /// How many times we lost track of the bits part way through a frame, E.g: a dropout or a burst of noise
+    pub fn bit_errors(&self) -> u32 {
+        self.bit_errors
+    }
+
+    /// Feeds in the next samples of audio, calling `on_frame` for every complete frame found in them
+    pub fn process(
+        &mut self,
+        samples: impl IntoIterator<Item = f32>,
+        mut on_frame: impl FnMut(DemodulatedFrame),
+    ) {
+        for sample in samples {
+            self.envelope = (self.envelope * ENVELOPE_DECAY).max(sample.abs());
+            let threshold = (self.envelope * HYSTERESIS).max(MIN_LEVEL);
+
+            let crossed = if self.high {
+                sample < -threshold
+            } else {
+                sample > threshold
+            };
+
+            if crossed {
+                // Work out how far between the last sample and this one the threshold was crossed
+                let level = if self.high { -threshold } else { threshold };
+                let fraction =
+                    f64::from((self.previous_sample - level) / (self.previous_sample - sample))
+                        .clamp(0.0, 1.0);
+                let time = self.sample_index as f64 - 1.0 + fraction;
+
+                self.high = !self.high;
+
+                if let Some(frame) = self.transition(time) {
+                    on_frame(frame);
+                }
+            }
+
+            self.previous_sample = sample;
+            self.sample_index += 1;
+        }
+    }
+
+    /// Handles the signal changing level at `time`, returning a frame if this completed one
+    fn transition(&mut self, time: f64) -> Option<DemodulatedFrame> {
+        let last = self.last_transition.replace(time)?;
+        let interval = time - last;
+
+        if interval > self.sample_rate / MIN_BITS_PER_SECOND {
+            // Silence, or the tape stopped
+            self.lose_sync();
+            None
+        } else if interval >= self.bit_period * 1.5 || interval < self.bit_period * 0.3 {
+            // Far from what we were expecting, the tape has jumped in speed. Start again from this bit
+            self.lose_sync();
+            self.bit_period = interval;
+            None
+        } else if interval < self.bit_period * 0.75 {
+            // Half of a 1
+            self.bit_period += (interval * 2.0 - self.bit_period) * PERIOD_SMOOTHING;
+
+            if let Some(start) = self.half_bit_start.take() {
+                self.push_bit(true, start, time)
+            } else {
+                self.half_bit_start = Some(last);
+                None
+            }
+        } else {
+            // A whole 0
+            self.bit_period += (interval - self.bit_period) * PERIOD_SMOOTHING;
+
+            // If the second half of a 1 never came we have been out of step with the bits,
+            // this transition is the first one we can trust
+            if self.half_bit_start.take().is_some() {
+                self.lose_sync();
+                return None;
+            }
+
+            self.push_bit(false, last, time)
+        }
+    }
+
+    /// Adds a bit that ran from `start` to `end`, returning a frame if it completed one
+    fn push_bit(&mut self, bit: bool, start: f64, end: f64) -> Option<DemodulatedFrame> {
+        self.bits = (self.bits >> 1) | (u128::from(bit) << (LTC_FRAME_BITS - 1));
+
+        self.bit_starts.push_back(start);
+        if self.bit_starts.len() > LTC_FRAME_BITS as usize {
+            self.bit_starts.pop_front();
+        }
+        if self.bit_starts.len() < LTC_FRAME_BITS as usize {
+            return None;
+        }
+
+        // Going forwards the sync word comes last, going backwards it comes first and reversed
+        let (bits, direction) = if (self.bits >> 64) as u16 == SYNC_WORD {
+            (self.bits, PlaybackDirection::Forward)
+        } else if self.bits as u16 == REVERSE_SYNC_WORD {
+            (reverse_frame_bits(self.bits), PlaybackDirection::Reverse)
+        } else {
+            return None;
+        };
+
+        let frame = LtcFrame::from_bits(bits)?;
+        let start_sample = self.bit_starts.front().copied()?;
+
+        // The next frame is made of the next 80 bits
+        self.bit_starts.clear();
+
+        Some(DemodulatedFrame {
+            frame,
+            direction,
+            start_sample,
+            length_samples: end - start_sample,
+        })
+    }
+
+    /// Throw away the bits of the current frame
+    fn lose_sync(&mut self) {
+        if !self.bit_starts.is_empty() || self.half_bit_start.is_some() {
+            self.bit_errors += 1;
+        }
+
+        self.half_bit_start = None;
+        self.bit_starts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{
+        frame_rate::FrameRate,
+        ltc_decoder::test_signal::{frames, modulate},
+        timecode::Timecode,
+    };
+
+    fn four_frames() -> Vec<LtcFrame> {
+        frames(Timecode::new(10, 20, 30, 0, FrameRate::Fps25), 4)
+    }
+
+    fn demodulate(sample_rate: u32, samples: Vec<f32>) -> Vec<DemodulatedFrame> {
+        let mut demodulator = BiphaseDemodulator::new(sample_rate);
+        let mut decoded = Vec::new();
+
+        demodulator.process(samples, |frame| decoded.push(frame));
+
+        decoded
+    }
+
+    #[test]
+    fn test_forward() {
+        let frames = four_frames();
+        // 25fps at 48kHz
+        let decoded = demodulate(48_000, modulate(&frames, 24.0, false));
+
+        // The first frame is missing its first transition and the last one has no transition to end it
+        assert_eq!(
+            decoded.iter().map(|frame| frame.frame).collect::<Vec<_>>(),
+            frames.get(1..3).expect("4 frames").to_vec()
+        );
+        assert!(
+            decoded
+                .iter()
+                .all(|frame| frame.direction == PlaybackDirection::Forward),
+            "Every frame should be forwards"
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        let frames = four_frames();
+        let decoded = demodulate(48_000, modulate(&frames, 24.0, true));
+
+        assert_eq!(
+            decoded.iter().map(|frame| frame.frame).collect::<Vec<_>>(),
+            frames
+                .get(1..3)
+                .expect("4 frames")
+                .iter()
+                .rev()
+                .copied()
+                .collect::<Vec<_>>()
+        );
+        assert!(
+            decoded
+                .iter()
+                .all(|frame| frame.direction == PlaybackDirection::Reverse),
+            "Every frame should be backwards"
+        );
+    }
+
+    #[test]
+    fn test_frame_position() {
+        let frames = four_frames();
+        // 30fps at 44.1kHz
+        let decoded = demodulate(44_100, modulate(&frames, 18.375, false));
+
+        let first = decoded.first().expect("A frame should be decoded");
+        assert!(
+            (first.start_sample - 80.0 * 18.375).abs() < 1.0,
+            "The second frame starts 80 bits in"
+        );
+        assert!(
+            (first.length_samples - 80.0 * 18.375).abs() < 1.0,
+            "Frames are 80 bits long"
+        );
+    }
+
+    #[test]
+    fn test_tracks_slow_tape() {
+        let frames = four_frames();
+        // Nearly half speed, far from where the bit rate starts
+        let decoded = demodulate(48_000, modulate(&frames, 44.0, false));
+
+        assert_eq!(decoded.len(), 2);
+    }
+
+    #[test]
+    fn test_dropout_is_an_error() {
+        let frames = four_frames();
+        let mut samples = modulate(&frames, 24.0, false);
+
+        // Silence half way through the third frame
+        for sample in samples.get_mut(4000..4500).expect("Enough samples") {
+            *sample = 0.0;
+        }
+
+        let mut demodulator = BiphaseDemodulator::new(48_000);
+        let mut decoded = Vec::new();
+        demodulator.process(samples, |frame| decoded.push(frame.frame));
+
+        assert_eq!(decoded, frames.get(1..2).expect("4 frames").to_vec());
+        assert!(
+            demodulator.bit_errors() > 0,
+            "The dropout should be counted"
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    ltc_decoder::{
+        RateDetector,
+        biphase_demodulator::{BiphaseDemodulator, DemodulatedFrame},
+    },
+    timecode::Timecode,
+    timecode_decoder::PlaybackDirection,
+};
+
+/// A frame of LTC found in a recording
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RecordedFrame {
+    pub timecode: Timecode,
+    /// UB1 in the lowest 4 bits up to UB8 in the highest
+    pub user_bits: u32,
+    pub direction: PlaybackDirection,
+    /// The sample the frame starts at
+    pub sample_offset: u64,
+    pub length_samples: u64,
+}
+
+/// A place where the timecode doesn't follow on from the frame before, E.g: an edit or a restarted generator
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Discontinuity {
+    /// The sample the unexpected frame starts at
+    pub sample_offset: u64,
+    /// The timecode that should have been there, allowing for any frames lost to a dropout
+    pub expected: Timecode,
+    pub found: Timecode,
+}
+
+/// A stretch of a recording with no readable timecode between two frames
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Dropout {
+    /// The sample the last good frame before the dropout ends at
+    pub sample_offset: u64,
+    pub length_samples: u64,
+}
+
+/// Everything found in the LTC track of a recording, for checking that it is clean
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LtcFileAnalysis {
+    pub sample_rate: u32,
+    /// The frame rate of the LTC, None if no frames were found
+    pub rate: Option<FrameRate>,
+    pub frames: Vec<RecordedFrame>,
+    pub discontinuities: Vec<Discontinuity>,
+    pub dropouts: Vec<Dropout>,
+}
+
+impl LtcFileAnalysis {
+    /// Reads the LTC from one channel of a WAV (including BWF) or FLAC file, counting from 0.
+    ///
+    /// # Errors
+    /// Fails if the file can't be read, isn't a WAV or FLAC file or doesn't have the channel
+    pub fn from_file(path: &Path, channel: usize) -> Result<Self> {
+        let is_flac = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("flac"));
+
+        if is_flac {
+            Self::from_flac(path, channel)
+        } else {
+            Self::from_wav(path, channel)
+        }
+    }
+
+    /// Decodes the LTC in the samples of one channel of audio
+    pub fn from_samples(samples: impl IntoIterator<Item = f32>, sample_rate: u32) -> Self {
+        let mut demodulator = BiphaseDemodulator::new(sample_rate);
+        let mut demodulated = Vec::new();
+
+        demodulator.process(samples, |frame| demodulated.push(frame));
+
+        Self::from_demodulated(&demodulated, sample_rate)
+    }
+
+    fn from_wav(path: &Path, channel: usize) -> Result<Self> {
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let channels = check_channel(usize::from(spec.channels), channel)?;
+
+        let mut read_error = None;
+        let analysis = match spec.sample_format {
+            hound::SampleFormat::Float => Self::from_samples(
+                channel_samples(reader.samples::<f32>(), channels, channel, &mut read_error),
+                spec.sample_rate,
+            ),
+            hound::SampleFormat::Int => {
+                let scale = int_sample_scale(u32::from(spec.bits_per_sample));
+                Self::from_samples(
+                    channel_samples(reader.samples::<i32>(), channels, channel, &mut read_error)
+                        .map(|sample| sample as f32 * scale),
+                    spec.sample_rate,
+                )
+            }
+        };
+
+        match read_error {
+            Some(e) => Err(anyhow!("Failed to read {}: {e}", path.display())),
+            None => Ok(analysis),
+        }
+    }
+
+    fn from_flac(path: &Path, channel: usize) -> Result<Self> {
+        let mut reader = claxon::FlacReader::open(path)?;
+        let info = reader.streaminfo();
+        let channels = check_channel(info.channels as usize, channel)?;
+        let scale = int_sample_scale(info.bits_per_sample);
+
+        let mut read_error = None;
+        let analysis = Self::from_samples(
+            channel_samples(reader.samples(), channels, channel, &mut read_error)
+                .map(|sample| sample as f32 * scale),
+            info.sample_rate,
+        );
+
+        match read_error {
+            Some(e) => Err(anyhow!("Failed to read {}: {e}", path.display())),
+            None => Ok(analysis),
+        }
+    }
+
+    /// Works out the rate from the whole recording, then looks for anything out of place
+    fn from_demodulated(demodulated: &[DemodulatedFrame], sample_rate: u32) -> Self {
+        let mut lengths: Vec<f64> = demodulated
+            .iter()
+            .map(|frame| frame.length_samples)
+            .collect();
+        lengths.sort_by(f64::total_cmp);
+
+        let Some(median_length) = lengths.get(lengths.len() / 2).copied() else {
+            return Self {
+                sample_rate,
+                ..Default::default()
+            };
+        };
+
+        let drop_frames = demodulated
+            .iter()
+            .filter(|frame| frame.frame.drop_frame)
+            .count();
+        let rate = RateDetector::default().update(
+            median_length / f64::from(sample_rate),
+            drop_frames * 2 > demodulated.len(),
+        );
+
+        let frames: Vec<RecordedFrame> = demodulated
+            .iter()
+            .map(|frame| RecordedFrame {
+                timecode: frame.frame.timecode(rate),
+                user_bits: frame.frame.user_bits,
+                direction: frame.direction,
+                sample_offset: frame.start_sample.round().max(0.0) as u64,
+                length_samples: frame.length_samples.round() as u64,
+            })
+            .collect();
+
+        let mut discontinuities = Vec::new();
+        let mut dropouts = Vec::new();
+
+        for pair in frames.windows(2) {
+            let [previous, next] = pair else {
+                continue;
+            };
+            let previous_end = previous.sample_offset + previous.length_samples;
+
+            // Anything more than half a frame between two frames means some have been lost
+            let gap = next.sample_offset.saturating_sub(previous_end);
+            if gap as f64 > median_length / 2.0 {
+                dropouts.push(Dropout {
+                    sample_offset: previous_end,
+                    length_samples: gap,
+                });
+            }
+
+            // Turning around isn't a discontinuity, the tape just changed direction
+            if previous.direction != next.direction {
+                continue;
+            }
+
+            let frames_on = ((next.sample_offset - previous.sample_offset) as f64 / median_length)
+                .round() as u32;
+            let expected = match next.direction {
+                PlaybackDirection::Forward => previous.timecode + frames_on,
+                PlaybackDirection::Reverse => previous.timecode - frames_on,
+            };
+
+            if next.timecode != expected {
+                discontinuities.push(Discontinuity {
+                    sample_offset: next.sample_offset,
+                    expected,
+                    found: next.timecode,
+                });
+            }
+        }
+
+        Self {
+            sample_rate,
+            rate: Some(rate),
+            frames,
+            discontinuities,
+            dropouts,
+        }
+    }
+}
+
+/// Returns the number of channels, or an error if `channel` isn't one of them
+fn check_channel(channels: usize, channel: usize) -> Result<usize> {
+    if channel < channels {
+        Ok(channels)
+    } else {
+        Err(anyhow!("The file only has {channels} channels"))
+    }
+}
+
+/// What to multiply an integer sample by to get it between -1.0 and 1.0
+fn int_sample_scale(bits_per_sample: u32) -> f32 {
+    1.0 / (1_u64 << bits_per_sample.saturating_sub(1)) as f32
+}
+
+/// Picks one channel out of interleaved samples, stopping at the first error and putting it in `error`
+fn channel_samples<'a, T, E>(
+    samples: impl Iterator<Item = Result<T, E>> + 'a,
+    channels: usize,
+    channel: usize,
+    error: &'a mut Option<E>,
+) -> impl Iterator<Item = T> + 'a {
+    samples
+        .map_while(|sample| match sample {
+            Ok(sample) => Some(sample),
+            Err(e) => {
+                *error = Some(e);
+                None
+            }
+        })
+        .skip(channel)
+        .step_by(channels)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::ltc_decoder::test_signal::{frames, modulate};
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// 25fps at 48kHz
+    const SAMPLES_PER_BIT: f64 = 24.0;
+
+    fn analyse(samples: Vec<f32>) -> LtcFileAnalysis {
+        LtcFileAnalysis::from_samples(samples, SAMPLE_RATE)
+    }
+
+    #[test]
+    fn test_clean_recording() {
+        let first = Timecode::new(1, 0, 0, 0, FrameRate::Fps25);
+        let analysis = analyse(modulate(&frames(first, 30), SAMPLES_PER_BIT, false));
+
+        assert_eq!(analysis.rate, Some(FrameRate::Fps25));
+        // The first and last frames are cut off
+        assert_eq!(analysis.frames.len(), 28);
+        assert_eq!(
+            analysis.frames.first().map(|frame| frame.timecode),
+            Some(first + 1)
+        );
+        assert!(
+            analysis
+                .frames
+                .get(1)
+                .is_some_and(|frame| frame.sample_offset.abs_diff(2 * 80 * 24) <= 1),
+            "The third frame starts 160 bits in"
+        );
+        assert_eq!(analysis.discontinuities, vec![]);
+        assert_eq!(analysis.dropouts, vec![]);
+    }
+
+    #[test]
+    fn test_drop_frame_recording() {
+        let first = Timecode::new(1, 0, 59, 20, FrameRate::Fps29_97Df);
+        // 29.97fps at 48kHz
+        let analysis = analyse(modulate(
+            &frames(first, 20),
+            48_000.0 * 1001.0 / 30_000.0 / 80.0,
+            false,
+        ));
+
+        assert_eq!(analysis.rate, Some(FrameRate::Fps29_97Df));
+        assert_eq!(analysis.discontinuities, vec![]);
+    }
+
+    #[test]
+    fn test_discontinuity() {
+        let before = frames(Timecode::new(1, 0, 0, 0, FrameRate::Fps25), 10);
+        let after = frames(Timecode::new(2, 0, 0, 0, FrameRate::Fps25), 10);
+        let analysis = analyse(modulate(&[before, after].concat(), SAMPLES_PER_BIT, false));
+
+        let [discontinuity] = analysis.discontinuities.as_slice() else {
+            panic!(
+                "Expected one discontinuity, found {:?}",
+                analysis.discontinuities
+            );
+        };
+        assert_eq!(
+            discontinuity.expected,
+            Timecode::new(1, 0, 0, 10, FrameRate::Fps25)
+        );
+        assert_eq!(
+            discontinuity.found,
+            Timecode::new(2, 0, 0, 0, FrameRate::Fps25)
+        );
+        assert!(
+            discontinuity.sample_offset.abs_diff(10 * 80 * 24) <= 1,
+            "The jump is 10 frames in"
+        );
+    }
+
+    #[test]
+    fn test_dropout_without_discontinuity() {
+        let first = Timecode::new(1, 0, 0, 0, FrameRate::Fps25);
+        let mut samples = modulate(&frames(first, 20), SAMPLES_PER_BIT, false);
+
+        // Lose frames 5 to 7 to silence
+        for sample in samples
+            .get_mut(5 * 80 * 24..8 * 80 * 24)
+            .expect("Enough samples")
+        {
+            *sample = 0.0;
+        }
+
+        let analysis = analyse(samples);
+
+        assert_eq!(analysis.dropouts.len(), 1);
+        assert_eq!(analysis.discontinuities, vec![]);
+    }
+
+    #[test]
+    fn test_silence() {
+        let analysis = analyse(vec![0.0; 48_000]);
+
+        assert_eq!(
+            analysis,
+            LtcFileAnalysis {
+                sample_rate: SAMPLE_RATE,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    sync::mpsc::{self, Receiver, SyncSender},
+    time::Instant,
+};
+
+use anyhow::{Result, anyhow};
+use cpal::{
+    Device, FromSample, Host, SampleFormat, SizedSample, Stream, StreamConfig,
+    traits::{DeviceTrait as _, HostTrait as _, StreamTrait as _},
+};
+
+use crate::backend::{
+    flywheel::Flywheel,
+    frame_rate::FrameRate,
+    ltc_decoder::biphase_demodulator::{BiphaseDemodulator, DemodulatedFrame},
+    ltc_frame::LTC_RATES,
+    timecode_decoder::{
+        ConnectionState, DecoderStatus, ErrorCounters, PlaybackDirection, TimecodeDecoder,
+        TimecodePosition,
+    },
+};
+
+mod biphase_demodulator;
+pub mod file_analysis;
+#[cfg(test)]
+mod test_signal;
+
+/// How close the measured rate has to be to one of [`LTC_RATES`] to switch to it, as a fraction.
+/// Further away than this and the tape is being varispeeded, so stick with the rate we had
+const RATE_TOLERANCE: f64 = 0.005;
+
+/// How much each new frame counts towards the measured rate
+const RATE_SMOOTHING: f64 = 0.1;
+
+/// How many events the audio thread can queue up before they are dropped. The queue is allocated up front
+/// so the audio callback never has to, this is a few seconds of frames even if the engine stalls
+const EVENT_QUEUE_LENGTH: usize = 256;
+
+/// What the audio thread tells the decoder about
+enum LtcEvent {
+    Frame {
+        position: TimecodePosition,
+        direction: PlaybackDirection,
+        measured_fps: f64,
+        user_bits: u32,
+    },
+    /// The bits stopped making sense part way through a frame
+    Dropout,
+}
+
+/// Works out the frame rate of LTC from how long its frames last, as LTC only carries the drop-frame flag
+#[derive(Default)]
+pub struct RateDetector {
+    measured_fps: Option<f64>,
+    rate: Option<FrameRate>,
+}
+
+impl RateDetector {
+    /// Takes the length of the latest frame in seconds and returns the rate the LTC is running at
+    pub fn update(&mut self, frame_seconds: f64, drop_frame: bool) -> FrameRate {
+        let fps = 1.0 / frame_seconds;
+        let measured_fps = match self.measured_fps {
+            Some(smoothed) => smoothed + (fps - smoothed) * RATE_SMOOTHING,
+            None => fps,
+        };
+        self.measured_fps = Some(measured_fps);
+
+        let candidates = LTC_RATES
+            .into_iter()
+            .filter(|rate| rate.is_drop_frame() == drop_frame);
+        let distance = |rate: &FrameRate| (rate.as_f64() - measured_fps).abs() / rate.as_f64();
+        let nearest = candidates
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or_default();
+
+        // Only change rate if we're sure, or the drop-frame flag says we have to
+        let rate = match self.rate {
+            Some(rate)
+                if rate.is_drop_frame() == drop_frame && distance(&nearest) > RATE_TOLERANCE =>
+            {
+                rate
+            }
+            _ => nearest,
+        };
+        self.rate = Some(rate);
+
+        rate
+    }
+
+    /// The smoothed rate frames have been arriving at, None until the first frame
+    pub fn measured_fps(&self) -> Option<f64> {
+        self.measured_fps
+    }
+}
+
+/// Reads SMPTE linear timecode from an audio input
+pub struct LtcTimecodeDecoder {
+    /// The name of the audio input to read from
+    pub device_name: Option<String>,
+    /// Which channel of the input carries the timecode, counting from 0
+    pub channel: usize,
+    /// Keeps the timecode counting if the LTC drops out
+    pub flywheel: Flywheel,
+    /// The open audio input, None when disconnected
+    stream: Option<Stream>,
+    /// Frames and errors from the audio thread
+    timecode_rx: Option<Receiver<LtcEvent>>,
+    /// Everything about the signal that isn't tracked by the flywheel
+    status: DecoderStatus,
+    user_bits: Option<u32>,
+    host: Host,
+}
+
+impl LtcTimecodeDecoder {
+    /// Creates a new, disconnected, LTC decoder using the system's default audio backend
+    pub fn new() -> Self {
+        Self {
+            device_name: None,
+            channel: 0,
+            flywheel: Flywheel::default(),
+            stream: None,
+            timecode_rx: None,
+            status: DecoderStatus::default(),
+            user_bits: None,
+            host: cpal::default_host(),
+        }
+    }
+
+    /// Gets the names of the audio inputs on the system. This is an I/O operation so don't spam it
+    pub fn get_devices(&self) -> Vec<String> {
+        self.host
+            .input_devices()
+            .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Start reading LTC from the selected audio input.
+    ///
+    /// # Errors
+    /// Fails if no input has been selected, it has gone away, it doesn't have the selected channel
+    /// or the audio stream could not be started
+    pub fn connect(&mut self) -> Result<()> {
+        let device_name = self
+            .device_name
+            .as_ref()
+            .ok_or_else(|| anyhow!("No audio input specified, cannot open connection"))?;
+
+        let device = self
+            .host
+            .input_devices()?
+            .find(|device| device.name().ok().as_ref() == Some(device_name))
+            .ok_or_else(|| anyhow!("Audio input {device_name} is no longer available"))?;
+
+        let supported_config = device.default_input_config()?;
+        let config = supported_config.config();
+
+        if self.channel >= usize::from(config.channels) {
+            return Err(anyhow!(
+                "{device_name} only has {} channels",
+                config.channels
+            ));
+        }
+
+        // Create a channel to send the frames from the audio thread back to our function to get current timecode
+        let (tx, rx) = mpsc::sync_channel(EVENT_QUEUE_LENGTH);
+
+        let stream = match supported_config.sample_format() {
+            SampleFormat::I8 => build_stream::<i8>(&device, &config, self.channel, tx),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, self.channel, tx),
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, self.channel, tx),
+            SampleFormat::U8 => build_stream::<u8>(&device, &config, self.channel, tx),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, self.channel, tx),
+            SampleFormat::U32 => build_stream::<u32>(&device, &config, self.channel, tx),
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, self.channel, tx),
+            SampleFormat::F64 => build_stream::<f64>(&device, &config, self.channel, tx),
+            format => Err(anyhow!("Unsupported audio sample format {format}")),
+        }?;
+        stream.play()?;
+
+        self.stream = Some(stream);
+        self.timecode_rx = Some(rx);
+        self.status.errors = ErrorCounters::default();
+
+        Ok(())
+    }
+
+    /// Stops reading from the audio input
+    ///
+    /// # Errors
+    /// Result will be Err if there is no connection currently open
+    pub fn disconnect(&mut self) -> Result<()> {
+        let stream = self
+            .stream
+            .take()
+            .ok_or_else(|| anyhow!("No connection is open, hence you cannot close it"))?;
+
+        // Dropping the stream stops it
+        drop(stream);
+
+        self.timecode_rx = None;
+        self.status.direction = None;
+        self.status.measured_fps = None;
+        self.user_bits = None;
+        self.flywheel.reset();
+
+        Ok(())
+    }
+
+    /// Returns true if there has been a connection made.
+    pub fn connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// The user bits of the last frame, UB1 in the lowest 4 bits up to UB8 in the highest
+    pub fn user_bits(&self) -> Option<u32> {
+        self.user_bits
+    }
+}
+
+impl Default for LtcTimecodeDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Opens an audio input stream that decodes LTC from one of its channels
+fn build_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    channel: usize,
+    tx: SyncSender<LtcEvent>,
+) -> Result<Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = usize::from(config.channels);
+    let sample_rate = config.sample_rate.0;
+
+    let mut demodulator = BiphaseDemodulator::new(sample_rate);
+    let mut rate_detector = RateDetector::default();
+
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            let send = |event| {
+                tx.try_send(event).ok();
+            };
+
+            // Samples are interleaved, so only take the ones from our channel
+            let samples = data
+                .chunks(channels)
+                .filter_map(|frame| frame.get(channel))
+                .map(|sample| sample.to_sample::<f32>());
+
+            let bit_errors = demodulator.bit_errors();
+
+            demodulator.process(samples, |demodulated| {
+                let DemodulatedFrame {
+                    frame,
+                    direction,
+                    length_samples,
+                    ..
+                } = demodulated;
+
+                let rate =
+                    rate_detector.update(length_samples / f64::from(sample_rate), frame.drop_frame);
+
+                // A frame is labelled with the time it starts, so once all of it has been read
+                // going forwards we are at the start of the next one
+                let timecode = frame.timecode(rate);
+                let timecode = match direction {
+                    PlaybackDirection::Forward => timecode + 1,
+                    PlaybackDirection::Reverse => timecode,
+                };
+
+                send(LtcEvent::Frame {
+                    position: timecode.into(),
+                    direction,
+                    measured_fps: rate_detector
+                        .measured_fps()
+                        .unwrap_or_else(|| rate.as_f64()),
+                    user_bits: frame.user_bits,
+                });
+            });
+
+            for _ in bit_errors..demodulator.bit_errors() {
+                send(LtcEvent::Dropout);
+            }
+        },
+        |error| log::error!("LTC audio input failed: {error}"),
+        None,
+    )?;
+
+    Ok(stream)
+}
+
+impl TimecodeDecoder for LtcTimecodeDecoder {
+    /// Gets the current position from LTC. If no new value is available then it returns None,
+    /// unless the flywheel is counting on over a dropout
+    fn get_current_position(&mut self) -> Option<TimecodePosition> {
+        let rx = self.timecode_rx.as_ref()?;
+        let now = Instant::now();
+
+        let mut latest = None;
+        for event in rx.try_iter() {
+            match event {
+                LtcEvent::Frame {
+                    position,
+                    direction,
+                    measured_fps,
+                    user_bits,
+                } => {
+                    latest = Some((position, direction));
+                    self.status.measured_fps = Some(measured_fps);
+                    self.user_bits = Some(user_bits);
+                }
+                LtcEvent::Dropout => self.status.errors.dropouts += 1,
+            }
+        }
+
+        let Some((position, direction)) = latest else {
+            return self.flywheel.poll(now);
+        };
+
+        self.status.direction = Some(direction);
+        self.status.rate = Some(position.timecode.rate());
+        self.flywheel.update(position, Some(direction), now);
+
+        Some(position)
+    }
+
+    fn status(&self) -> DecoderStatus {
+        DecoderStatus {
+            connection: if self.connected() {
+                ConnectionState::Connected
+            } else {
+                ConnectionState::Disconnected
+            },
+            signal: self.flywheel.state(),
+            last_received: self.flywheel.last_received(),
+            ..self.status
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn detect(fps: f64, drop_frame: bool, frames: usize) -> FrameRate {
+        let mut rate_detector = RateDetector::default();
+
+        (0..frames)
+            .map(|_| rate_detector.update(1.0 / fps, drop_frame))
+            .last()
+            .expect("At least one frame")
+    }
+
+    #[test]
+    fn test_detects_25fps() {
+        assert_eq!(detect(25.0, false, 10), FrameRate::Fps25);
+    }
+
+    #[test]
+    fn test_tells_29_97_from_30() {
+        assert_eq!(detect(30.0, false, 10), FrameRate::Fps30);
+        assert_eq!(detect(30_000.0 / 1001.0, false, 10), FrameRate::Fps29_97Ndf);
+    }
+
+    #[test]
+    fn test_drop_frame_flag() {
+        assert_eq!(detect(30_000.0 / 1001.0, true, 10), FrameRate::Fps29_97Df);
+    }
+
+    #[test]
+    fn test_varispeed_keeps_rate() {
+        let mut rate_detector = RateDetector::default();
+
+        for _ in 0..10 {
+            rate_detector.update(1.0 / 25.0, false);
+        }
+
+        // Speeding up by 10% would look like 27.5fps
+        assert_eq!(rate_detector.update(1.0 / 27.5, false), FrameRate::Fps25);
+    }
+}
@@ -0,0 +1,51 @@
+//! Made up LTC signals, so the decoders can be tested without any audio hardware
+
+use crate::backend::{
+    ltc_frame::{LTC_FRAME_BITS, LtcFrame},
+    timecode::Timecode,
+};
+
+/// `count` frames following on from `first`, all with the user bits `0x1234_5678`
+pub fn frames(first: Timecode, count: u32) -> Vec<LtcFrame> {
+    (0..count)
+        .map(|frame| LtcFrame {
+            user_bits: 0x1234_5678,
+            ..LtcFrame::from(first + frame)
+        })
+        .collect()
+}
+
+/// Biphase-mark codes the frames into a square wave, `samples_per_bit` long for each bit
+pub fn modulate(frames: &[LtcFrame], samples_per_bit: f64, reverse: bool) -> Vec<f32> {
+    let mut bits: Vec<bool> = frames
+        .iter()
+        .flat_map(|frame| {
+            let bits = frame.to_bits();
+            (0..LTC_FRAME_BITS).map(move |bit| (bits >> bit) & 1 == 1)
+        })
+        .collect();
+    if reverse {
+        bits.reverse();
+    }
+
+    let mut samples = Vec::new();
+    let mut level = 0.5;
+    let mut end = 0.0;
+    for bit in bits {
+        let half_way = end + samples_per_bit / 2.0;
+        end += samples_per_bit;
+
+        level = -level;
+        while (samples.len() as f64) < half_way {
+            samples.push(level);
+        }
+        if bit {
+            level = -level;
+        }
+        while (samples.len() as f64) < end {
+            samples.push(level);
+        }
+    }
+
+    samples
+}
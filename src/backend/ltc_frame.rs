@@ -0,0 +1,235 @@
+use crate::backend::{frame_rate::FrameRate, timecode::Timecode};
+
+/// The number of bits in one LTC frame, including the sync word
+pub const LTC_FRAME_BITS: u32 = 80;
+
+/// The rates LTC can run at, anything faster doesn't fit 80 bits a frame down an audio cable
+pub const LTC_RATES: [FrameRate; 6] = [
+    FrameRate::Fps23_976,
+    FrameRate::Fps24,
+    FrameRate::Fps25,
+    FrameRate::Fps29_97Df,
+    FrameRate::Fps29_97Ndf,
+    FrameRate::Fps30,
+];
+
+/// The 16 bit sync word at the end of every frame, bit 64 first (`0011 1111 1111 1101`)
+pub const SYNC_WORD: u16 = 0xBFFC;
+
+/// The sync word as it arrives when the tape is playing backwards, bit 79 first
+pub const REVERSE_SYNC_WORD: u16 = SYNC_WORD.reverse_bits();
+
+/// Where each 4 bit group of user bits sits in the frame, UB1 first
+const USER_BIT_GROUPS: [u32; 8] = [4, 12, 20, 28, 36, 44, 52, 60];
+
+/// One frame of SMPTE 12M linear timecode.
+///
+/// LTC doesn't say what rate it is running at beyond the drop-frame flag, that has to be worked
+/// out from how quickly the frames arrive
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LtcFrame {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+    pub drop_frame: bool,
+    pub colour_frame: bool,
+    /// The 32 user bits, UB1 in the lowest 4 bits up to UB8 in the highest
+    pub user_bits: u32,
+}
+
+impl LtcFrame {
+    /// Reads a frame from its 80 bits, bit 0 being the first sent when playing forwards.
+    /// Returns None if the sync word is missing or any of the digits are out of range
+    pub fn from_bits(bits: u128) -> Option<Self> {
+        if field(bits, 64, 16) != u32::from(SYNC_WORD) {
+            return None;
+        }
+
+        let frame = Self {
+            hours: bcd(bits, 48, 56, 2)?,
+            minutes: bcd(bits, 32, 40, 3)?,
+            seconds: bcd(bits, 16, 24, 3)?,
+            frames: bcd(bits, 0, 8, 2)?,
+            drop_frame: field(bits, 10, 1) == 1,
+            colour_frame: field(bits, 11, 1) == 1,
+            user_bits: USER_BIT_GROUPS
+                .iter()
+                .enumerate()
+                .map(|(group, start)| field(bits, *start, 4) << (group * 4))
+                .sum(),
+        };
+
+        (frame.hours < 24 && frame.minutes < 60 && frame.seconds < 60).then_some(frame)
+    }
+
+    /// Writes the frame out as 80 bits, bit 0 being the first sent when playing forwards
+    pub fn to_bits(&self) -> u128 {
+        let digits = [
+            (0, self.frames % 10),
+            (8, self.frames / 10),
+            (16, self.seconds % 10),
+            (24, self.seconds / 10),
+            (32, self.minutes % 10),
+            (40, self.minutes / 10),
+            (48, self.hours % 10),
+            (56, self.hours / 10),
+        ];
+
+        let mut bits = u128::from(SYNC_WORD) << 64;
+        for (start, digit) in digits {
+            bits |= u128::from(digit) << start;
+        }
+        for (group, start) in USER_BIT_GROUPS.iter().enumerate() {
+            bits |= u128::from((self.user_bits >> (group * 4)) & 0xF) << start;
+        }
+        bits |= u128::from(self.drop_frame) << 10;
+        bits |= u128::from(self.colour_frame) << 11;
+
+        bits
+    }
+
+    /// The timecode this frame carries, at a rate worked out by the caller
+    pub fn timecode(&self, rate: FrameRate) -> Timecode {
+        Timecode::new(self.hours, self.minutes, self.seconds, self.frames, rate)
+    }
+}
+
+impl From<Timecode> for LtcFrame {
+    fn from(timecode: Timecode) -> Self {
+        Self {
+            hours: timecode.hours(),
+            minutes: timecode.minutes(),
+            seconds: timecode.seconds(),
+            frames: timecode.frames(),
+            drop_frame: timecode.rate().is_drop_frame(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Reads `len` bits starting at `start`, lowest bit first
+fn field(bits: u128, start: u32, len: u32) -> u32 {
+    ((bits >> start) & ((1 << len) - 1)) as u32
+}
+
+/// Reads a binary coded decimal number split into 4 bits of units and `tens_len` bits of tens
+fn bcd(bits: u128, units_start: u32, tens_start: u32, tens_len: u32) -> Option<u8> {
+    let units = field(bits, units_start, 4);
+    let tens = field(bits, tens_start, tens_len);
+
+    (units < 10).then(|| (tens * 10 + units) as u8)
+}
+
+/// Reverses the order of the lowest 80 bits, turning a frame read backwards into one read forwards
+pub fn reverse_frame_bits(bits: u128) -> u128 {
+    bits.reverse_bits() >> (128 - LTC_FRAME_BITS)
+}
+
+/// Sets the polarity correction bit so the frame has an even number of 0s, which means every frame
+/// starts on the same edge. It is bit 59 at 25fps and bit 27 at every other rate
+pub fn correct_polarity(bits: u128, rate: FrameRate) -> u128 {
+    let polarity_bit = if rate == FrameRate::Fps25 { 59 } else { 27 };
+    let bits = bits & !(1 << polarity_bit);
+    let zeros = LTC_FRAME_BITS - bits.count_ones();
+
+    if zeros.is_multiple_of(2) {
+        bits
+    } else {
+        bits | (1 << polarity_bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Sets `value` into the frame starting from `start`, lowest bit first
+    fn set(bits: &mut u128, start: u32, value: u32) {
+        *bits |= u128::from(value) << start;
+    }
+
+    /// 12:34:56:27 with drop-frame set and user bits `0x8765_4321`
+    fn example_bits() -> u128 {
+        let mut bits = 0;
+        set(&mut bits, 0, 7);
+        set(&mut bits, 8, 2);
+        set(&mut bits, 10, 1);
+        set(&mut bits, 16, 6);
+        set(&mut bits, 24, 5);
+        set(&mut bits, 32, 4);
+        set(&mut bits, 40, 3);
+        set(&mut bits, 48, 2);
+        set(&mut bits, 56, 1);
+        for (group, start) in USER_BIT_GROUPS.iter().enumerate() {
+            set(&mut bits, *start, group as u32 + 1);
+        }
+        set(&mut bits, 64, u32::from(SYNC_WORD));
+        bits
+    }
+
+    #[test]
+    fn test_from_bits() {
+        let frame = LtcFrame::from_bits(example_bits());
+
+        assert_eq!(
+            frame,
+            Some(LtcFrame {
+                hours: 12,
+                minutes: 34,
+                seconds: 56,
+                frames: 27,
+                drop_frame: true,
+                colour_frame: false,
+                user_bits: 0x8765_4321,
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_bits() {
+        let frame = LtcFrame::from_bits(example_bits()).map(|frame| frame.to_bits());
+
+        assert_eq!(frame, Some(example_bits()));
+    }
+
+    #[test]
+    fn test_correct_polarity() {
+        for rate in LTC_RATES {
+            let bits = correct_polarity(example_bits(), rate);
+
+            assert!(
+                (LTC_FRAME_BITS - bits.count_ones()).is_multiple_of(2),
+                "{rate} should have an even number of 0s"
+            );
+            assert_eq!(
+                LtcFrame::from_bits(bits),
+                LtcFrame::from_bits(example_bits())
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_sync_word() {
+        let bits = example_bits() & !(1 << 79);
+
+        assert_eq!(LtcFrame::from_bits(bits), None);
+    }
+
+    #[test]
+    fn test_invalid_digit() {
+        // Frame units of 10
+        let bits = (example_bits() & !0xF) | 0xA;
+
+        assert_eq!(LtcFrame::from_bits(bits), None);
+    }
+
+    #[test]
+    fn test_reverse_frame_bits_round_trip() {
+        let bits = example_bits();
+
+        assert_eq!(reverse_frame_bits(reverse_frame_bits(bits)), bits);
+        assert_eq!(reverse_frame_bits(bits) as u16, REVERSE_SYNC_WORD);
+    }
+}
@@ -0,0 +1,252 @@
+use std::{f64::consts::PI, time::Duration};
+
+use crate::backend::{
+    ltc_frame::{LTC_FRAME_BITS, LtcFrame, correct_polarity},
+    timecode::Timecode,
+};
+
+/// A raised cosine edge spends this fraction of its length between 10% and 90%, which is how rise time is measured
+const RISE_TIME_FRACTION: f64 = 0.59;
+
+/// Turns timecode into LTC audio, one sample at a time.
+///
+/// Every bit starts with a transition and a 1 has another one half way through (biphase-mark).
+/// Transitions are shaped into raised cosine edges, as a square wave with instant edges
+/// would be full of harmonics that ring through anything it is sent down
+pub struct LtcEncoder {
+    samples_per_bit: f64,
+    /// How long each transition takes, in samples
+    edge_samples: f64,
+    amplitude: f32,
+    /// The frame being sent
+    timecode: Timecode,
+    bits: u128,
+    /// Which bit of the frame is being sent
+    bit: u32,
+    /// How far through the current bit we are, in samples
+    position: f64,
+    /// Whether the last transition was up or down
+    high: bool,
+    /// How long ago the last transition started, in samples
+    since_transition: f64,
+    /// The frame to send after this one instead of counting on, for following another source
+    next: Option<Timecode>,
+}
+
+impl LtcEncoder {
+    /// Creates an encoder starting at the beginning of `start`, at the rate of `start`.
+    /// `level_dbfs` is the peak level and `rise_time` is how long an edge takes from 10% to 90%
+    pub fn new(sample_rate: u32, start: Timecode, level_dbfs: f32, rise_time: Duration) -> Self {
+        let sample_rate = f64::from(sample_rate);
+        let samples_per_bit = sample_rate / (start.rate().as_f64() * f64::from(LTC_FRAME_BITS));
+
+        // Edges can't take longer than half a bit, or they would run into each other
+        let edge_samples = (rise_time.as_secs_f64() / RISE_TIME_FRACTION * sample_rate)
+            .clamp(f64::EPSILON, samples_per_bit / 2.0);
+
+        Self {
+            samples_per_bit,
+            edge_samples,
+            amplitude: 10.0_f32.powf(level_dbfs / 20.0),
+            timecode: start,
+            bits: frame_bits(start),
+            bit: 0,
+            position: 0.0,
+            high: true,
+            since_transition: 0.0,
+            next: None,
+        }
+    }
+
+    /// The frame currently being sent
+    pub fn timecode(&self) -> Timecode {
+        self.timecode
+    }
+
+    /// Sends `next` once the current frame is finished, rather than the one after it
+    pub fn chase(&mut self, next: Timecode) {
+        self.next = Some(next.relabel(self.timecode.rate()));
+    }
+
+    /// How long until the next frame starts, in frames. Just over 0.0 at the end of a frame up to 1.0 at the start
+    pub fn frames_until_next(&self) -> f64 {
+        1.0 - (f64::from(self.bit) + self.position / self.samples_per_bit)
+            / f64::from(LTC_FRAME_BITS)
+    }
+
+    /// Gets the next sample of audio
+    pub fn next_sample(&mut self) -> f32 {
+        let sample = self.shape();
+        self.advance();
+        sample
+    }
+
+    /// The level of the signal right now, part way through an edge or settled after one
+    fn shape(&self) -> f32 {
+        let target = if self.high {
+            self.amplitude
+        } else {
+            -self.amplitude
+        };
+
+        if self.since_transition >= self.edge_samples {
+            return target;
+        }
+
+        // Goes from -1.0 to 1.0 over the length of the edge
+        let progress = -(PI * self.since_transition / self.edge_samples).cos();
+
+        target * progress as f32
+    }
+
+    /// Moves on by one sample, making any transitions that happen during it
+    fn advance(&mut self) {
+        let half_way = self.samples_per_bit / 2.0;
+        let previous_position = self.position;
+
+        self.position += 1.0;
+        self.since_transition += 1.0;
+
+        // A 1 has a transition half way through
+        if (self.bits >> self.bit) & 1 == 1
+            && previous_position < half_way
+            && self.position >= half_way
+        {
+            self.transition(self.position - half_way);
+        }
+
+        // Every bit starts with a transition
+        if self.position >= self.samples_per_bit {
+            self.position -= self.samples_per_bit;
+            self.next_bit();
+            self.transition(self.position);
+        }
+    }
+
+    /// Flips the signal, `since` samples ago
+    fn transition(&mut self, since: f64) {
+        self.high = !self.high;
+        self.since_transition = since;
+    }
+
+    fn next_bit(&mut self) {
+        self.bit += 1;
+
+        if self.bit == LTC_FRAME_BITS {
+            self.bit = 0;
+            self.timecode = self.next.take().unwrap_or(self.timecode + 1);
+            self.bits = frame_bits(self.timecode);
+        }
+    }
+}
+
+/// The bits to send for a frame
+fn frame_bits(timecode: Timecode) -> u128 {
+    correct_polarity(LtcFrame::from(timecode).to_bits(), timecode.rate())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{
+        frame_rate::FrameRate, ltc_decoder::file_analysis::LtcFileAnalysis, ltc_frame::LTC_RATES,
+    };
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn encode(start: Timecode, seconds: u32, rise_time: Duration) -> Vec<f32> {
+        let mut encoder = LtcEncoder::new(SAMPLE_RATE, start, -6.0, rise_time);
+
+        (0..SAMPLE_RATE * seconds)
+            .map(|_| encoder.next_sample())
+            .collect()
+    }
+
+    #[test]
+    fn test_decodes_at_every_rate() {
+        for rate in LTC_RATES {
+            let start = Timecode::new(1, 0, 59, 0, rate);
+            let analysis = LtcFileAnalysis::from_samples(
+                encode(start, 2, Duration::from_micros(25)),
+                SAMPLE_RATE,
+            );
+
+            assert_eq!(analysis.rate, Some(rate));
+            assert_eq!(
+                analysis.frames.first().map(|frame| frame.timecode),
+                Some(start),
+                "{rate} should start where it was asked to"
+            );
+            assert_eq!(analysis.discontinuities, vec![], "{rate}");
+            assert_eq!(analysis.dropouts, vec![], "{rate}");
+        }
+    }
+
+    #[test]
+    fn test_level() {
+        let samples = encode(
+            Timecode::new(0, 0, 0, 0, FrameRate::Fps25),
+            1,
+            Duration::from_micros(25),
+        );
+        let peak = samples
+            .iter()
+            .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+
+        // -6dBFS
+        assert!((peak - 0.501).abs() < 0.001, "Peak was {peak}");
+    }
+
+    #[test]
+    fn test_slow_rise_time_still_decodes() {
+        let start = Timecode::new(0, 0, 0, 0, FrameRate::Fps30);
+        let analysis = LtcFileAnalysis::from_samples(
+            encode(start, 1, Duration::from_micros(100)),
+            SAMPLE_RATE,
+        );
+
+        assert_eq!(analysis.frames.len(), 29);
+    }
+
+    #[test]
+    fn test_chase() {
+        let mut encoder = LtcEncoder::new(
+            SAMPLE_RATE,
+            Timecode::new(0, 0, 0, 0, FrameRate::Fps25),
+            -6.0,
+            Duration::from_micros(25),
+        );
+        assert!((encoder.frames_until_next() - 1.0).abs() < 1e-9);
+
+        // Half a frame in
+        for _ in 0..SAMPLE_RATE / 50 {
+            encoder.next_sample();
+        }
+        assert!((encoder.frames_until_next() - 0.5).abs() < 1e-6);
+
+        // The frame being sent is finished before jumping
+        encoder.chase(Timecode::new(10, 0, 0, 0, FrameRate::Fps25));
+        assert_eq!(
+            encoder.timecode(),
+            Timecode::new(0, 0, 0, 0, FrameRate::Fps25)
+        );
+
+        for _ in 0..SAMPLE_RATE / 25 {
+            encoder.next_sample();
+        }
+        assert_eq!(
+            encoder.timecode(),
+            Timecode::new(10, 0, 0, 0, FrameRate::Fps25)
+        );
+
+        // Then counts on from there if nothing else comes along
+        for _ in 0..SAMPLE_RATE / 25 {
+            encoder.next_sample();
+        }
+        assert_eq!(
+            encoder.timecode(),
+            Timecode::new(10, 0, 0, 1, FrameRate::Fps25)
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use std::{
+    path::Path,
+    sync::mpsc::{self, Receiver, Sender, SyncSender},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, anyhow};
+use cpal::{
+    Device, FromSample, Host, SampleFormat, SizedSample, Stream, StreamConfig,
+    traits::{DeviceTrait as _, HostTrait as _, StreamTrait as _},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    ltc_generator::ltc_encoder::LtcEncoder,
+    timecode::Timecode,
+    timecode_decoder::{PlaybackDirection, TimecodePosition},
+};
+
+mod ltc_encoder;
+
+/// The latest position from the source being chased, the direction it is playing in and when it arrived
+type ChasePosition = (TimecodePosition, Option<PlaybackDirection>, Instant);
+
+/// How many frames the audio thread can report before it has to drop them, allocated up front
+/// so the audio callback never has to
+const TIMECODE_QUEUE_LENGTH: usize = 64;
+
+/// What to generate and how it should sound
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LtcGeneratorSettings {
+    /// The first frame to send, at the rate to send it at
+    pub start: Timecode,
+    /// The peak level of the signal
+    pub level_dbfs: f32,
+    /// How long each edge takes from 10% to 90%, SMPTE 12M asks for 25µs ± 5µs
+    pub rise_time_micros: u32,
+    /// The name of the audio output to send to
+    pub device_name: Option<String>,
+    /// Which channel of the output to send on, counting from 0. The other channels are left silent
+    pub channel: usize,
+}
+
+impl Default for LtcGeneratorSettings {
+    fn default() -> Self {
+        Self {
+            start: Timecode::new(0, 0, 0, 0, FrameRate::Fps25),
+            level_dbfs: -18.0,
+            rise_time_micros: 25,
+            device_name: None,
+            channel: 0,
+        }
+    }
+}
+
+impl LtcGeneratorSettings {
+    fn encoder(&self, sample_rate: u32) -> LtcEncoder {
+        LtcEncoder::new(
+            sample_rate,
+            self.start,
+            self.level_dbfs,
+            Duration::from_micros(u64::from(self.rise_time_micros)),
+        )
+    }
+}
+
+/// Sends LTC out of an audio output, in place of a hardware generator
+pub struct LtcGenerator {
+    /// The frames being sent, from the audio thread
+    timecode_rx: Option<Receiver<Timecode>>,
+    /// Positions for the audio thread to chase
+    chase_tx: Option<Sender<ChasePosition>>,
+    last_position: Option<TimecodePosition>,
+    current_timecode: Option<Timecode>,
+    rate: Option<FrameRate>,
+    stream: Option<Stream>,
+    host: Host,
+}
+
+impl LtcGenerator {
+    /// Creates a new, stopped, generator using the system's default audio backend
+    pub fn new() -> Self {
+        Self {
+            timecode_rx: None,
+            chase_tx: None,
+            last_position: None,
+            current_timecode: None,
+            rate: None,
+            stream: None,
+            host: cpal::default_host(),
+        }
+    }
+
+    /// Gets the names of the audio outputs on the system. This is an I/O operation so don't spam it
+    pub fn get_devices(&self) -> Vec<String> {
+        self.host
+            .output_devices()
+            .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Start sending LTC from the start time in the settings. It counts on from there by itself unless it is
+    /// given positions to chase with [`Self::update`]
+    ///
+    /// # Errors
+    /// Fails if no output has been selected, it has gone away, it doesn't have the selected channel
+    /// or the audio stream could not be started
+    pub fn start(&mut self, settings: &LtcGeneratorSettings) -> Result<()> {
+        let device_name = settings
+            .device_name
+            .as_ref()
+            .ok_or_else(|| anyhow!("No audio output specified, cannot start generating"))?;
+
+        let device = self
+            .host
+            .output_devices()?
+            .find(|device| device.name().ok().as_ref() == Some(device_name))
+            .ok_or_else(|| anyhow!("Audio output {device_name} is no longer available"))?;
+
+        let supported_config = device.default_output_config()?;
+        let config = supported_config.config();
+
+        if settings.channel >= usize::from(config.channels) {
+            return Err(anyhow!(
+                "{device_name} only has {} channels",
+                config.channels
+            ));
+        }
+
+        let encoder = settings.encoder(config.sample_rate.0);
+        let channel = settings.channel;
+
+        // Create a channel to send the frames being generated back from the audio thread,
+        // and one to send it positions to chase
+        let (tx, rx) = mpsc::sync_channel(TIMECODE_QUEUE_LENGTH);
+        let (chase_tx, chase_rx) = mpsc::channel();
+        let channels = AudioChannels {
+            channel,
+            timecode_tx: tx,
+            chase_rx,
+        };
+
+        let stream = match supported_config.sample_format() {
+            SampleFormat::I8 => build_stream::<i8>(&device, &config, encoder, channels),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, encoder, channels),
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, encoder, channels),
+            SampleFormat::U8 => build_stream::<u8>(&device, &config, encoder, channels),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, encoder, channels),
+            SampleFormat::U32 => build_stream::<u32>(&device, &config, encoder, channels),
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, encoder, channels),
+            SampleFormat::F64 => build_stream::<f64>(&device, &config, encoder, channels),
+            format => Err(anyhow!("Unsupported audio sample format {format}")),
+        }?;
+        stream.play()?;
+
+        self.stream = Some(stream);
+        self.timecode_rx = Some(rx);
+        self.chase_tx = Some(chase_tx);
+        self.rate = Some(settings.start.rate());
+
+        Ok(())
+    }
+
+    /// Stops sending LTC
+    ///
+    /// # Errors
+    /// Result will be Err if the generator isn't running
+    pub fn stop(&mut self) -> Result<()> {
+        let stream = self
+            .stream
+            .take()
+            .ok_or_else(|| anyhow!("The generator isn't running, hence you cannot stop it"))?;
+
+        // Dropping the stream stops it
+        drop(stream);
+
+        self.timecode_rx = None;
+        self.chase_tx = None;
+        self.last_position = None;
+        self.current_timecode = None;
+        self.rate = None;
+
+        Ok(())
+    }
+
+    /// Returns true if LTC is being sent
+    pub fn running(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// The rate LTC is being sent at, None if the generator isn't running
+    pub fn rate(&self) -> Option<FrameRate> {
+        self.rate
+    }
+
+    /// Give the generator the latest position from a source to chase, `direction` is None if the source isn't
+    /// playing. The same position twice is ignored, so it is fine to call this however often the
+    /// source is read
+    pub fn update(&mut self, position: TimecodePosition, direction: Option<PlaybackDirection>) {
+        if self.last_position == Some(position) {
+            return;
+        }
+        self.last_position = Some(position);
+
+        if let Some(tx) = &self.chase_tx
+            && tx.send((position, direction, Instant::now())).is_err()
+        {
+            log::error!("LTC audio output has stopped unexpectedly");
+        }
+    }
+
+    /// The frame currently being sent, None if the generator isn't running
+    pub fn current_timecode(&mut self) -> Option<Timecode> {
+        if let Some(rx) = &self.timecode_rx
+            && let Some(timecode) = rx.try_iter().last()
+        {
+            self.current_timecode = Some(timecode);
+        }
+
+        self.current_timecode
+    }
+}
+
+impl Default for LtcGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes `length` of LTC to a mono 24 bit WAV file, starting from the start time in the settings.
+///
+/// # Errors
+/// Fails if the file could not be written
+pub fn render_wav(
+    settings: &LtcGeneratorSettings,
+    sample_rate: u32,
+    length: Duration,
+    path: &Path,
+) -> Result<()> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 24,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    let mut encoder = settings.encoder(sample_rate);
+
+    let samples = (length.as_secs_f64() * f64::from(sample_rate)).round() as u64;
+    let full_scale = f64::from((1 << 23) - 1);
+
+    for _ in 0..samples {
+        writer.write_sample((f64::from(encoder.next_sample()) * full_scale).round() as i32)?;
+    }
+
+    writer.finalize()?;
+
+    Ok(())
+}
+
+/// What the audio thread needs besides the encoder: where to put the signal and how to talk to the generator
+struct AudioChannels {
+    /// Which channel of the output to send on
+    channel: usize,
+    timecode_tx: SyncSender<Timecode>,
+    chase_rx: Receiver<ChasePosition>,
+}
+
+/// Opens an audio output stream that sends LTC on one of its channels
+fn build_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    mut encoder: LtcEncoder,
+    channels: AudioChannels,
+) -> Result<Stream>
+where
+    T: SizedSample + FromSample<f32> + Send + 'static,
+{
+    let AudioChannels {
+        channel,
+        timecode_tx,
+        chase_rx,
+    } = channels;
+    let channel_count = usize::from(config.channels);
+    let silence = T::EQUILIBRIUM;
+    let mut last_sent = None;
+    let mut chasing = None;
+
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            if let Some(latest) = chase_rx.try_iter().last() {
+                chasing = Some(latest);
+            }
+
+            // Line the next frame up with wherever the source will be when it starts
+            if let Some((position, direction, received_at)) = chasing {
+                let next = position
+                    .extrapolate(direction, received_at.elapsed())
+                    .advance(direction, encoder.frames_until_next())
+                    .offset_by_frames(0.5); // Round to the nearest frame
+
+                encoder.chase(next.timecode);
+            }
+
+            // Samples are interleaved, so only fill in the ones for our channel
+            for frame in data.chunks_mut(channel_count) {
+                let sample = encoder.next_sample();
+
+                for (index, output) in frame.iter_mut().enumerate() {
+                    *output = if index == channel {
+                        T::from_sample(sample)
+                    } else {
+                        silence
+                    };
+                }
+            }
+
+            let timecode = encoder.timecode();
+            if last_sent != Some(timecode) {
+                last_sent = Some(timecode);
+                timecode_tx.try_send(timecode).ok();
+            }
+        },
+        |error| log::error!("LTC audio output failed: {error}"),
+        None,
+    )?;
+
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{
+        env, fs, process,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+    use crate::backend::ltc_decoder::file_analysis::LtcFileAnalysis;
+
+    /// Makes each test file name different, so test runs going on at the same time don't write over each other
+    static TEST_FILE_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    #[test]
+    fn test_render_wav_round_trip() {
+        let settings = LtcGeneratorSettings {
+            start: Timecode::new(10, 0, 0, 0, FrameRate::Fps24),
+            ..Default::default()
+        };
+        let path = env::temp_dir().join(format!(
+            "technus_timecode_test_render_{}_{}.wav",
+            process::id(),
+            TEST_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        render_wav(&settings, 44_100, Duration::from_secs(1), &path).expect("Writable temp dir");
+        let analysis = LtcFileAnalysis::from_file(&path, 0);
+        fs::remove_file(&path).expect("The test file was written");
+        let analysis = analysis.expect("Readable WAV file");
+
+        assert_eq!(analysis.sample_rate, 44_100);
+        assert_eq!(analysis.rate, Some(FrameRate::Fps24));
+        assert_eq!(
+            analysis.frames.first().map(|frame| frame.timecode),
+            Some(settings.start)
+        );
+        assert_eq!(analysis.frames.len(), 23);
+    }
+}
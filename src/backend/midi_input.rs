@@ -0,0 +1,95 @@
+use anyhow::{Result, anyhow};
+use midir::{Ignore, MidiInput, MidiInputConnection, MidiInputPort};
+
+/// A MIDI input port to pick and listen on, shared by everything that reads MIDI
+pub struct MidiInputListener {
+    pub port: Option<MidiInputPort>,
+    connection: Option<MidiInputConnection<()>>,
+    /// Only used to look up ports, each connection gets its own
+    midi_in: MidiInput,
+    /// What the connections are called on the system, E.g: in `aconnect -l`
+    client_name: &'static str,
+}
+
+impl MidiInputListener {
+    /// Creates a new, disconnected, listener.
+    ///
+    /// # Errors
+    /// Fails if the system MIDI backend could not be initialised
+    pub fn new(client_name: &'static str) -> Result<Self> {
+        let mut midi_in = MidiInput::new(&format!("{client_name} check ports"))?;
+        midi_in.ignore(Ignore::None);
+
+        Ok(Self {
+            port: None,
+            connection: None,
+            midi_in,
+            client_name,
+        })
+    }
+
+    /// Gets the currently active ports on the system. This is an I/O operation so don't spam it
+    pub fn get_ports(&self) -> Vec<MidiInputPort> {
+        self.midi_in.ports()
+    }
+
+    /// Gets the human readable name of a port.
+    ///
+    /// # Errors
+    /// Fails if the port is no longer available
+    pub fn port_name(&self, port: &MidiInputPort) -> Result<String> {
+        Ok(self.midi_in.port_name(port)?)
+    }
+
+    /// Connects to the selected port, calling `on_message` on the MIDI thread with every message and when it
+    /// arrived in microseconds. Nothing is filtered out, so clock and system exclusive messages arrive too.
+    ///
+    /// `on_message` usually sends what it finds down a channel to its decoder. The connection can outlive the
+    /// receiving end while a decoder is being dropped, so failed sends should be ignored rather than unwrapped
+    ///
+    /// # Errors
+    /// Fails if no port has been selected or the connection could not be opened
+    pub fn connect(
+        &mut self,
+        mut on_message: impl FnMut(u64, &[u8]) + Send + 'static,
+    ) -> Result<()> {
+        let mut midi_in = MidiInput::new(self.client_name)?;
+        midi_in.ignore(Ignore::None);
+
+        let port = self
+            .port
+            .as_ref()
+            .ok_or_else(|| anyhow!("No port specified, cannot open connection"))?;
+
+        let connection = midi_in
+            .connect(
+                port,
+                self.client_name,
+                move |timestamp, message, ()| on_message(timestamp, message),
+                (),
+            )
+            .map_err(|e| anyhow!("Failed to connect to MIDI port: {e}"))?;
+
+        self.connection = Some(connection);
+
+        Ok(())
+    }
+
+    /// Closes the currently open connection
+    ///
+    /// # Errors
+    /// Result will be Err if there is no connection currently open
+    pub fn disconnect(&mut self) -> Result<()> {
+        self.connection
+            .take()
+            .ok_or_else(|| anyhow!("No connection is open, hence you cannot close it"))?
+            .close();
+
+        Ok(())
+    }
+
+    /// Returns true if there has been a connection made.
+    pub fn connected(&self) -> bool {
+        self.connection.is_some()
+    }
+}
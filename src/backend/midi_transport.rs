@@ -0,0 +1,176 @@
+use std::{fmt, time::Duration};
+
+use midi_msg::{MidiMsg, SystemCommonMsg, SystemRealTimeMsg};
+
+use crate::backend::mmc_sender::MmcCommand;
+
+/// MIDI beat clock is sent 24 times a quarter note
+pub const CLOCKS_PER_BEAT: u32 = 24;
+
+/// How long the clock can go quiet for before it counts as stopped, long enough for anything above 5 BPM
+pub const CLOCK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How much each new clock interval counts towards the measured tempo
+const TEMPO_SMOOTHING: f64 = 0.1;
+
+/// Something a machine did to its transport, as opposed to where it is
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TransportEvent {
+    Mmc(MmcCommand),
+    /// Song Position Pointer, in sixteenth notes from the start of the song
+    SongPosition(u16),
+    Start,
+    Stop,
+    Continue,
+    /// Beat clock started arriving
+    ClockStarted,
+    /// Beat clock stopped arriving
+    ClockStopped,
+}
+
+impl TransportEvent {
+    /// Reads the transport events out of a raw MIDI message, `parsed` being the same message if midi-msg could
+    /// make sense of it. Beat clock ticks aren't events by themselves, see [`ClockTempo`]
+    pub fn from_midi(message: &[u8], parsed: Option<&MidiMsg>) -> Vec<Self> {
+        // midi-msg can't parse MMC, so it has to be done by hand
+        if let Some((_, commands)) = MmcCommand::from_midi(message) {
+            return commands.into_iter().map(Self::Mmc).collect();
+        }
+
+        let event = match parsed {
+            Some(MidiMsg::SystemCommon {
+                msg: SystemCommonMsg::SongPosition(position),
+            }) => Self::SongPosition(*position),
+            Some(MidiMsg::SystemRealTime { msg }) => match msg {
+                SystemRealTimeMsg::Start => Self::Start,
+                SystemRealTimeMsg::Stop => Self::Stop,
+                SystemRealTimeMsg::Continue => Self::Continue,
+                _ => return Vec::new(),
+            },
+            _ => return Vec::new(),
+        };
+
+        vec![event]
+    }
+}
+
+impl fmt::Display for TransportEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Mmc(command) => write!(f, "MMC {command}"),
+            Self::SongPosition(position) => write!(
+                f,
+                "Song position bar {}, beat {}, sixteenth {}",
+                position / 16 + 1,
+                position / 4 % 4 + 1,
+                position % 4 + 1
+            ),
+            Self::Start => write!(f, "Start"),
+            Self::Stop => write!(f, "Stop"),
+            Self::Continue => write!(f, "Continue"),
+            Self::ClockStarted => write!(f, "Clock started"),
+            Self::ClockStopped => write!(f, "Clock stopped"),
+        }
+    }
+}
+
+/// Measures the tempo of MIDI beat clock from when the ticks arrive
+#[derive(Debug, Default)]
+pub struct ClockTempo {
+    /// The MIDI timestamp of the last tick, in microseconds
+    last_timestamp: Option<u64>,
+    /// The smoothed time between ticks, in microseconds
+    interval: Option<f64>,
+}
+
+impl ClockTempo {
+    /// Takes in a tick, `timestamp` being when it arrived in microseconds. Returns the tempo in BPM,
+    /// None until there have been two ticks close enough together to measure
+    pub fn tick(&mut self, timestamp: u64) -> Option<f64> {
+        let last_timestamp = self.last_timestamp.replace(timestamp);
+        let gap = timestamp.saturating_sub(last_timestamp?);
+
+        // The clock stopped in between, so the gap says nothing about the tempo
+        if gap > CLOCK_TIMEOUT.as_micros() as u64 || gap == 0 {
+            self.interval = None;
+            return None;
+        }
+
+        let gap = gap as f64;
+        let interval = match self.interval {
+            Some(smoothed) => smoothed + (gap - smoothed) * TEMPO_SMOOTHING,
+            None => gap,
+        };
+        self.interval = Some(interval);
+
+        Some(60_000_000.0 / (interval * f64::from(CLOCKS_PER_BEAT)))
+    }
+
+    /// Forgets the ticks so far, E.g: after reconnecting
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Whether a message is a beat clock tick
+pub fn is_clock(parsed: &MidiMsg) -> bool {
+    matches!(
+        parsed,
+        MidiMsg::SystemRealTime {
+            msg: SystemRealTimeMsg::TimingClock
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_from_midi() {
+        let spp = [0xF2, 0x10, 0x01];
+        let (parsed, _) = MidiMsg::from_midi(&spp).expect("Valid song position");
+
+        assert_eq!(
+            TransportEvent::from_midi(&spp, Some(&parsed)),
+            vec![TransportEvent::SongPosition(144)]
+        );
+        assert_eq!(
+            TransportEvent::SongPosition(144).to_string(),
+            "Song position bar 10, beat 1, sixteenth 1"
+        );
+
+        let (parsed, _) = MidiMsg::from_midi(&[0xFB]).expect("Valid continue");
+        assert_eq!(
+            TransportEvent::from_midi(&[0xFB], Some(&parsed)),
+            vec![TransportEvent::Continue]
+        );
+
+        assert_eq!(
+            TransportEvent::from_midi(&[0xF0, 0x7F, 0x7F, 0x06, 0x01, 0xF7], None),
+            vec![TransportEvent::Mmc(MmcCommand::Stop)]
+        );
+    }
+
+    #[test]
+    fn test_clock_tempo() {
+        let mut tempo = ClockTempo::default();
+
+        // 120 BPM is a tick every 20.8ms
+        let ticks: Vec<_> = (0..10)
+            .map(|tick| tempo.tick(tick * 500_000 / 24))
+            .collect();
+
+        assert_eq!(ticks.first(), Some(&None));
+        assert!(
+            ticks
+                .iter()
+                .skip(1)
+                .all(|bpm| bpm.is_some_and(|bpm| (bpm - 120.0).abs() < 0.1))
+        );
+
+        // Starting again after a pause has to wait for the next tick
+        assert_eq!(tempo.tick(10_000_000), None);
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt::Display;
+
+use anyhow::{Result, anyhow};
+use midi_msg::{
+    DeviceID, MachineControlCommandMsg, MidiMsg, StandardTimeCode, SystemExclusiveMsg,
+    UniversalRealTimeMsg,
+};
+use midir::{MidiOutput, MidiOutputConnection, MidiOutputPort};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{frame_rate::FrameRate, timecode::Timecode};
+
+/// The device ID that every machine listens to
+pub const ALL_CALL: u8 = 0x7F;
+
+/// A MIDI Machine Control command, for driving the transport of a DAW or video deck
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum MmcCommand {
+    Play,
+    Stop,
+    /// Play once the machine has finished locating
+    DeferredPlay,
+    /// Drop in to record
+    RecordStrobe,
+    /// Drop out of record
+    RecordExit,
+    Pause,
+    FastForward,
+    Rewind,
+    Locate(Timecode),
+    /// A command this app doesn't know about, by its command byte
+    Other(u8),
+}
+
+impl MmcCommand {
+    /// Every command that doesn't need anything else to go with it
+    pub const TRANSPORT: [Self; 5] = [
+        Self::Play,
+        Self::Stop,
+        Self::DeferredPlay,
+        Self::RecordStrobe,
+        Self::RecordExit,
+    ];
+
+    /// The system exclusive message for this command, addressed to `device_id` or [`ALL_CALL`]
+    pub fn to_midi(self, device_id: u8) -> Vec<u8> {
+        let command = match self {
+            Self::Play => MachineControlCommandMsg::Play,
+            Self::Stop => MachineControlCommandMsg::Stop,
+            Self::DeferredPlay => MachineControlCommandMsg::DeferredPlay,
+            Self::RecordStrobe => MachineControlCommandMsg::RecordStrobe,
+            Self::RecordExit => MachineControlCommandMsg::RecordExit,
+            Self::Pause => MachineControlCommandMsg::Pause,
+            Self::FastForward => MachineControlCommandMsg::FastForward,
+            Self::Rewind => MachineControlCommandMsg::Rewind,
+            Self::Other(command) => MachineControlCommandMsg::Unimplemented(vec![command]),
+            Self::Locate(timecode) => MachineControlCommandMsg::LocateTarget(
+                StandardTimeCode::from(midi_msg::TimeCode::from(timecode)),
+            ),
+        };
+
+        let device = if device_id >= ALL_CALL {
+            DeviceID::AllCall
+        } else {
+            DeviceID::Device(device_id)
+        };
+
+        MidiMsg::SystemExclusive {
+            msg: SystemExclusiveMsg::UniversalRealTime {
+                device,
+                msg: UniversalRealTimeMsg::MachineControlCommand(command),
+            },
+        }
+        .to_midi()
+    }
+
+    /// Reads the commands out of an MMC message (`F0 7F <device> 06 <commands> F7`), along with the device ID
+    /// they are for. A single message can carry several commands. None if it isn't an MMC command message
+    pub fn from_midi(message: &[u8]) -> Option<(u8, Vec<Self>)> {
+        let [0xF0, 0x7F, device_id, 0x06, commands @ .., 0xF7] = message else {
+            return None;
+        };
+
+        let mut parsed = Vec::new();
+        let mut remaining = commands;
+        while let [command, rest @ ..] = remaining {
+            remaining = rest;
+
+            // Commands from 0x40 up carry a count of the data bytes that follow them
+            if (0x40..=0x77).contains(command) {
+                let (&count, rest) = remaining.split_first()?;
+                let (data, rest) = rest.split_at_checked(usize::from(count))?;
+                remaining = rest;
+
+                parsed.push(match (command, data) {
+                    (0x44, [0x01, hours, minutes, seconds, frames, ..]) => {
+                        Self::Locate(locate_target(*hours, *minutes, *seconds, *frames)?)
+                    }
+                    _ => Self::Other(*command),
+                });
+                continue;
+            }
+
+            parsed.push(match command {
+                0x01 => Self::Stop,
+                0x02 => Self::Play,
+                0x03 => Self::DeferredPlay,
+                0x04 => Self::FastForward,
+                0x05 => Self::Rewind,
+                0x06 => Self::RecordStrobe,
+                0x07 => Self::RecordExit,
+                0x09 => Self::Pause,
+                _ => Self::Other(*command),
+            });
+        }
+
+        Some((*device_id, parsed))
+    }
+}
+
+/// Builds the timecode of a locate target. Like a full-frame message, the rate is in the top bits of the hours byte
+fn locate_target(hours: u8, minutes: u8, seconds: u8, frames: u8) -> Option<Timecode> {
+    let rate = match (hours >> 5) & 0x03 {
+        0 => FrameRate::Fps24,
+        1 => FrameRate::Fps25,
+        2 => FrameRate::Fps29_97Df,
+        _ => FrameRate::Fps30,
+    };
+
+    // The frames byte also carries colour frame and sign bits
+    Timecode::try_new(
+        hours & 0x1F,
+        minutes & 0x3F,
+        seconds & 0x3F,
+        frames & 0x1F,
+        rate,
+    )
+    .ok()
+}
+
+impl Display for MmcCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Play => write!(f, "Play"),
+            Self::Stop => write!(f, "Stop"),
+            Self::DeferredPlay => write!(f, "Deferred play"),
+            Self::RecordStrobe => write!(f, "Record"),
+            Self::RecordExit => write!(f, "Record exit"),
+            Self::Pause => write!(f, "Pause"),
+            Self::FastForward => write!(f, "Fast forward"),
+            Self::Rewind => write!(f, "Rewind"),
+            Self::Other(command) => write!(f, "Command {command:#04X}"),
+            Self::Locate(timecode) => {
+                write!(f, "Locate to {}", timecode.format(Default::default()))
+            }
+        }
+    }
+}
+
+/// Sends MMC commands out of a MIDI port
+pub struct MmcSender {
+    pub port: Option<MidiOutputPort>,
+    /// Which machine to talk to, [`ALL_CALL`] for every machine on the port
+    pub device_id: u8,
+    connection: Option<MidiOutputConnection>,
+    midi_out: MidiOutput,
+}
+
+impl MmcSender {
+    /// Creates a new, disconnected, MMC sender.
+    ///
+    /// # Errors
+    /// Fails if the system MIDI backend could not be initialised
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            port: None,
+            device_id: ALL_CALL,
+            connection: None,
+            midi_out: MidiOutput::new("technus timecode MMC output check ports")?,
+        })
+    }
+
+    /// Gets the currently active ports on the system. This is an I/O operation so don't spam it
+    pub fn get_ports(&self) -> Vec<MidiOutputPort> {
+        self.midi_out.ports()
+    }
+
+    /// Gets the human readable name of a port.
+    ///
+    /// # Errors
+    /// Fails if the port is no longer available
+    pub fn port_name(&self, port: &MidiOutputPort) -> Result<String> {
+        Ok(self.midi_out.port_name(port)?)
+    }
+
+    /// Opens the selected port
+    ///
+    /// # Errors
+    /// Fails if no port has been selected or the connection could not be opened
+    pub fn connect(&mut self) -> Result<()> {
+        let port = self
+            .port
+            .as_ref()
+            .ok_or_else(|| anyhow!("No port specified, cannot open connection"))?;
+
+        let connection = MidiOutput::new("technus timecode MMC output")?
+            .connect(port, "technus-timecode-mmc-output")
+            .map_err(|e| anyhow!("Failed to connect to MIDI port: {e}"))?;
+
+        self.connection = Some(connection);
+
+        Ok(())
+    }
+
+    /// Closes the port
+    ///
+    /// # Errors
+    /// Result will be Err if there is no connection currently open
+    pub fn disconnect(&mut self) -> Result<()> {
+        self.connection
+            .take()
+            .ok_or_else(|| anyhow!("No connection is open, hence you cannot close it"))?
+            .close();
+
+        Ok(())
+    }
+
+    /// Returns true if there has been a connection made.
+    pub fn connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Sends a command to the machine
+    ///
+    /// # Errors
+    /// Fails if the port isn't open or the message could not be sent
+    pub fn send(&mut self, command: MmcCommand) -> Result<()> {
+        let connection = self
+            .connection
+            .as_mut()
+            .ok_or_else(|| anyhow!("Not connected to a MIDI port"))?;
+
+        connection
+            .send(&command.to_midi(self.device_id))
+            .map_err(|e| anyhow!("Failed to send {command}: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_to_midi() {
+        assert_eq!(
+            MmcCommand::Play.to_midi(ALL_CALL),
+            vec![0xF0, 0x7F, 0x7F, 0x06, 0x02, 0xF7]
+        );
+        assert_eq!(
+            MmcCommand::RecordStrobe.to_midi(3),
+            vec![0xF0, 0x7F, 0x03, 0x06, 0x06, 0xF7]
+        );
+
+        // The hours byte carries the rate, 25fps is 1
+        assert_eq!(
+            MmcCommand::Locate(Timecode::new(1, 2, 3, 4, FrameRate::Fps25)).to_midi(ALL_CALL),
+            vec![
+                0xF0, 0x7F, 0x7F, 0x06, 0x44, 0x06, 0x01, 0x21, 0x02, 0x03, 0x04, 0x00, 0xF7
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_midi() {
+        assert_eq!(
+            MmcCommand::from_midi(&[0xF0, 0x7F, 0x7F, 0x06, 0x02, 0xF7]),
+            Some((ALL_CALL, vec![MmcCommand::Play]))
+        );
+
+        // Commands can be strung together, and ones we don't know are kept by number
+        assert_eq!(
+            MmcCommand::from_midi(&[0xF0, 0x7F, 0x03, 0x06, 0x09, 0x0B, 0xF7]),
+            Some((3, vec![MmcCommand::Pause, MmcCommand::Other(0x0B)]))
+        );
+
+        // Full-frame timecode isn't MMC
+        assert_eq!(
+            MmcCommand::from_midi(&[0xF0, 0x7F, 0x7F, 0x01, 0x01, 0x21, 0x02, 0x03, 0x04, 0xF7]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_locate_round_trip() {
+        for timecode in [
+            Timecode::new(1, 2, 3, 4, FrameRate::Fps25),
+            Timecode::new(10, 0, 0, 2, FrameRate::Fps29_97Df),
+            Timecode::new(23, 59, 59, 23, FrameRate::Fps24),
+        ] {
+            let command = MmcCommand::Locate(timecode);
+
+            assert_eq!(
+                MmcCommand::from_midi(&command.to_midi(ALL_CALL)),
+                Some((ALL_CALL, vec![command]))
+            );
+        }
+    }
+}
@@ -1,3 +1,31 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod artnet_decoder;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod artnet_sender;
+pub mod artnet_timecode;
+pub mod beat_clock_decoder;
+pub mod cue_list;
+pub mod flywheel;
+pub mod frame_rate;
+pub mod frame_sender;
+pub mod ltc_decoder;
+pub mod ltc_frame;
+pub mod ltc_generator;
+pub mod midi_input;
+pub mod midi_transport;
+pub mod mmc_sender;
 pub mod mtc_decoder;
+pub mod mtc_generator;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod network_interface;
+pub mod osc;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod osc_decoder;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod osc_sender;
+pub mod packet_tracker;
 pub mod timecode;
 pub mod timecode_decoder;
+pub mod timecode_formatter;
+pub mod timecode_generator;
+pub mod timecode_parser;
@@ -1,144 +1,314 @@
-use std::sync::mpsc::{self, Receiver};
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver},
+    time::Instant,
+};
 
-use anyhow::{Result, anyhow};
-use midi_msg::MidiMsg;
-use midir::{Ignore, MidiInput, MidiInputConnection, MidiInputPort};
+use anyhow::Result;
+use midi_msg::{MidiMsg, SystemExclusiveMsg, TimeCode, UniversalRealTimeMsg};
 
 use crate::backend::{
-    mtc_decoder::timecode_quarter_frame_buffer::TimeCodeQuarterFrameBuffer, timecode::Timecode,
-    timecode_decoder::TimecodeDecoder,
+    flywheel::Flywheel,
+    midi_input::MidiInputListener,
+    midi_transport::{CLOCK_TIMEOUT, ClockTempo, TransportEvent, is_clock},
+    mtc_decoder::timecode_quarter_frame_buffer::TimeCodeQuarterFrameBuffer,
+    timecode::Timecode,
+    timecode_decoder::{
+        ConnectionState, DecoderStatus, ErrorCounters, PlaybackDirection, TimecodeDecoder,
+        TimecodePosition,
+    },
 };
 
-mod timecode_quarter_frame_buffer;
+pub mod timecode_quarter_frame_buffer;
+
+/// How many transport events to remember for the UI
+const TRANSPORT_HISTORY_LENGTH: usize = 20;
+
+/// What the MIDI thread tells the decoder about
+enum MtcEvent {
+    /// A new position, with the direction of playback and the rate quarter frames are arriving at if they are known
+    Position {
+        position: TimecodePosition,
+        direction: Option<PlaybackDirection>,
+        measured_fps: Option<f64>,
+    },
+    /// A MIDI message that could not be parsed
+    Malformed,
+    /// Quarter frames went missing
+    Dropout,
+    /// A transport command or song position
+    Transport(TransportEvent),
+    /// A beat clock tick, with the tempo if it could be measured yet
+    Clock { tempo: Option<f64> },
+}
 
 pub struct MtcTimecodeDecoder {
-    pub fps: u8,
-    pub port: Option<MidiInputPort>,
-    timecode_rx: Option<Receiver<Timecode>>,
-    connection: Option<MidiInputConnection<()>>,
-    midi_in: MidiInput,
-    connected: bool,
+    pub input: MidiInputListener,
+    /// Keeps the timecode counting if quarter frames stop arriving
+    pub flywheel: Flywheel,
+    /// Positions and errors from the MIDI thread
+    timecode_rx: Option<Receiver<MtcEvent>>,
+    /// Everything about the signal that isn't tracked by the flywheel
+    status: DecoderStatus,
+    /// The most recent transport events and when they were received, newest last
+    transport_history: VecDeque<(Instant, TransportEvent)>,
+    /// When the last beat clock tick was received and the tempo it was running at
+    clock: Option<(Instant, Option<f64>)>,
 }
 
 impl MtcTimecodeDecoder {
+    /// Creates a new, disconnected, MTC decoder.
+    ///
+    /// # Errors
+    /// Fails if the system MIDI backend could not be initialised
     pub fn new() -> Result<Self> {
-        let mut midi_in = MidiInput::new("technus timecode reading input check ports")?;
-        midi_in.ignore(Ignore::None);
-
         Ok(Self {
-            fps: 0,
-            port: None,
+            input: MidiInputListener::new("technus timecode MTC input")?,
+            flywheel: Flywheel::default(),
             timecode_rx: None,
-            connection: None,
-            midi_in,
-            connected: false,
+            status: DecoderStatus::default(),
+            transport_history: VecDeque::new(),
+            clock: None,
         })
     }
 
-    /// Gets the currently active ports on the system. This is an I/O operation so don't spam it
-    pub fn get_ports(&self) -> Vec<MidiInputPort> {
-        self.midi_in.ports()
-    }
-
-    pub fn port_name(&self, port: &MidiInputPort) -> Result<String> {
-        Ok(self.midi_in.port_name(port)?)
-    }
-
-    /// Connect to the specified MIDI port and start updating timecode.
+    /// Connect to the selected MIDI port and start updating timecode.
+    ///
+    /// # Errors
+    /// Fails if no port has been selected or the connection could not be opened
     pub fn connect(&mut self) -> Result<()> {
-        let mut midi_in = MidiInput::new("technus timecode reading midi timecode input")?;
-        midi_in.ignore(Ignore::None);
-
-        let port = self
-            .port
-            .take()
-            .ok_or_else(|| anyhow!("No port specified, cannot open connection"))?;
-
-        // Once port is taken for a connection, can no longer be in struct
-        self.port = None;
-
         // Create a new buffer for the received TimeCodeQuarterFrames
         let mut quarter_frame_buffer = TimeCodeQuarterFrameBuffer::new();
+        let mut clock_tempo = ClockTempo::default();
 
         // Create a channel to send the timecode values from the closure back to our function to get current timecode
         let (tx, rx) = mpsc::channel();
-        self.timecode_rx = Some(rx);
 
-        // Connect to the midi port and (for now) just print everything that comes through it
-        let connection = midi_in
-            .connect(
-                &port,
-                "technus-timecode-read-input",
-                move |_, message, _| {
-                    let (parsed_message, _) = match MidiMsg::from_midi(&message) {
-                        Ok((msg, err)) => (msg, err),
-                        Err(_) => return,
-                    };
-
-                    // Add the message to the TimeCodeQuarterFrameBuffer (ignores every message type
-                    // other than TimeCodeQuarterFrameX)
-                    quarter_frame_buffer.add(parsed_message);
-
-                    // Construct a timecode if possible
-                    let maybe_timecode = quarter_frame_buffer.construct_timecode();
-
-                    // When we got a timecode, print it out
-                    if let Some(tc) = maybe_timecode {
-                        // Send the timecode
-                        tx.send(tc.into()).expect("MTC Timecode pipe receiving end has been destroyed, cannot send timecode");
-                    }
-                },
-                (),
-            )
-            .map_err(|e| anyhow!("Failed to connect to MIDI port: {e}"))?;
+        self.input.connect(move |timestamp, message| {
+            let send = |event| {
+                tx.send(event).ok();
+            };
 
-        self.connection = Some(connection);
+            let parsed_message = MidiMsg::from_midi(message).ok().map(|(parsed, _)| parsed);
+
+            // Transport commands come along with the timecode, so show why a machine stopped or located
+            let transport = TransportEvent::from_midi(message, parsed_message.as_ref());
+            let is_transport = !transport.is_empty();
+            for event in transport {
+                send(MtcEvent::Transport(event));
+            }
 
-        self.connected = true;
+            let Some(parsed_message) = parsed_message else {
+                if !is_transport {
+                    send(MtcEvent::Malformed);
+                }
+                return;
+            };
+
+            if is_clock(&parsed_message) {
+                send(MtcEvent::Clock {
+                    tempo: clock_tempo.tick(timestamp),
+                });
+                return;
+            }
+
+            // A full-frame message is sent after a locate, publish it straight away rather than
+            // waiting for eight more quarter frames. Any pieces received before the jump are stale
+            if let Some(tc) = full_frame_timecode(&parsed_message) {
+                quarter_frame_buffer.clear();
+                let position = TimecodePosition::from(Timecode::from(tc));
+                send(MtcEvent::Position {
+                    position,
+                    direction: None,
+                    measured_fps: None,
+                });
+                return;
+            }
+
+            // Add the message to the TimeCodeQuarterFrameBuffer (ignores every message type
+            // other than TimeCodeQuarterFrameX), once it has a full timecode every quarter frame moves it on
+            let dropouts = quarter_frame_buffer.dropouts();
+            let added = quarter_frame_buffer.add(&parsed_message, timestamp);
+
+            if quarter_frame_buffer.dropouts() > dropouts {
+                send(MtcEvent::Dropout);
+            }
+
+            if let Some((position, direction)) = added {
+                send(MtcEvent::Position {
+                    position,
+                    direction: Some(direction),
+                    measured_fps: quarter_frame_buffer.measured_fps(),
+                });
+            }
+        })?;
+
+        self.timecode_rx = Some(rx);
+        self.status.errors = ErrorCounters::default();
 
         Ok(())
     }
 
     /// Closes the currently open connection
+    ///
+    /// # Errors
     /// Result will be Err if there is no connection currently open
     pub fn disconnect(&mut self) -> Result<()> {
-        let connection = self
-            .connection
-            .take()
-            .ok_or_else(|| anyhow!("No connection is open, hence you cannot close it"))?;
-
-        connection.close();
+        self.input.disconnect()?;
 
-        self.connected = false;
-
-        // After closing the connection, it makes no sense to keep the old one around
-        self.connection = None;
+        self.timecode_rx = None;
+        self.status.direction = None;
+        self.status.measured_fps = None;
+        self.clock = None;
+        self.flywheel.reset();
 
         Ok(())
     }
 
     /// Returns true if there has been a connection made.
     pub fn connected(&self) -> bool {
-        self.connected
+        self.input.connected()
+    }
+
+    /// The most recent transport events, oldest first
+    pub fn transport_history(&self) -> impl DoubleEndedIterator<Item = &(Instant, TransportEvent)> {
+        self.transport_history.iter()
+    }
+
+    /// The tempo of the beat clock in BPM, None if there is no clock or it hasn't been measured yet
+    pub fn clock_tempo(&self) -> Option<f64> {
+        let (_, tempo) = self.clock?;
+        tempo
+    }
+
+    /// Remembers a transport event
+    fn record_transport(&mut self, event: TransportEvent, now: Instant) {
+        log::info!("MTC input transport: {event}");
+
+        self.transport_history.push_back((now, event));
+        if self.transport_history.len() > TRANSPORT_HISTORY_LENGTH {
+            self.transport_history.pop_front();
+        }
+    }
+}
+
+/// Gets the timecode out of a Universal Real-Time full-frame message (`F0 7F cc 01 01 hh mm ss ff F7`),
+/// the rate is carried in the top bits of the hours byte
+fn full_frame_timecode(message: &MidiMsg) -> Option<TimeCode> {
+    match *message {
+        MidiMsg::SystemExclusive {
+            msg:
+                SystemExclusiveMsg::UniversalRealTime {
+                    msg: UniversalRealTimeMsg::TimeCodeFull(tc),
+                    ..
+                },
+        } => Some(tc),
+        _ => None,
     }
 }
 
 impl TimecodeDecoder for MtcTimecodeDecoder {
-    /// Gets the current timecode value from MTC. If no new value is available then it returns None
-    fn get_current_timecode(&mut self) -> Option<Timecode> {
+    /// Gets the current position from MTC. If no new value is available then it returns None,
+    /// unless the flywheel is counting on over a dropout
+    fn get_current_position(&mut self) -> Option<TimecodePosition> {
         // Get the receiver, if we can't get it then just return the current timecode value
-        let rx = match &self.timecode_rx {
-            Some(rx) => rx,
-            None => {
-                return None;
+        let rx = self.timecode_rx.as_ref()?;
+        let now = Instant::now();
+
+        // Positions arrive every quarter frame which can be faster than we are asked for them,
+        // so count up the errors and skip straight to the newest one
+        let mut latest = None;
+        let events: Vec<_> = rx.try_iter().collect();
+        for event in events {
+            match event {
+                MtcEvent::Position {
+                    position,
+                    direction,
+                    measured_fps,
+                } => {
+                    latest = Some((position, direction));
+                    self.status.measured_fps = measured_fps;
+                }
+                MtcEvent::Malformed => self.status.errors.malformed += 1,
+                MtcEvent::Dropout => self.status.errors.dropouts += 1,
+                MtcEvent::Transport(event) => self.record_transport(event, now),
+                MtcEvent::Clock { tempo } => {
+                    if self.clock.is_none() {
+                        self.record_transport(TransportEvent::ClockStarted, now);
+                    }
+                    self.clock = Some((now, tempo));
+                }
             }
+        }
+
+        if let Some((last_tick, _)) = self.clock
+            && now.saturating_duration_since(last_tick) > CLOCK_TIMEOUT
+        {
+            self.clock = None;
+            self.record_transport(TransportEvent::ClockStopped, now);
+        }
+
+        let Some((position, direction)) = latest else {
+            return self.flywheel.poll(now);
         };
 
-        // Try to get data from the channel
-        if let Ok(tc) = rx.try_recv() {
-            return Some(tc);
-        } else {
-            return None;
+        self.status.direction = direction;
+        self.status.rate = Some(position.timecode.rate());
+        self.flywheel.update(position, direction, now);
+
+        Some(position)
+    }
+
+    fn status(&self) -> DecoderStatus {
+        DecoderStatus {
+            connection: if self.connected() {
+                ConnectionState::Connected
+            } else {
+                ConnectionState::Disconnected
+            },
+            signal: self.flywheel.state(),
+            last_received: self.flywheel.last_received(),
+            ..self.status
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{frame_rate::FrameRate, timecode::Timecode};
+
+    #[test]
+    fn test_full_frame_drop_frame() {
+        let bytes = [0xF0, 0x7F, 0x7F, 0x01, 0x01, 0x41, 0x02, 0x03, 0x04, 0xF7];
+        let (message, _) = MidiMsg::from_midi(&bytes).expect("Valid full-frame message");
+
+        let timecode = full_frame_timecode(&message).map(Timecode::from);
+
+        assert_eq!(
+            timecode,
+            Some(Timecode::new(1, 2, 3, 4, FrameRate::Fps29_97Df))
+        );
+    }
+
+    #[test]
+    fn test_full_frame_25fps_other_device() {
+        let bytes = [0xF0, 0x7F, 0x01, 0x01, 0x01, 0x37, 0x3B, 0x3B, 0x18, 0xF7];
+        let (message, _) = MidiMsg::from_midi(&bytes).expect("Valid full-frame message");
+
+        let timecode = full_frame_timecode(&message).map(Timecode::from);
+
+        assert_eq!(
+            timecode,
+            Some(Timecode::new(23, 59, 59, 24, FrameRate::Fps25))
+        );
+    }
+
+    #[test]
+    fn test_quarter_frame_is_not_full_frame() {
+        let (message, _) = MidiMsg::from_midi(&[0xF1, 0x00]).expect("Valid quarter frame");
+
+        assert_eq!(full_frame_timecode(&message), None);
+    }
+}
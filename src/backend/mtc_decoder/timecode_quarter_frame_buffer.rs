@@ -1,62 +1,201 @@
 use midi_msg::{MidiMsg, SystemCommonMsg, TimeCode};
 
-/// A buffer for TimeCodeQuarterFrameX from which a full TimeCode can
-/// be constructed when all 8 Quartes have been received
-/// Full credit to: https://github.com/AlexCharlton/midi-msg/blob/master/examples/read_mtc_from_input.rs
+use crate::backend::{
+    frame_rate::FrameRate,
+    timecode::Timecode,
+    timecode_decoder::{PlaybackDirection, TimecodePosition},
+};
+
+/// MTC describes the frame at which the first quarter frame of a sequence was sent,
+/// by the time the next sequence starts two more frames have passed
+const SEQUENCE_LENGTH_FRAMES: u32 = 2;
+
+/// How much each new quarter frame interval counts towards the measured rate
+const RATE_SMOOTHING: f64 = 0.1;
+
+/// A state machine for `TimeCodeQuarterFrameX` messages from which a full `TimeCode` can
+/// be constructed when all 8 Quarters have been received in order.
+///
+/// Going forwards the pieces arrive 0 to 7, going backwards they arrive 7 to 0. Any gap or
+/// change of direction part way through a sequence throws away the pieces collected so far.
+/// Once a sequence has completed, every following piece moves the position on by a quarter of a frame.
+/// Based on: <https://github.com/AlexCharlton/midi-msg/blob/master/examples/read_mtc_from_input.rs>
 pub struct TimeCodeQuarterFrameBuffer {
     buffer: [Option<TimeCode>; 8],
+    /// How many pieces of the current sequence have been received in order
+    received: usize,
+    /// The last piece received, used to check the next one follows on from it
+    last_piece: Option<usize>,
+    /// The MIDI timestamp of the last piece received, in microseconds
+    last_timestamp: Option<u64>,
+    /// The direction the pieces are arriving in, None until two in a row have been seen
+    direction: Option<PlaybackDirection>,
+    /// The timecode the following sequence starts at, and how many quarter frames we are from it
+    anchor: Option<(Timecode, i64)>,
+    /// The smoothed time between quarter frames while locked, in microseconds
+    quarter_frame_interval: Option<f64>,
+    /// How many times pieces went missing, either part way through a sequence or while locked
+    dropouts: u32,
 }
 
 impl TimeCodeQuarterFrameBuffer {
-    /// Return a new empty TimeCodeQuarterFrameBuffer
+    /// Return a new empty `TimeCodeQuarterFrameBuffer`
     pub fn new() -> Self {
-        Self { buffer: [None; 8] }
-    }
-
-    /// Add a TimeCodeQuarterFrameX to the buffer, replacing the old one
-    pub fn add(&mut self, message: MidiMsg) {
-        if let MidiMsg::SystemCommon { msg } = message {
-            // Get the target index and timecode from the TimeCodeQuarterFrameX
-            let (index, tc) = match msg {
-                SystemCommonMsg::TimeCodeQuarterFrame1(tc) => (0, tc),
-                SystemCommonMsg::TimeCodeQuarterFrame2(tc) => (1, tc),
-                SystemCommonMsg::TimeCodeQuarterFrame3(tc) => (2, tc),
-                SystemCommonMsg::TimeCodeQuarterFrame4(tc) => (3, tc),
-                SystemCommonMsg::TimeCodeQuarterFrame5(tc) => (4, tc),
-                SystemCommonMsg::TimeCodeQuarterFrame6(tc) => (5, tc),
-                SystemCommonMsg::TimeCodeQuarterFrame7(tc) => (6, tc),
-                SystemCommonMsg::TimeCodeQuarterFrame8(tc) => (7, tc),
-                _ => return,
+        Self {
+            buffer: [None; 8],
+            received: 0,
+            last_piece: None,
+            last_timestamp: None,
+            direction: None,
+            anchor: None,
+            quarter_frame_interval: None,
+            dropouts: 0,
+        }
+    }
+
+    /// Throw away any quarter frames received so far, E.g: after a locate
+    pub fn clear(&mut self) {
+        self.buffer = [None; 8];
+        self.received = 0;
+        self.last_piece = None;
+        self.last_timestamp = None;
+        self.direction = None;
+        self.anchor = None;
+        self.quarter_frame_interval = None;
+    }
+
+    /// How many times pieces have gone missing since the buffer was created, a locate doesn't count
+    pub fn dropouts(&self) -> u32 {
+        self.dropouts
+    }
+
+    /// The frame rate the quarter frames are actually arriving at, None until a sequence has been received
+    pub fn measured_fps(&self) -> Option<f64> {
+        self.quarter_frame_interval
+            .map(|interval| 1_000_000.0 / (interval * 4.0))
+    }
+
+    /// Add a `TimeCodeQuarterFrameX` to the buffer, `timestamp` is when it arrived in microseconds.
+    /// Once a full sequence has been received, every piece returns the position the source is at
+    /// along with the direction of playback. Every other message type is ignored
+    pub fn add(
+        &mut self,
+        message: &MidiMsg,
+        timestamp: u64,
+    ) -> Option<(TimecodePosition, PlaybackDirection)> {
+        let (piece, tc) = quarter_frame(message)?;
+
+        // Quarter frames arrive four times a frame, a longer gap means the source stopped or jumped
+        if let (Some(last_timestamp), Some((anchor, _))) = (self.last_timestamp, self.anchor)
+            && timestamp.saturating_sub(last_timestamp) > frame_length_micros(anchor.rate())
+        {
+            self.clear();
+            self.dropouts += 1;
+        }
+        let last_timestamp = self.last_timestamp.replace(timestamp);
+
+        // Work out which way we are going from how this piece follows on from the last one
+        let direction = match self.last_piece.map(|last| (piece + 8 - last) % 8) {
+            Some(1) => Some(PlaybackDirection::Forward),
+            Some(7) => Some(PlaybackDirection::Reverse),
+            // Either this is the first piece we've seen, or some were lost in between
+            _ => None,
+        };
+
+        // Pieces went missing in between, rather than this being the first one
+        if direction.is_none() && self.last_piece.is_some() {
+            self.dropouts += 1;
+        }
+        self.last_piece = Some(piece);
+
+        // Only time the pieces once locked, the gap check above has then ruled out a stopped source
+        if let (Some(last_timestamp), Some(_), Some(_)) = (last_timestamp, direction, self.anchor) {
+            let interval = timestamp.saturating_sub(last_timestamp) as f64;
+            self.quarter_frame_interval = Some(match self.quarter_frame_interval {
+                Some(smoothed) => smoothed + (interval - smoothed) * RATE_SMOOTHING,
+                None => interval,
+            });
+        }
+
+        // Losing track of the order, or turning around, means the pieces collected so far are torn
+        let turned_around = self.direction.is_some() && direction != self.direction;
+        if direction.is_none() || turned_around {
+            self.start_sequence();
+            self.anchor = None;
+        }
+        self.direction = direction;
+
+        // A sequence starts on piece 0 going forwards and on piece 7 going backwards
+        let (first_piece, last_piece) = match direction {
+            Some(PlaybackDirection::Forward) => (Some(0), 7),
+            Some(PlaybackDirection::Reverse) => (Some(7), 0),
+            None => (None, 8),
+        };
+        if first_piece == Some(piece) || (direction.is_none() && (piece == 0 || piece == 7)) {
+            self.start_sequence();
+        }
+
+        *self.buffer.get_mut(piece)? = Some(tc);
+        self.received += 1;
+
+        let direction = direction?;
+        let completed = if piece == last_piece && self.received == 8 {
+            self.construct_timecode()
+        } else {
+            None
+        };
+
+        if let Some(tc) = completed {
+            // The next sequence starts two frames on from this one, and we are a quarter frame short of it
+            let timecode = Timecode::from(tc);
+            self.anchor = Some(match direction {
+                PlaybackDirection::Forward => (timecode + SEQUENCE_LENGTH_FRAMES, -1),
+                PlaybackDirection::Reverse => (timecode - SEQUENCE_LENGTH_FRAMES, 1),
+            });
+            self.start_sequence();
+        } else if let Some((_, quarters)) = &mut self.anchor {
+            *quarters += match direction {
+                PlaybackDirection::Forward => 1,
+                PlaybackDirection::Reverse => -1,
             };
-            // Store the fitting tc at the matching position if is None
-            if self.buffer[index].is_none() {
-                self.buffer[index] = Some(tc);
-            }
         }
+
+        let (anchor, quarters) = self.anchor?;
+
+        Some((quarters_from(anchor, quarters), direction))
     }
 
-    /// Return true if all values in the buffer have a value, false otherwise
-    fn is_filled(&self) -> bool {
-        self.buffer.iter().all(|b| b.is_some())
+    /// Empty the buffer ready for the next sequence, keeping track of order and direction
+    fn start_sequence(&mut self) {
+        self.buffer = [None; 8];
+        self.received = 0;
     }
 
-    /// Construct a Timecode from the TimeCodeQuarterFrames if possible
-    pub fn construct_timecode(&mut self) -> Option<TimeCode> {
+    /// Construct a `TimeCode` from the `TimeCodeQuarterFrames` if possible
+    fn construct_timecode(&self) -> Option<TimeCode> {
         // If the Buffer is not ready, return None
-        if !self.is_filled() {
+        let [
+            Some(frames_low),
+            Some(frames_high),
+            Some(seconds_low),
+            Some(seconds_high),
+            Some(minutes_low),
+            Some(minutes_high),
+            Some(hours_low),
+            Some(hours_high),
+        ] = self.buffer
+        else {
             return None;
-        }
+        };
         // Combine the 4 bit nibbles of the pairs of TimeCode
         // E.g. the low nibble of the frames: u8 stored in TimeCodeQuarter1
         // and the high nibble of frames u8 stored in TimeCodeQuarter2
-        let frames: u8 = self.buffer[0].unwrap().frames ^ self.buffer[1].unwrap().frames;
-        let seconds: u8 = self.buffer[2].unwrap().seconds ^ self.buffer[3].unwrap().seconds;
-        let minutes: u8 = self.buffer[4].unwrap().minutes ^ self.buffer[5].unwrap().minutes;
-        let hours: u8 = self.buffer[6].unwrap().hours ^ self.buffer[7].unwrap().hours;
+        let frames: u8 = frames_low.frames ^ frames_high.frames;
+        let seconds: u8 = seconds_low.seconds ^ seconds_high.seconds;
+        let minutes: u8 = minutes_low.minutes ^ minutes_high.minutes;
+        let hours: u8 = hours_low.hours ^ hours_high.hours;
         // The last high TimeCodeQuarter contains the propper code_type so extract it from there
-        let code_type = self.buffer[7].unwrap().code_type;
-        // Empty the buffer
-        self.buffer = [None; 8];
+        let code_type = hours_high.code_type;
         // Construct and return the TimeCode
         Some(TimeCode {
             frames,
@@ -67,3 +206,246 @@ impl TimeCodeQuarterFrameBuffer {
         })
     }
 }
+
+impl Default for TimeCodeQuarterFrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The length of one frame at the given rate, in microseconds
+fn frame_length_micros(rate: FrameRate) -> u64 {
+    let (numerator, denominator) = rate.as_ratio();
+
+    1_000_000 * u64::from(denominator) / u64::from(numerator)
+}
+
+/// The position a number of quarter frames away from a timecode
+fn quarters_from(timecode: Timecode, quarters: i64) -> TimecodePosition {
+    let quarters_per_day = i64::from(timecode.rate().frames_per_day()) * 4;
+    let total = (i64::from(timecode.frame_count()) * 4 + quarters).rem_euclid(quarters_per_day);
+
+    TimecodePosition {
+        timecode: Timecode::from_frame_count((total / 4) as u32, timecode.rate()),
+        subframe: (total % 4) as f64 / 4.0,
+    }
+}
+
+/// Get the piece number and partial timecode out of a `TimeCodeQuarterFrameX`
+fn quarter_frame(message: &MidiMsg) -> Option<(usize, TimeCode)> {
+    let MidiMsg::SystemCommon { msg } = *message else {
+        return None;
+    };
+
+    match msg {
+        SystemCommonMsg::TimeCodeQuarterFrame1(tc) => Some((0, tc)),
+        SystemCommonMsg::TimeCodeQuarterFrame2(tc) => Some((1, tc)),
+        SystemCommonMsg::TimeCodeQuarterFrame3(tc) => Some((2, tc)),
+        SystemCommonMsg::TimeCodeQuarterFrame4(tc) => Some((3, tc)),
+        SystemCommonMsg::TimeCodeQuarterFrame5(tc) => Some((4, tc)),
+        SystemCommonMsg::TimeCodeQuarterFrame6(tc) => Some((5, tc)),
+        SystemCommonMsg::TimeCodeQuarterFrame7(tc) => Some((6, tc)),
+        SystemCommonMsg::TimeCodeQuarterFrame8(tc) => Some((7, tc)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use midi_msg::TimeCodeType;
+
+    use super::*;
+    use crate::backend::frame_rate::FrameRate;
+
+    /// The eight quarter frame messages for a timecode, in forward order
+    fn quarter_frames(hours: u8, minutes: u8, seconds: u8, frames: u8) -> Vec<MidiMsg> {
+        let tc = TimeCode {
+            frames,
+            seconds,
+            minutes,
+            hours,
+            code_type: TimeCodeType::FPS25,
+        };
+
+        tc.to_nibbles()
+            .iter()
+            .map(|nibble| {
+                MidiMsg::from_midi(&[0xF1, *nibble])
+                    .expect("Valid quarter frame")
+                    .0
+            })
+            .collect()
+    }
+
+    /// Feeds messages into the buffer a quarter frame (10ms at 25fps) apart
+    fn add_all<'a>(
+        buffer: &mut TimeCodeQuarterFrameBuffer,
+        messages: impl Iterator<Item = &'a MidiMsg>,
+    ) -> Vec<(TimecodePosition, PlaybackDirection)> {
+        messages
+            .zip((0..).map(|quarter| quarter * 10_000))
+            .filter_map(|(message, timestamp)| buffer.add(message, timestamp))
+            .collect()
+    }
+
+    fn position(frames: u8, subframe: f64) -> TimecodePosition {
+        TimecodePosition {
+            subframe,
+            ..Timecode::new(1, 2, 3, frames, FrameRate::Fps25).into()
+        }
+    }
+
+    #[test]
+    fn test_forward_sequence_is_nearly_two_frames_on() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let messages = quarter_frames(1, 2, 3, 4);
+
+        let positions = add_all(&mut buffer, messages.iter());
+
+        assert_eq!(
+            positions,
+            vec![(position(5, 0.75), PlaybackDirection::Forward)]
+        );
+    }
+
+    #[test]
+    fn test_reverse_sequence_is_nearly_two_frames_back() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let messages = quarter_frames(1, 2, 3, 4);
+
+        let positions = add_all(&mut buffer, messages.iter().rev());
+
+        assert_eq!(
+            positions,
+            vec![(position(2, 0.25), PlaybackDirection::Reverse)]
+        );
+    }
+
+    #[test]
+    fn test_every_quarter_frame_after_a_sequence_is_published() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let first = quarter_frames(1, 2, 3, 4);
+        let second = quarter_frames(1, 2, 3, 6);
+
+        let positions = add_all(&mut buffer, first.iter().chain(second.iter()));
+
+        assert_eq!(
+            positions,
+            vec![
+                (position(5, 0.75), PlaybackDirection::Forward),
+                (position(6, 0.0), PlaybackDirection::Forward),
+                (position(6, 0.25), PlaybackDirection::Forward),
+                (position(6, 0.5), PlaybackDirection::Forward),
+                (position(6, 0.75), PlaybackDirection::Forward),
+                (position(7, 0.0), PlaybackDirection::Forward),
+                (position(7, 0.25), PlaybackDirection::Forward),
+                (position(7, 0.5), PlaybackDirection::Forward),
+                (position(7, 0.75), PlaybackDirection::Forward),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_torn_sequence_is_discarded() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let messages = quarter_frames(1, 2, 3, 4);
+
+        // Lose piece 4
+        let positions = add_all(
+            &mut buffer,
+            messages
+                .iter()
+                .enumerate()
+                .filter(|(piece, _)| *piece != 4)
+                .map(|(_, message)| message),
+        );
+
+        assert_eq!(positions, vec![]);
+    }
+
+    #[test]
+    fn test_partial_sequence_waits_for_next_start() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let first = quarter_frames(1, 2, 3, 4);
+        let second = quarter_frames(1, 2, 3, 6);
+
+        let positions = add_all(&mut buffer, first.iter().skip(3).chain(second.iter()));
+
+        assert_eq!(
+            positions,
+            vec![(position(7, 0.75), PlaybackDirection::Forward)]
+        );
+    }
+
+    #[test]
+    fn test_change_of_direction_discards_sequence() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let messages = quarter_frames(1, 2, 3, 4);
+
+        // Go forwards to piece 5 then turn around, the reverse sequence never starts at piece 7
+        let positions = add_all(
+            &mut buffer,
+            messages.iter().take(6).chain(messages.iter().take(5).rev()),
+        );
+
+        assert_eq!(positions, vec![]);
+    }
+
+    #[test]
+    fn test_gap_in_arrival_stops_interpolating() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let messages = quarter_frames(1, 2, 3, 4);
+
+        let positions = add_all(&mut buffer, messages.iter());
+        assert_eq!(positions.len(), 1);
+
+        // The next piece 0 turns up a second later
+        let late = messages
+            .first()
+            .and_then(|message| buffer.add(message, 1_070_000));
+
+        assert_eq!(late, None);
+    }
+
+    #[test]
+    fn test_measured_fps() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let first = quarter_frames(1, 2, 3, 4);
+        let second = quarter_frames(1, 2, 3, 6);
+
+        add_all(&mut buffer, first.iter().chain(second.iter()));
+
+        assert_eq!(buffer.measured_fps(), Some(25.0));
+    }
+
+    #[test]
+    fn test_missing_pieces_are_counted() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let messages = quarter_frames(1, 2, 3, 4);
+
+        // Lose pieces 2 and 5
+        add_all(
+            &mut buffer,
+            messages
+                .iter()
+                .enumerate()
+                .filter(|(piece, _)| *piece != 2 && *piece != 5)
+                .map(|(_, message)| message),
+        );
+
+        assert_eq!(buffer.dropouts(), 2);
+    }
+
+    #[test]
+    fn test_locate_is_not_a_dropout() {
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let messages = quarter_frames(1, 2, 3, 4);
+
+        add_all(&mut buffer, messages.iter());
+        buffer.clear();
+        add_all(&mut buffer, messages.iter());
+
+        assert_eq!(buffer.dropouts(), 0);
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, anyhow};
+use midir::{MidiOutput, MidiOutputConnection, MidiOutputPort};
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    mtc_generator::mtc_encoder::MtcEncoder,
+    timecode::Timecode,
+    timecode_decoder::{MAX_EXTRAPOLATION_FRAMES, PlaybackDirection, TimecodePosition},
+};
+
+mod mtc_encoder;
+
+/// How long the MIDI thread waits for a new position when there is nothing to send
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+/// What the generator tells the MIDI thread
+enum MtcCommand {
+    /// Jump to a position and send a full frame for it
+    Locate {
+        position: TimecodePosition,
+        direction: Option<PlaybackDirection>,
+    },
+    /// The latest position from the source being chased
+    Follow {
+        position: TimecodePosition,
+        direction: Option<PlaybackDirection>,
+    },
+}
+
+/// Sends MTC out of a MIDI port, as a master clock or to pass on another source
+pub struct MtcGenerator {
+    pub port: Option<MidiOutputPort>,
+    /// Positions for the MIDI thread to send, dropping this stops it
+    command_tx: Option<Sender<MtcCommand>>,
+    last_position: Option<TimecodePosition>,
+    rate: Option<FrameRate>,
+    thread: Option<JoinHandle<()>>,
+    midi_out: MidiOutput,
+}
+
+impl MtcGenerator {
+    /// Creates a new, stopped, MTC generator.
+    ///
+    /// # Errors
+    /// Fails if the system MIDI backend could not be initialised
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            port: None,
+            command_tx: None,
+            last_position: None,
+            rate: None,
+            thread: None,
+            midi_out: MidiOutput::new("technus timecode writing output check ports")?,
+        })
+    }
+
+    /// Gets the currently active ports on the system. This is an I/O operation so don't spam it
+    pub fn get_ports(&self) -> Vec<MidiOutputPort> {
+        self.midi_out.ports()
+    }
+
+    /// Gets the human readable name of a port.
+    ///
+    /// # Errors
+    /// Fails if the port is no longer available
+    pub fn port_name(&self, port: &MidiOutputPort) -> Result<String> {
+        Ok(self.midi_out.port_name(port)?)
+    }
+
+    /// Start sending MTC at the closest rate MTC has to `rate`. When `free_running` the generator is its own clock
+    /// and counts on from wherever it is located to, otherwise it follows positions passed to [`Self::update`].
+    /// Nothing is sent until the first locate or update
+    ///
+    /// # Errors
+    /// Fails if no port has been selected or the connection could not be opened
+    pub fn start(&mut self, rate: FrameRate, free_running: bool) -> Result<()> {
+        let port = self
+            .port
+            .as_ref()
+            .ok_or_else(|| anyhow!("No port specified, cannot open connection"))?;
+
+        let connection = MidiOutput::new("technus timecode writing midi timecode output")?
+            .connect(port, "technus-timecode-write-output")
+            .map_err(|e| anyhow!("Failed to connect to MIDI port: {e}"))?;
+
+        let extrapolation_limit = if free_running {
+            None
+        } else {
+            Some(MAX_EXTRAPOLATION_FRAMES)
+        };
+        let encoder = MtcEncoder::new(rate, extrapolation_limit);
+        self.rate = Some(encoder.rate());
+
+        let (tx, rx) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name("MTC generator".to_owned())
+            .spawn(move || send_mtc(connection, encoder, &rx))?;
+
+        self.command_tx = Some(tx);
+        self.thread = Some(thread);
+
+        Ok(())
+    }
+
+    /// Stops sending MTC and closes the port
+    ///
+    /// # Errors
+    /// Result will be Err if the generator isn't running
+    pub fn stop(&mut self) -> Result<()> {
+        let thread = self
+            .thread
+            .take()
+            .ok_or_else(|| anyhow!("The generator isn't running, hence you cannot stop it"))?;
+
+        // Hanging up on the MIDI thread tells it to finish
+        self.command_tx = None;
+        self.last_position = None;
+        self.rate = None;
+        thread
+            .join()
+            .map_err(|_panic| anyhow!("The MTC generator thread panicked"))?;
+
+        Ok(())
+    }
+
+    /// Returns true if MTC is being sent
+    pub fn running(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    /// The rate MTC is being sent at, None if the generator isn't running
+    pub fn rate(&self) -> Option<FrameRate> {
+        self.rate
+    }
+
+    /// Jump to a timecode, sending a full-frame message so receivers follow straight away.
+    /// Quarter frames carry on from there if `playing`
+    pub fn locate(&mut self, timecode: Timecode, playing: bool) {
+        let position = TimecodePosition::from(timecode);
+        self.last_position = Some(position);
+
+        self.send(MtcCommand::Locate {
+            position,
+            direction: playing.then_some(PlaybackDirection::Forward),
+        });
+    }
+
+    /// Give the generator the latest position from the source it is following, `direction` is None if the source
+    /// isn't playing. The same position twice is ignored, so it is fine to call this however often
+    /// the source is read
+    pub fn update(&mut self, position: TimecodePosition, direction: Option<PlaybackDirection>) {
+        if self.last_position == Some(position) {
+            return;
+        }
+        self.last_position = Some(position);
+
+        self.send(MtcCommand::Follow {
+            position,
+            direction,
+        });
+    }
+
+    fn send(&self, command: MtcCommand) {
+        if let Some(tx) = &self.command_tx
+            && tx.send(command).is_err()
+        {
+            log::error!("MTC generator thread has stopped unexpectedly");
+        }
+    }
+}
+
+/// Sends every message as soon as it is due until the generator hangs up, runs on its own thread
+fn send_mtc(
+    mut connection: MidiOutputConnection,
+    mut encoder: MtcEncoder,
+    rx: &Receiver<MtcCommand>,
+) {
+    loop {
+        let timeout = encoder.next_deadline().map_or(IDLE_WAIT, |deadline| {
+            deadline.saturating_duration_since(Instant::now())
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(MtcCommand::Locate {
+                position,
+                direction,
+            }) => encoder.locate(position, direction, Instant::now()),
+            Ok(MtcCommand::Follow {
+                position,
+                direction,
+            }) => encoder.update(position, direction, Instant::now()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                connection.close();
+                return;
+            }
+        }
+
+        for message in encoder.poll(Instant::now()) {
+            if let Err(e) = connection.send(&message) {
+                log::error!("Failed to send MTC: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use std::time::{Duration, Instant};
+
+use midi_msg::{DeviceID, MidiMsg, SystemExclusiveMsg, TimeCode, UniversalRealTimeMsg};
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    timecode::Timecode,
+    timecode_decoder::{PlaybackDirection, TimecodePosition},
+};
+
+/// How far a source can be from where we thought it would be before it counts as a locate
+const JUMP_THRESHOLD_FRAMES: f64 = 2.0;
+
+/// How many quarter frames we can fall behind by before giving up on sending them all and skipping ahead
+const MAX_CATCH_UP_QUARTERS: i64 = 8;
+
+/// Works out which MTC messages to send and when.
+///
+/// Quarter frames are numbered from 00:00:00:00, so piece 0 always lands on the start of an even frame
+/// and carries the timecode of that frame, like a hardware generator
+pub struct MtcEncoder {
+    rate: FrameRate,
+    /// Where the clock was at a point in time, in frames since 00:00:00:00 at `rate`
+    anchor: Option<(f64, Instant)>,
+    direction: Option<PlaybackDirection>,
+    /// How many frames to count on for after the last position, None to count on forever
+    extrapolation_limit: Option<f64>,
+    /// The quarter frame to send next, None if we haven't started sending
+    next_quarter: Option<i64>,
+    /// A full-frame message is due after a locate
+    full_frame_pending: bool,
+}
+
+impl MtcEncoder {
+    /// Creates an encoder sending at the MTC rate closest to `rate`. `extrapolation_limit` is how many frames to count
+    /// on by ourselves after the last position, None to run freely
+    pub fn new(rate: FrameRate, extrapolation_limit: Option<f64>) -> Self {
+        Self {
+            rate: midi_msg::TimeCodeType::from(rate).into(),
+            anchor: None,
+            direction: None,
+            extrapolation_limit,
+            next_quarter: None,
+            full_frame_pending: false,
+        }
+    }
+
+    /// Jump to a position, sending a full-frame message for it. `direction` is None to park there
+    pub fn locate(
+        &mut self,
+        position: TimecodePosition,
+        direction: Option<PlaybackDirection>,
+        now: Instant,
+    ) {
+        self.anchor = Some((self.frames(position), now));
+        self.direction = direction;
+        self.next_quarter = None;
+        self.full_frame_pending = true;
+    }
+
+    /// Follow a new position from a source. Small differences keep the quarter frames running,
+    /// a jump or a change of direction is sent as a locate
+    pub fn update(
+        &mut self,
+        position: TimecodePosition,
+        direction: Option<PlaybackDirection>,
+        now: Instant,
+    ) {
+        let frames = self.frames(position);
+        let jumped = self.position_at(now).is_none_or(|expected| {
+            circular_difference(frames, expected, self.rate).abs() > JUMP_THRESHOLD_FRAMES
+        });
+
+        if jumped || direction != self.direction {
+            self.locate(position, direction, now);
+        } else {
+            self.anchor = Some((frames, now));
+        }
+    }
+
+    /// The messages that are due by `now`, oldest first
+    pub fn poll(&mut self, now: Instant) -> Vec<Vec<u8>> {
+        let mut messages = vec![];
+
+        let Some(position) = self.position_at(now) else {
+            return messages;
+        };
+
+        if self.full_frame_pending {
+            self.full_frame_pending = false;
+            messages.push(self.full_frame(position));
+        }
+
+        let Some(direction) = self.running_direction(now) else {
+            self.next_quarter = None;
+            return messages;
+        };
+
+        let current_quarter = (position * 4.0).floor() as i64;
+        let next_quarter = *self.next_quarter.get_or_insert(match direction {
+            PlaybackDirection::Forward => (position * 4.0).ceil() as i64,
+            PlaybackDirection::Reverse => current_quarter,
+        });
+
+        // Fell too far behind, E.g: the thread was starved, so rejoin the stream where it is now
+        let next_quarter = if (current_quarter - next_quarter).abs() > MAX_CATCH_UP_QUARTERS {
+            current_quarter
+        } else {
+            next_quarter
+        };
+
+        let mut quarter = next_quarter;
+        let step = match direction {
+            PlaybackDirection::Forward => 1,
+            PlaybackDirection::Reverse => -1,
+        };
+        while is_due(quarter, position, direction) {
+            messages.push(self.quarter_frame(quarter));
+            quarter += step;
+        }
+        self.next_quarter = Some(quarter);
+
+        messages
+    }
+
+    /// When the next message will be due, None if nothing will be sent until there is a new position
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let (anchor_frames, anchor_time) = self.anchor?;
+
+        if self.full_frame_pending {
+            return Some(anchor_time);
+        }
+
+        let direction = self.direction?;
+        let next_quarter = self.next_quarter?;
+
+        let frames_away = match direction {
+            PlaybackDirection::Forward => next_quarter as f64 / 4.0 - anchor_frames,
+            PlaybackDirection::Reverse => anchor_frames - next_quarter as f64 / 4.0,
+        };
+        let deadline =
+            anchor_time + Duration::from_secs_f64(frames_away.max(0.0) / self.rate.as_f64());
+
+        // Stop waking up once we have run out of positions to count on from
+        let limit = self.extrapolation_limit.map_or(Duration::MAX, |limit| {
+            Duration::from_secs_f64(limit / self.rate.as_f64())
+        });
+        (deadline.saturating_duration_since(anchor_time) <= limit).then_some(deadline)
+    }
+
+    /// The rate the encoder is sending at
+    pub fn rate(&self) -> FrameRate {
+        self.rate
+    }
+
+    /// Where the clock is at `now`, in frames since 00:00:00:00
+    fn position_at(&self, now: Instant) -> Option<f64> {
+        let (anchor_frames, anchor_time) = self.anchor?;
+
+        let elapsed_frames = match self.direction {
+            Some(_) => {
+                now.saturating_duration_since(anchor_time).as_secs_f64() * self.rate.as_f64()
+            }
+            None => 0.0,
+        };
+        let elapsed_frames = self
+            .extrapolation_limit
+            .map_or(elapsed_frames, |limit| elapsed_frames.min(limit));
+
+        let frames = match self.direction {
+            Some(PlaybackDirection::Reverse) => anchor_frames - elapsed_frames,
+            _ => anchor_frames + elapsed_frames,
+        };
+
+        Some(frames.rem_euclid(f64::from(self.rate.frames_per_day())))
+    }
+
+    /// The direction the clock is running in, None if it is parked or we have counted on as far as we are allowed
+    fn running_direction(&self, now: Instant) -> Option<PlaybackDirection> {
+        let (_, anchor_time) = self.anchor?;
+        let elapsed_frames =
+            now.saturating_duration_since(anchor_time).as_secs_f64() * self.rate.as_f64();
+
+        self.direction.filter(|_| {
+            self.extrapolation_limit
+                .is_none_or(|limit| elapsed_frames <= limit)
+        })
+    }
+
+    /// A position in frames since 00:00:00:00 at our rate
+    fn frames(&self, position: TimecodePosition) -> f64 {
+        f64::from(position.timecode.relabel(self.rate).frame_count()) + position.subframe
+    }
+
+    fn timecode(&self, frame_count: i64) -> TimeCode {
+        let frames_per_day = i64::from(self.rate.frames_per_day());
+        let frame_count = frame_count.rem_euclid(frames_per_day) as u32;
+
+        Timecode::from_frame_count(frame_count, self.rate).into()
+    }
+
+    /// The message for one quarter frame, the piece is picked by where it falls in its pair of frames
+    fn quarter_frame(&self, quarter: i64) -> Vec<u8> {
+        let piece = quarter.rem_euclid(8);
+        let nibbles = self.timecode((quarter - piece) / 4).to_nibbles();
+
+        vec![
+            0xF1,
+            nibbles.get(piece as usize).copied().unwrap_or_default(),
+        ]
+    }
+
+    /// The full-frame message for a position, sent after a locate
+    fn full_frame(&self, frames: f64) -> Vec<u8> {
+        MidiMsg::SystemExclusive {
+            msg: SystemExclusiveMsg::UniversalRealTime {
+                device: DeviceID::AllCall,
+                msg: UniversalRealTimeMsg::TimeCodeFull(self.timecode(frames.floor() as i64)),
+            },
+        }
+        .to_midi()
+    }
+}
+
+/// Returns true if the clock has reached a quarter frame. Deadlines are rounded to the nearest nanosecond,
+/// so a quarter frame is let through a touch early rather than waking up again straight away
+fn is_due(quarter: i64, position: f64, direction: PlaybackDirection) -> bool {
+    const TOLERANCE: f64 = 1e-6;
+    let quarter_position = quarter as f64 / 4.0;
+
+    match direction {
+        PlaybackDirection::Forward => position >= quarter_position - TOLERANCE,
+        PlaybackDirection::Reverse => position <= quarter_position + TOLERANCE,
+    }
+}
+
+/// How many frames `a` is ahead of `b`, the short way around midnight
+fn circular_difference(a: f64, b: f64, rate: FrameRate) -> f64 {
+    let frames_per_day = f64::from(rate.frames_per_day());
+    let difference = (a - b).rem_euclid(frames_per_day);
+
+    if difference > frames_per_day / 2.0 {
+        difference - frames_per_day
+    } else {
+        difference
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::mtc_decoder::timecode_quarter_frame_buffer::TimeCodeQuarterFrameBuffer;
+
+    #[test]
+    fn test_locate_sends_full_frame() {
+        let now = Instant::now();
+        let mut encoder = MtcEncoder::new(FrameRate::Fps25, None);
+
+        encoder.locate(
+            Timecode::new(1, 2, 3, 4, FrameRate::Fps25).into(),
+            None,
+            now,
+        );
+
+        assert_eq!(
+            encoder.poll(now),
+            vec![vec![
+                0xF0, 0x7F, 0x7F, 0x01, 0x01, 0x21, 0x02, 0x03, 0x04, 0xF7
+            ]]
+        );
+        assert_eq!(
+            encoder.poll(now + Duration::from_secs(1)),
+            Vec::<Vec<u8>>::new()
+        );
+    }
+
+    #[test]
+    fn test_quarter_frames_decode() {
+        let start = Instant::now();
+        let mut encoder = MtcEncoder::new(FrameRate::Fps25, None);
+        let timecode = Timecode::new(10, 0, 0, 0, FrameRate::Fps25);
+        encoder.locate(timecode.into(), Some(PlaybackDirection::Forward), start);
+
+        // Run for a second, waking up whenever the encoder asks to
+        let mut messages = vec![];
+        let mut now = start;
+        while now < start + Duration::from_secs(1) {
+            messages.extend(encoder.poll(now));
+            now = encoder
+                .next_deadline()
+                .unwrap_or(now + Duration::from_millis(1));
+        }
+
+        // A full frame then 4 quarter frames for each of the 25 frames
+        assert_eq!(messages.len(), 101);
+
+        let mut buffer = TimeCodeQuarterFrameBuffer::new();
+        let mut decoded = None;
+        for (index, message) in messages.iter().skip(1).enumerate() {
+            let (parsed, _) = MidiMsg::from_midi(message).expect("Valid quarter frame");
+            if let Some((position, direction)) = buffer.add(&parsed, index as u64 * 10_000) {
+                assert_eq!(direction, PlaybackDirection::Forward);
+                decoded = Some(position.timecode);
+            }
+        }
+
+        // The last quarter frame is sent as 00:00:01:00 starts
+        assert_eq!(decoded, Some(timecode + 24));
+    }
+
+    #[test]
+    fn test_chasing_source() {
+        let start = Instant::now();
+        let mut encoder = MtcEncoder::new(FrameRate::Fps30, Some(5.0));
+        let timecode = Timecode::new(0, 0, 10, 0, FrameRate::Fps30);
+
+        encoder.update(timecode.into(), Some(PlaybackDirection::Forward), start);
+        assert_eq!(encoder.poll(start).len(), 2, "Full frame and piece 0");
+
+        // A position in line with the clock keeps the quarter frames going
+        let later = start + Duration::from_millis(100);
+        encoder.update(
+            (timecode + 3).into(),
+            Some(PlaybackDirection::Forward),
+            later,
+        );
+        assert!(
+            encoder
+                .poll(later)
+                .iter()
+                .all(|message| message.first() == Some(&0xF1)),
+            "No full frame"
+        );
+
+        // A jump is a locate
+        encoder.update(
+            (timecode + 100).into(),
+            Some(PlaybackDirection::Forward),
+            later,
+        );
+        assert_eq!(
+            encoder
+                .poll(later)
+                .first()
+                .and_then(|message| message.first()),
+            Some(&0xF0)
+        );
+
+        // Once the source goes quiet the quarter frames stop
+        encoder.poll(later + Duration::from_millis(200));
+        assert_eq!(encoder.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_reverse() {
+        let start = Instant::now();
+        let mut encoder = MtcEncoder::new(FrameRate::Fps24, None);
+        encoder.locate(
+            Timecode::new(0, 0, 1, 0, FrameRate::Fps24).into(),
+            Some(PlaybackDirection::Reverse),
+            start,
+        );
+
+        let mut messages = encoder.poll(start);
+        messages.extend(encoder.poll(start + Duration::from_millis(50)));
+        let pieces: Vec<u8> = messages
+            .iter()
+            .skip(1)
+            .filter_map(|message| message.get(1).map(|data| data >> 4))
+            .collect();
+
+        // 24 frames in, going backwards through 1.2 frames
+        assert_eq!(pieces, vec![0, 7, 6, 5, 4]);
+    }
+}
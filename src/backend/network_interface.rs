@@ -0,0 +1,108 @@
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+
+use if_addrs::IfAddr;
+use socket2::{Domain, Protocol, Socket, Type};
+
+/// A network interface that Art-Net can be sent or received on
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub address: IpAddr,
+    /// Which part of the address is the subnet
+    pub netmask: IpAddr,
+    /// The address that reaches everything on the interface's subnet
+    pub broadcast: Option<IpAddr>,
+}
+
+impl NetworkInterface {
+    /// Whether an address is on this interface's subnet, so a packet from it came in on this interface
+    pub fn contains(&self, address: IpAddr) -> bool {
+        match (self.address, self.netmask, address) {
+            (IpAddr::V4(own), IpAddr::V4(netmask), IpAddr::V4(address)) => {
+                own & netmask == address & netmask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Gets the IPv4 interfaces on the system, Art-Net doesn't use IPv6. This is an I/O operation so don't spam it
+pub fn get_interfaces() -> Vec<NetworkInterface> {
+    if_addrs::get_if_addrs()
+        .map(|interfaces| {
+            interfaces
+                .into_iter()
+                .filter_map(|interface| match interface.addr {
+                    IfAddr::V4(address) => Some(NetworkInterface {
+                        name: interface.name,
+                        address: IpAddr::V4(address.ip),
+                        netmask: IpAddr::V4(address.netmask),
+                        broadcast: address.broadcast.map(IpAddr::V4),
+                    }),
+                    IfAddr::V6(_) => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Opens a UDP socket on a port that other sockets and programs can listen on too.
+///
+/// E.g: a second Art-Net input, or a lighting desk's own software on the same machine. Broadcasts reach every
+/// socket on the port, but a packet sent straight to this machine only reaches one of them.
+///
+/// # Errors
+/// Fails if the port could not be opened, E.g: if another program is using it without sharing
+pub fn bind_shared_udp(address: SocketAddr) -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(
+        Domain::for_address(address),
+        Type::DGRAM,
+        Some(Protocol::UDP),
+    )?;
+    socket.set_reuse_address(true)?;
+
+    // Linux shares the port with SO_REUSEADDR alone, and SO_REUSEPORT there would split packets sent to us
+    // between the sockets instead of giving them to one
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+    socket.set_reuse_port(true)?;
+
+    socket.bind(&address.into())?;
+
+    Ok(socket.into())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn test_shared_port_can_be_bound_twice() {
+        let first = bind_shared_udp(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+            .expect("Free port on localhost");
+        let address = first.local_addr().expect("Bound socket");
+
+        assert!(bind_shared_udp(address).is_ok());
+    }
+
+    #[test]
+    fn test_contains_only_its_subnet() {
+        let interface = NetworkInterface {
+            name: "eth0".to_owned(),
+            address: IpAddr::V4(Ipv4Addr::new(2, 0, 0, 10)),
+            netmask: IpAddr::V4(Ipv4Addr::new(255, 0, 0, 0)),
+            broadcast: Some(IpAddr::V4(Ipv4Addr::new(2, 255, 255, 255))),
+        };
+
+        assert!(
+            interface.contains(IpAddr::V4(Ipv4Addr::new(2, 1, 2, 3))),
+            "Same subnet"
+        );
+        assert!(
+            !interface.contains(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))),
+            "Another subnet"
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use std::fmt::Display;
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{frame_rate::FrameRate, timecode::Timecode};
+
+/// The port timecode is read from and sent to unless told otherwise. Nothing standard, but the same for both
+/// so the sender can be looped back into an input on the same machine
+pub const DEFAULT_OSC_PORT: u16 = 9000;
+
+/// Every bundle starts with this
+const BUNDLE_ID: &[u8; 8] = b"#bundle\0";
+
+/// An argument of an OSC message, only the types timecode could be sent as are understood
+#[derive(Debug, PartialEq, Clone)]
+pub enum OscArgument {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl OscArgument {
+    fn type_tag(&self) -> char {
+        match self {
+            Self::Int(_) => 'i',
+            Self::Float(_) => 'f',
+            Self::String(_) => 's',
+        }
+    }
+
+    /// The argument as a whole number, None if it is a string or not a whole number
+    fn as_int(&self) -> Option<i32> {
+        match *self {
+            Self::Int(value) => Some(value),
+            Self::Float(value) if value.fract() == 0.0 => Some(value as i32),
+            _ => None,
+        }
+    }
+}
+
+/// An Open Sound Control message, which show control software uses to talk over UDP
+#[derive(Debug, PartialEq, Clone)]
+pub struct OscMessage {
+    /// Where the message is going, E.g: "/timecode"
+    pub address: String,
+    pub arguments: Vec<OscArgument>,
+}
+
+impl OscMessage {
+    /// Reads the messages out of a packet, which is either a single message or a bundle of them.
+    ///
+    /// # Errors
+    /// Fails if the packet isn't OSC, or has arguments of a type we don't understand
+    pub fn from_packet(bytes: &[u8]) -> Result<Vec<Self>> {
+        let Some(mut rest) = bytes.strip_prefix(BUNDLE_ID.as_slice()) else {
+            return Ok(vec![Self::from_bytes(bytes)?]);
+        };
+
+        // Nothing is scheduled, so the time tag doesn't matter
+        rest = rest
+            .get(8..)
+            .ok_or_else(|| anyhow!("OSC bundle has no time tag"))?;
+
+        let mut messages = Vec::new();
+        while !rest.is_empty() {
+            let (size, element) = rest
+                .split_first_chunk::<4>()
+                .ok_or_else(|| anyhow!("OSC bundle element has no size"))?;
+            let (element, remaining) = element
+                .split_at_checked(u32::from_be_bytes(*size) as usize)
+                .ok_or_else(|| anyhow!("OSC bundle element is shorter than its size"))?;
+
+            messages.extend(Self::from_packet(element)?);
+            rest = remaining;
+        }
+
+        Ok(messages)
+    }
+
+    /// Reads a single message.
+    ///
+    /// # Errors
+    /// Fails if the message is cut short, or has arguments of a type we don't understand
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (address, mut rest) = read_string(bytes)?;
+        if !address.starts_with('/') {
+            return Err(anyhow!("Not an OSC message"));
+        }
+
+        // Very old senders leave the type tags off, which means no arguments we could make sense of
+        if rest.is_empty() {
+            return Ok(Self {
+                address,
+                arguments: Vec::new(),
+            });
+        }
+
+        let (type_tags, remaining) = read_string(rest)?;
+        rest = remaining;
+        let type_tags = type_tags
+            .strip_prefix(',')
+            .ok_or_else(|| anyhow!("OSC type tags don't start with a comma"))?;
+
+        let mut arguments = Vec::new();
+        for tag in type_tags.chars() {
+            let argument = match tag {
+                'i' | 'f' => {
+                    let (value, remaining) = rest
+                        .split_first_chunk::<4>()
+                        .ok_or_else(|| anyhow!("OSC message is missing an argument"))?;
+                    rest = remaining;
+
+                    if tag == 'i' {
+                        OscArgument::Int(i32::from_be_bytes(*value))
+                    } else {
+                        OscArgument::Float(f32::from_be_bytes(*value))
+                    }
+                }
+                's' => {
+                    let (value, remaining) = read_string(rest)?;
+                    rest = remaining;
+                    OscArgument::String(value)
+                }
+                _ => return Err(anyhow!("OSC argument type '{tag}' isn't supported")),
+            };
+
+            arguments.push(argument);
+        }
+
+        Ok(Self { address, arguments })
+    }
+
+    /// Gets the bytes to send for this message
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let type_tags: String = std::iter::once(',')
+            .chain(self.arguments.iter().map(OscArgument::type_tag))
+            .collect();
+
+        let mut bytes = Vec::new();
+        write_string(&mut bytes, &self.address);
+        write_string(&mut bytes, &type_tags);
+
+        for argument in &self.arguments {
+            match argument {
+                OscArgument::Int(value) => bytes.extend(value.to_be_bytes()),
+                OscArgument::Float(value) => bytes.extend(value.to_be_bytes()),
+                OscArgument::String(value) => write_string(&mut bytes, value),
+            }
+        }
+
+        bytes
+    }
+}
+
+/// Reads a null terminated string padded to a multiple of four bytes, returning it and what comes after it
+fn read_string(bytes: &[u8]) -> Result<(String, &[u8])> {
+    let length = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or_else(|| anyhow!("OSC string isn't terminated"))?;
+
+    let text = std::str::from_utf8(bytes.get(..length).unwrap_or_default())?.to_owned();
+    let padded_length = (length / 4 + 1) * 4;
+
+    Ok((text, bytes.get(padded_length..).unwrap_or_default()))
+}
+
+/// Writes a string with at least one null after it, padded to a multiple of four bytes
+fn write_string(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend(text.as_bytes());
+    bytes.resize((bytes.len() / 4 + 1) * 4, 0);
+}
+
+/// Whether an address matches a pattern, where `?` matches any one character and `*` any number of them.
+/// Neither match across a `/`, E.g: "/*/timecode" matches "/qlab/timecode" but not "/timecode"
+pub fn address_matches(pattern: &str, address: &str) -> bool {
+    let pattern_parts: Vec<_> = pattern.split('/').collect();
+    let address_parts: Vec<_> = address.split('/').collect();
+
+    pattern_parts.len() == address_parts.len()
+        && pattern_parts
+            .iter()
+            .zip(&address_parts)
+            .all(|(pattern, part)| part_matches(pattern.as_bytes(), part.as_bytes()))
+}
+
+fn part_matches(pattern: &[u8], part: &[u8]) -> bool {
+    match (pattern.split_first(), part.split_first()) {
+        (None, None) => true,
+        (Some((b'*', rest)), _) => {
+            part_matches(rest, part)
+                || part
+                    .split_first()
+                    .is_some_and(|(_, part_rest)| part_matches(pattern, part_rest))
+        }
+        (Some((b'?', rest)), Some((_, part_rest))) => part_matches(rest, part_rest),
+        (Some((expected, rest)), Some((actual, part_rest))) => {
+            expected == actual && part_matches(rest, part_rest)
+        }
+        _ => false,
+    }
+}
+
+/// How timecode is laid out in the arguments of a message
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum OscTimecodeFormat {
+    /// A single string, E.g: "01:02:03:04"
+    #[default]
+    String,
+    /// Four ints for the hours, minutes, seconds and frames
+    Ints,
+}
+
+impl OscTimecodeFormat {
+    pub const ALL: [Self; 2] = [Self::String, Self::Ints];
+
+    /// The arguments to send a timecode with
+    pub fn arguments(self, timecode: Timecode) -> Vec<OscArgument> {
+        match self {
+            Self::String => vec![OscArgument::String(
+                timecode.format(Default::default()).to_string(),
+            )],
+            Self::Ints => [
+                timecode.hours(),
+                timecode.minutes(),
+                timecode.seconds(),
+                timecode.frames(),
+            ]
+            .into_iter()
+            .map(|value| OscArgument::Int(i32::from(value)))
+            .collect(),
+        }
+    }
+}
+
+impl Display for OscTimecodeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::String => write!(f, "String \"01:02:03:04\""),
+            Self::Ints => write!(f, "Four ints 1 2 3 4"),
+        }
+    }
+}
+
+/// Reads a timecode out of the arguments of a message, in either format. OSC doesn't say what rate it is, so that
+/// has to be known already.
+///
+/// # Errors
+/// Fails if the arguments aren't a timecode, or it is out of range for the rate
+pub fn timecode_from_arguments(arguments: &[OscArgument], rate: FrameRate) -> Result<Timecode> {
+    match arguments {
+        [OscArgument::String(text), ..] => Ok(Timecode::parse(text, rate)?),
+        [hours, minutes, seconds, frames, ..] => {
+            let field = |argument: &OscArgument| {
+                argument
+                    .as_int()
+                    .and_then(|value| u8::try_from(value).ok())
+                    .ok_or_else(|| anyhow!("{argument:?} isn't part of a timecode"))
+            };
+
+            Ok(Timecode::try_new(
+                field(hours)?,
+                field(minutes)?,
+                field(seconds)?,
+                field(frames)?,
+                rate,
+            )?)
+        }
+        _ => Err(anyhow!(
+            "Expected a string or four numbers, got {arguments:?}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let message = OscMessage {
+            address: "/timecode".to_owned(),
+            arguments: vec![
+                OscArgument::String("01:02:03:04".to_owned()),
+                OscArgument::Int(-5),
+                OscArgument::Float(0.5),
+            ],
+        };
+
+        let bytes = message.to_bytes();
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(bytes.get(..16), Some(b"/timecode\0\0\0,sif".as_slice()));
+
+        assert_eq!(OscMessage::from_packet(&bytes).ok(), Some(vec![message]));
+    }
+
+    #[test]
+    fn test_bundle() {
+        let message = OscMessage {
+            address: "/tc".to_owned(),
+            arguments: vec![OscArgument::Int(1)],
+        };
+        let element = message.to_bytes();
+
+        let mut bundle = BUNDLE_ID.to_vec();
+        bundle.extend([0, 0, 0, 0, 0, 0, 0, 1]);
+        for _ in 0..2 {
+            bundle.extend((element.len() as u32).to_be_bytes());
+            bundle.extend(&element);
+        }
+
+        assert_eq!(
+            OscMessage::from_packet(&bundle).ok(),
+            Some(vec![message.clone(), message])
+        );
+    }
+
+    #[test]
+    fn test_address_matches() {
+        assert!(address_matches("/timecode", "/timecode"));
+        assert!(address_matches("/*/timecode", "/qlab/timecode"));
+        assert!(address_matches("/time?ode", "/timecode"));
+        assert!(address_matches("/tc*", "/tc"));
+        assert!(!address_matches("/*/timecode", "/timecode"));
+        assert!(!address_matches("/timecode", "/timecode/status"));
+    }
+
+    #[test]
+    fn test_timecode_from_arguments() {
+        let expected = Timecode::new(1, 2, 3, 4, FrameRate::Fps25);
+
+        for format in OscTimecodeFormat::ALL {
+            assert_eq!(
+                timecode_from_arguments(&format.arguments(expected), FrameRate::Fps25).ok(),
+                Some(expected)
+            );
+        }
+
+        // Some software only sends floats
+        let floats = [1.0, 2.0, 3.0, 4.0].map(OscArgument::Float);
+        assert_eq!(
+            timecode_from_arguments(&floats, FrameRate::Fps25).ok(),
+            Some(expected)
+        );
+
+        assert!(timecode_from_arguments(&[OscArgument::Int(1)], FrameRate::Fps25).is_err());
+    }
+}
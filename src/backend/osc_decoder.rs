@@ -0,0 +1,325 @@
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::backend::{
+    flywheel::Flywheel,
+    frame_rate::FrameRate,
+    osc::{DEFAULT_OSC_PORT, OscMessage, address_matches, timecode_from_arguments},
+    packet_tracker::PacketTracker,
+    timecode_decoder::{
+        ConnectionState, DecoderStatus, ErrorCounters, PlaybackDirection, TimecodeDecoder,
+        TimecodePosition,
+    },
+};
+
+/// How often the network thread stops waiting for packets to check if it should stop
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What the network thread tells the decoder about
+enum OscEvent {
+    Position {
+        position: TimecodePosition,
+        direction: Option<PlaybackDirection>,
+        measured_fps: Option<f64>,
+    },
+    /// A packet that wasn't OSC, or a message on our address that wasn't a timecode
+    Malformed,
+    /// Frames were skipped between messages
+    Dropout,
+}
+
+/// What to listen for, fixed for as long as the decoder is connected
+struct OscListen {
+    address_pattern: String,
+    rate: FrameRate,
+}
+
+/// Reads timecode sent as OSC messages, E.g: `/timecode "01:02:03:04"` or `/timecode 1 2 3 4`
+pub struct OscTimecodeDecoder {
+    /// The address of the interface to listen on, unspecified to listen on all of them
+    pub bind_address: IpAddr,
+    pub port: u16,
+    /// Which messages carry timecode, `?` and `*` match any one or any number of characters
+    pub address_pattern: String,
+    /// OSC doesn't say what rate the timecode is, so it has to be set here
+    pub rate: FrameRate,
+    /// Keeps the timecode counting if messages stop arriving
+    pub flywheel: Flywheel,
+    /// Positions and errors from the network thread
+    timecode_rx: Option<Receiver<OscEvent>>,
+    /// Everything about the signal that isn't tracked by the flywheel
+    status: DecoderStatus,
+    /// Tells the network thread to finish
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OscTimecodeDecoder {
+    /// Creates a new, disconnected, OSC decoder listening on all interfaces
+    pub fn new() -> Self {
+        Self {
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: DEFAULT_OSC_PORT,
+            address_pattern: "/timecode".to_owned(),
+            rate: FrameRate::Fps25,
+            flywheel: Flywheel::default(),
+            timecode_rx: None,
+            status: DecoderStatus::default(),
+            stop: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Start listening for OSC timecode on the selected interface and port.
+    ///
+    /// # Errors
+    /// Fails if the port could not be opened, E.g: if another input or program is already listening on it
+    pub fn connect(&mut self) -> Result<()> {
+        // OSC is sent straight to this machine, so a shared port would quietly hand the messages to only one
+        // of the sockets on it. Not sharing means a clash is reported instead
+        let socket =
+            UdpSocket::bind(SocketAddr::new(self.bind_address, self.port)).map_err(|e| {
+                anyhow!(
+                    "Failed to listen on {}:{}: {e}",
+                    self.bind_address,
+                    self.port
+                )
+            })?;
+        socket.set_read_timeout(Some(STOP_CHECK_INTERVAL))?;
+
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let listen = OscListen {
+            address_pattern: self.address_pattern.clone(),
+            rate: self.rate,
+        };
+
+        let thread_stop = Arc::clone(&stop);
+        let thread = thread::Builder::new()
+            .name("OSC timecode".to_owned())
+            .spawn(move || receive(&socket, &listen, &thread_stop, &tx))?;
+
+        self.stop = stop;
+        self.thread = Some(thread);
+        self.timecode_rx = Some(rx);
+        self.status.errors = ErrorCounters::default();
+
+        Ok(())
+    }
+
+    /// Stops listening for OSC
+    ///
+    /// # Errors
+    /// Result will be Err if there is no connection currently open
+    pub fn disconnect(&mut self) -> Result<()> {
+        let thread = self
+            .thread
+            .take()
+            .ok_or_else(|| anyhow!("No connection is open, hence you cannot close it"))?;
+
+        self.stop.store(true, Ordering::Relaxed);
+        thread
+            .join()
+            .map_err(|_panic| anyhow!("The OSC thread panicked"))?;
+
+        self.timecode_rx = None;
+        self.status.direction = None;
+        self.status.measured_fps = None;
+        self.flywheel.reset();
+
+        Ok(())
+    }
+
+    /// Returns true if there has been a connection made.
+    pub fn connected(&self) -> bool {
+        self.thread.is_some()
+    }
+}
+
+impl Default for OscTimecodeDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for OscTimecodeDecoder {
+    /// The network thread doesn't notice the decoder has gone, so tell it to finish
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Reads packets until told to stop, runs on its own thread
+fn receive(socket: &UdpSocket, listen: &OscListen, stop: &AtomicBool, tx: &Sender<OscEvent>) {
+    let mut tracker = PacketTracker::default();
+    let mut buffer = [0; 1536];
+
+    while !stop.load(Ordering::Relaxed) {
+        let length = match socket.recv(&mut buffer) {
+            Ok(length) => length,
+            // Nothing arrived in time, go round and check if we should stop
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => {
+                log::error!("OSC receive failed: {e}");
+                return;
+            }
+        };
+
+        let Ok(messages) = OscMessage::from_packet(buffer.get(..length).unwrap_or_default()) else {
+            if tx.send(OscEvent::Malformed).is_err() {
+                return;
+            }
+            continue;
+        };
+
+        // Messages to other addresses are for something else listening on the same port
+        for message in messages
+            .iter()
+            .filter(|message| address_matches(&listen.address_pattern, &message.address))
+        {
+            let event = match timecode_from_arguments(&message.arguments, listen.rate) {
+                Ok(timecode) => {
+                    if tracker.update(timecode, Instant::now()) {
+                        tx.send(OscEvent::Dropout).ok();
+                    }
+
+                    OscEvent::Position {
+                        position: timecode.into(),
+                        direction: tracker.direction,
+                        measured_fps: tracker.measured_fps,
+                    }
+                }
+                Err(_) => OscEvent::Malformed,
+            };
+
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl TimecodeDecoder for OscTimecodeDecoder {
+    /// Gets the current position from OSC. If no new value is available then it returns None,
+    /// unless the flywheel is counting on over a dropout
+    fn get_current_position(&mut self) -> Option<TimecodePosition> {
+        let rx = self.timecode_rx.as_ref()?;
+        let now = Instant::now();
+
+        let mut latest = None;
+        for event in rx.try_iter() {
+            match event {
+                OscEvent::Position {
+                    position,
+                    direction,
+                    measured_fps,
+                } => {
+                    latest = Some((position, direction));
+                    self.status.measured_fps = measured_fps;
+                }
+                OscEvent::Malformed => self.status.errors.malformed += 1,
+                OscEvent::Dropout => self.status.errors.dropouts += 1,
+            }
+        }
+
+        let Some((position, direction)) = latest else {
+            return self.flywheel.poll(now);
+        };
+
+        self.status.direction = direction;
+        self.status.rate = Some(position.timecode.rate());
+        self.flywheel.update(position, direction, now);
+
+        Some(position)
+    }
+
+    fn status(&self) -> DecoderStatus {
+        DecoderStatus {
+            connection: if self.connected() {
+                ConnectionState::Connected
+            } else {
+                ConnectionState::Disconnected
+            },
+            signal: self.flywheel.state(),
+            last_received: self.flywheel.last_received(),
+            ..self.status
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{osc::OscArgument, timecode::Timecode};
+
+    #[test]
+    fn test_receives_matching_messages() {
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("Free port on localhost");
+        socket
+            .set_read_timeout(Some(STOP_CHECK_INTERVAL))
+            .expect("Valid timeout");
+        let address = socket.local_addr().expect("Bound socket");
+
+        let listen = OscListen {
+            address_pattern: "/*/timecode".to_owned(),
+            rate: FrameRate::Fps25,
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+
+        let thread_stop = Arc::clone(&stop);
+        let thread = thread::spawn(move || receive(&socket, &listen, &thread_stop, &tx));
+
+        let sender = UdpSocket::bind("127.0.0.1:0").expect("Free port on localhost");
+        for (address_text, argument) in
+            [("/other", "00:00:00:00"), ("/qlab/timecode", "01:02:03:04")]
+        {
+            let message = OscMessage {
+                address: address_text.to_owned(),
+                arguments: vec![OscArgument::String(argument.to_owned())],
+            };
+            sender
+                .send_to(&message.to_bytes(), address)
+                .expect("Sent on localhost");
+        }
+
+        let event = rx
+            .recv_timeout(Duration::from_secs(1))
+            .expect("Timecode arrives");
+        let OscEvent::Position { position, .. } = event else {
+            panic!("Expected a position");
+        };
+        assert_eq!(
+            position.timecode,
+            Timecode::new(1, 2, 3, 4, FrameRate::Fps25)
+        );
+
+        stop.store(true, Ordering::Relaxed);
+        thread.join().expect("Thread finishes when told to stop");
+    }
+
+    #[test]
+    fn test_port_in_use_is_reported() {
+        let other = UdpSocket::bind("127.0.0.1:0").expect("Free port on localhost");
+        let mut decoder = OscTimecodeDecoder::new();
+        decoder.bind_address = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        decoder.port = other.local_addr().expect("Bound socket").port();
+
+        assert!(
+            decoder.connect().is_err(),
+            "A port another socket is listening on should not be shared"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    frame_sender::FrameSender,
+    osc::{DEFAULT_OSC_PORT, OscArgument, OscMessage, OscTimecodeFormat},
+    timecode::Timecode,
+    timecode_decoder::{PlaybackDirection, TimecodePosition},
+};
+
+/// Where to send timecode and what it should look like
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OscSenderSettings {
+    pub target: SocketAddr,
+    /// The address timecode is sent to, the status goes to the same address with "/status" on the end
+    pub address: String,
+    pub format: OscTimecodeFormat,
+    /// The rate to send at, None to send at the source's rate
+    pub rate: Option<FrameRate>,
+}
+
+impl Default for OscSenderSettings {
+    /// Sends to this machine on the port OSC inputs listen on, so it can be looped back
+    fn default() -> Self {
+        Self {
+            target: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), DEFAULT_OSC_PORT),
+            address: "/timecode".to_owned(),
+            format: OscTimecodeFormat::default(),
+            rate: None,
+        }
+    }
+}
+
+/// Sends timecode as OSC messages, one for every frame of the source along with whether it is playing
+pub struct OscTimecodeSender {
+    sender: FrameSender,
+}
+
+impl OscTimecodeSender {
+    /// Creates a new, stopped, sender
+    pub fn new() -> Self {
+        Self {
+            sender: FrameSender::new("OSC timecode sender"),
+        }
+    }
+
+    /// Start sending timecode. Nothing is sent until the first call to [`Self::update`]
+    ///
+    /// # Errors
+    /// Fails if a network socket could not be opened
+    pub fn start(&mut self, settings: OscSenderSettings) -> Result<()> {
+        let socket = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))?;
+        socket.set_broadcast(true)?;
+
+        let rate = settings.rate;
+        let target = settings.target;
+        self.sender.start(
+            rate,
+            move |position, direction| {
+                let timecode =
+                    rate.map_or(position.timecode, |rate| position.timecode.relabel(rate));
+                (timecode, direction)
+            },
+            move |(timecode, direction): &(Timecode, Option<PlaybackDirection>)| {
+                for message in messages(&settings, *timecode, *direction) {
+                    if let Err(e) = socket.send_to(&message.to_bytes(), target) {
+                        log::error!("Failed to send OSC timecode to {target}: {e}");
+                    }
+                }
+            },
+        )
+    }
+
+    /// Stops sending timecode
+    ///
+    /// # Errors
+    /// Result will be Err if the sender isn't running
+    pub fn stop(&mut self) -> Result<()> {
+        self.sender.stop()
+    }
+
+    /// Returns true if timecode is being sent
+    pub fn running(&self) -> bool {
+        self.sender.running()
+    }
+
+    /// The rate timecode is being sent at, None if it follows the source or the sender isn't running
+    pub fn rate(&self) -> Option<FrameRate> {
+        self.sender.rate()
+    }
+
+    /// Give the sender the latest position from the source, `direction` is None if the source isn't playing
+    pub fn update(&mut self, position: TimecodePosition, direction: Option<PlaybackDirection>) {
+        self.sender.update(position, direction);
+    }
+}
+
+impl Default for OscTimecodeSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The messages to send for a frame, the timecode then the status
+fn messages(
+    settings: &OscSenderSettings,
+    timecode: Timecode,
+    direction: Option<PlaybackDirection>,
+) -> [OscMessage; 2] {
+    let status = match direction {
+        Some(PlaybackDirection::Forward) => "playing",
+        Some(PlaybackDirection::Reverse) => "reversing",
+        None => "stopped",
+    };
+
+    [
+        OscMessage {
+            address: settings.address.clone(),
+            arguments: settings.format.arguments(timecode),
+        },
+        OscMessage {
+            address: format!("{}/status", settings.address),
+            arguments: vec![OscArgument::String(status.to_owned())],
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::backend::osc::timecode_from_arguments;
+
+    #[test]
+    fn test_sends_timecode_and_status() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").expect("Free port on localhost");
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .expect("Valid timeout");
+
+        let settings = OscSenderSettings {
+            target: receiver.local_addr().expect("Bound socket"),
+            format: OscTimecodeFormat::Ints,
+            ..Default::default()
+        };
+
+        let mut sender = OscTimecodeSender::new();
+        sender.start(settings).expect("Socket opens");
+
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps25);
+        sender.update(timecode.into(), None);
+
+        let mut buffer = [0; 256];
+        let mut receive = || {
+            let length = receiver.recv(&mut buffer).expect("Message arrives");
+            OscMessage::from_bytes(buffer.get(..length).unwrap_or_default())
+                .expect("Valid OSC message")
+        };
+
+        let timecode_message = receive();
+        assert_eq!(timecode_message.address, "/timecode");
+        assert_eq!(
+            timecode_from_arguments(&timecode_message.arguments, FrameRate::Fps25).ok(),
+            Some(timecode)
+        );
+
+        let status_message = receive();
+        assert_eq!(status_message.address, "/timecode/status");
+        assert_eq!(
+            status_message.arguments,
+            vec![OscArgument::String("stopped".to_owned())]
+        );
+
+        sender.stop().expect("Sender was running");
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Instant;
+
+use crate::backend::{timecode::Timecode, timecode_decoder::PlaybackDirection};
+
+/// How much each new packet counts towards the measured rate
+const RATE_SMOOTHING: f64 = 0.1;
+
+/// Works out which way timecode is going and how fast from consecutive packets, as Art-Net and OSC only send the time
+#[derive(Default)]
+pub struct PacketTracker {
+    last: Option<(Timecode, Instant)>,
+    pub direction: Option<PlaybackDirection>,
+    pub measured_fps: Option<f64>,
+}
+
+impl PacketTracker {
+    /// Takes the timecode of a new packet and returns true if frames went missing since the last one
+    pub fn update(&mut self, timecode: Timecode, now: Instant) -> bool {
+        let Some((last_timecode, last_time)) = self.last.replace((timecode, now)) else {
+            return false;
+        };
+
+        let offset = timecode.frame_offset(&last_timecode);
+        let nominal_fps = i64::from(timecode.rate().nominal_fps());
+
+        self.direction = match offset {
+            1..=i64::MAX => Some(PlaybackDirection::Forward),
+            i64::MIN..=-1 => Some(PlaybackDirection::Reverse),
+            // The same frame again means it has been paused
+            0 => None,
+        };
+
+        // Anything further than a second is a locate rather than a few lost packets
+        if offset.abs() > nominal_fps {
+            self.direction = None;
+            return false;
+        }
+
+        if offset.abs() == 1 {
+            let fps = 1.0 / now.duration_since(last_time).as_secs_f64();
+            self.measured_fps = Some(match self.measured_fps {
+                Some(smoothed) => smoothed + (fps - smoothed) * RATE_SMOOTHING,
+                None => fps,
+            });
+        }
+
+        offset.abs() > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::backend::frame_rate::FrameRate;
+
+    #[test]
+    fn test_tracker_direction_and_rate() {
+        let mut tracker = PacketTracker::default();
+        let start = Instant::now();
+        let timecode = Timecode::new(1, 0, 0, 0, FrameRate::Fps25);
+
+        for frame in 0..10 {
+            let dropout =
+                tracker.update(timecode + frame, start + Duration::from_millis(40) * frame);
+            assert!(!dropout, "No frames were skipped");
+        }
+
+        assert_eq!(tracker.direction, Some(PlaybackDirection::Forward));
+        let measured_fps = tracker.measured_fps.unwrap_or_default();
+        assert!(
+            (measured_fps - 25.0).abs() < 0.01,
+            "Measured {measured_fps}"
+        );
+
+        // Paused
+        tracker.update(timecode + 9, start + Duration::from_millis(400));
+        assert_eq!(tracker.direction, None);
+    }
+
+    #[test]
+    fn test_tracker_dropout_and_locate() {
+        let mut tracker = PacketTracker::default();
+        let start = Instant::now();
+        let timecode = Timecode::new(1, 0, 0, 0, FrameRate::Fps25);
+
+        tracker.update(timecode, start);
+        assert!(
+            tracker.update(timecode + 3, start + Duration::from_millis(120)),
+            "Two frames were skipped"
+        );
+        assert_eq!(tracker.direction, Some(PlaybackDirection::Forward));
+
+        assert!(
+            !tracker.update(timecode + 1000, start + Duration::from_millis(160)),
+            "A locate isn't a dropout"
+        );
+        assert_eq!(tracker.direction, None);
+    }
+}
@@ -1,29 +1,101 @@
 use std::fmt::Display;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
-/// Stores timecode values with an associated FPS, can be used for SMPTE timecode and others.
+use crate::backend::{frame_rate::FrameRate, timecode_formatter::TimecodeFormat};
+
+/// Stores timecode values with an associated frame rate, can be used for SMPTE timecode and others.
+///
 /// Values are private to enforce the following conditions:
 ///     minutes < 60
 ///     seconds < 60
-///     frames < fps
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+///     frames < `rate.nominal_fps()`
+///     hours < 24
+///     frame numbers skipped by drop-frame rates are never stored
+///
+/// Deserializing checks these conditions too, so every `Timecode` is valid for its rate.
+///
+/// Timecodes are ordered by their label, so two values of the same rate sort chronologically.
+/// Arithmetic wraps around at 24 hours like a real timecode source does.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(try_from = "TimecodeFields")]
 pub struct Timecode {
     hours: u8,
     minutes: u8,
     seconds: u8,
     frames: u8,
-    fps: u8,
+    rate: FrameRate,
+}
+
+/// Why a set of values can't be stored as a [`Timecode`], returned by [`Timecode::try_new`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TimecodeError {
+    /// A field was too large for the rate, E.g: 24 hours or frame 25 at 25fps
+    OutOfRange {
+        field: &'static str,
+        value: u8,
+        max: u8,
+    },
+    /// The label is one of the frame numbers that drop-frame skips, E.g: 00:01:00;00
+    DroppedFrame,
+}
+
+impl Display for TimecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfRange { field, value, max } => {
+                write!(f, "{field} must be at most {max}, but was {value}")
+            }
+            Self::DroppedFrame => write!(f, "This frame number is skipped by drop-frame timecode"),
+        }
+    }
+}
+
+impl std::error::Error for TimecodeError {}
+
+/// The values of a [`Timecode`] as they are stored on disk, checked with [`Timecode::try_new`] when loaded
+#[derive(Deserialize)]
+struct TimecodeFields {
+    hours: u8,
+    minutes: u8,
+    seconds: u8,
+    frames: u8,
+    rate: FrameRate,
+}
+
+impl TryFrom<TimecodeFields> for Timecode {
+    type Error = TimecodeError;
+
+    fn try_from(value: TimecodeFields) -> Result<Self, Self::Error> {
+        Self::try_new(
+            value.hours,
+            value.minutes,
+            value.seconds,
+            value.frames,
+            value.rate,
+        )
+    }
 }
 
 impl Timecode {
     /// Creates a new timecode struct, it will automatically correct for when values are too high.
-    /// E.g: if seconds are > 60 then it wil roll that over to minutes
-    pub fn new(mut hours: u8, mut minutes: u8, mut seconds: u8, mut frames: u8, fps: u8) -> Self {
+    /// E.g: if seconds are > 60 then it wil roll that over to minutes, and 24 hours wraps back to 0
+    pub fn new(hours: u8, minutes: u8, seconds: u8, frames: u8, rate: FrameRate) -> Self {
+        // Work in u32 so that rolling over can never overflow
+        let fps = u32::from(rate.nominal_fps());
+        let (mut hours, mut minutes, mut seconds, mut frames) = (
+            u32::from(hours),
+            u32::from(minutes),
+            u32::from(seconds),
+            u32::from(frames),
+        );
+
         // If the number of frames is too high, convert the extra to seconds and add the remainder to the frames
         if frames >= fps {
-            seconds = seconds + frames / fps;
-            frames = frames % fps;
+            seconds += frames / fps;
+            frames %= fps;
         }
 
         // The value at which we should roll over to the next value for minutes and seconds
@@ -31,56 +103,316 @@ impl Timecode {
 
         // If there are too many seconds and it could be stored in minutes, do so
         if seconds >= max_time {
-            minutes = minutes + seconds / max_time;
-            seconds = seconds % max_time;
+            minutes += seconds / max_time;
+            seconds %= max_time;
         }
 
         // If there are too many minutes and it should be stored in hours, do so
         if minutes >= max_time {
-            hours = hours + minutes / max_time;
-            minutes = minutes % max_time;
+            hours += minutes / max_time;
+            minutes %= max_time;
         }
 
-        // Now that we are sure that the timecode value makes sense, store it to the struct
+        // SMPTE timecode goes back to midnight after 23:59:59:FF
+        hours %= 24;
+
+        // Drop-frame skips the first few frame numbers of every minute except each tenth one,
+        // those labels don't exist so move forward to the first one that does
+        let dropped = u32::from(rate.dropped_frames_per_minute());
+        if seconds == 0 && !minutes.is_multiple_of(10) && frames < dropped {
+            frames = dropped;
+        }
+
+        // Now that we are sure that the timecode value makes sense, store it to the struct.
+        // Every value has been wrapped below its limit so they all fit in a u8
         Self {
+            hours: hours as u8,
+            minutes: minutes as u8,
+            seconds: seconds as u8,
+            frames: frames as u8,
+            rate,
+        }
+    }
+
+    /// Creates a new timecode struct only if every value is already in range for the rate.
+    ///
+    /// # Errors
+    /// Returns a [`TimecodeError`] for the first value that is out of range, or if the label is skipped by drop-frame
+    pub fn try_new(
+        hours: u8,
+        minutes: u8,
+        seconds: u8,
+        frames: u8,
+        rate: FrameRate,
+    ) -> Result<Self, TimecodeError> {
+        let check = |field, value, max| {
+            if value > max {
+                Err(TimecodeError::OutOfRange { field, value, max })
+            } else {
+                Ok(())
+            }
+        };
+
+        check("Hours", hours, 23)?;
+        check("Minutes", minutes, 59)?;
+        check("Seconds", seconds, 59)?;
+        check("Frames", frames, rate.nominal_fps() - 1)?;
+
+        if seconds == 0 && !minutes.is_multiple_of(10) && frames < rate.dropped_frames_per_minute()
+        {
+            return Err(TimecodeError::DroppedFrame);
+        }
+
+        Ok(Self {
             hours,
             minutes,
             seconds,
             frames,
-            fps,
+            rate,
+        })
+    }
+
+    /// Creates a timecode from the number of frames elapsed since 00:00:00:00, taking drop-frame into account.
+    /// Frame counts longer than a day wrap back around to midnight
+    pub fn from_frame_count(frame_count: u32, rate: FrameRate) -> Self {
+        let mut frame_count = frame_count % rate.frames_per_day();
+
+        // Add the skipped frame numbers back in so the count can be split up like a non drop-frame one
+        let dropped = u32::from(rate.dropped_frames_per_minute());
+        if dropped > 0 {
+            let ten_minute_blocks = frame_count / rate.frames_per_ten_minutes();
+            let remainder = frame_count % rate.frames_per_ten_minutes();
+
+            frame_count += dropped * 9 * ten_minute_blocks;
+            if remainder > dropped {
+                frame_count += dropped * ((remainder - dropped) / rate.frames_per_minute());
+            }
+        }
+
+        let fps = u32::from(rate.nominal_fps());
+        let total_seconds = frame_count / fps;
+
+        Self {
+            hours: (total_seconds / 3600) as u8,
+            minutes: (total_seconds / 60 % 60) as u8,
+            seconds: (total_seconds % 60) as u8,
+            frames: (frame_count % fps) as u8,
+            rate,
         }
     }
+
+    /// The number of frames elapsed since 00:00:00:00, taking drop-frame into account
+    pub fn frame_count(&self) -> u32 {
+        let fps = u32::from(self.rate.nominal_fps());
+        let total_minutes = u32::from(self.hours) * 60 + u32::from(self.minutes);
+
+        // Count every label as if nothing was dropped, then take away the ones drop-frame skips
+        let labels = (total_minutes * 60 + u32::from(self.seconds)) * fps + u32::from(self.frames);
+        let dropped =
+            u32::from(self.rate.dropped_frames_per_minute()) * (total_minutes - total_minutes / 10);
+
+        labels - dropped
+    }
+
+    pub fn hours(&self) -> u8 {
+        self.hours
+    }
+
+    pub fn minutes(&self) -> u8 {
+        self.minutes
+    }
+
+    pub fn seconds(&self) -> u8 {
+        self.seconds
+    }
+
+    pub fn frames(&self) -> u8 {
+        self.frames
+    }
+
+    pub fn rate(&self) -> FrameRate {
+        self.rate
+    }
+
+    /// The wall-clock time elapsed since 00:00:00:00 when running at this timecode's rate
+    pub fn to_duration(&self) -> Duration {
+        let (numerator, denominator) = self.rate.as_ratio();
+        let nanos = u128::from(self.frame_count()) * u128::from(denominator) * 1_000_000_000
+            / u128::from(numerator);
+
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Creates the timecode of the frame that is showing after `duration` has elapsed since 00:00:00:00.
+    /// Durations longer than a day wrap back around to midnight
+    pub fn from_duration(duration: Duration, rate: FrameRate) -> Self {
+        let (numerator, denominator) = rate.as_ratio();
+        let frame_count = duration.as_nanos() * u128::from(numerator)
+            / (u128::from(denominator) * 1_000_000_000)
+            % u128::from(rate.frames_per_day());
+
+        Self::from_frame_count(frame_count as u32, rate)
+    }
+
+    /// Converts this timecode to be sent at another rate. Rates that count the same number of frames a second
+    /// keep the labels, E.g: 29.97fps NDF sent as 30fps, as that's what a bridge between two formats is expected to do.
+    /// Other rates are converted with [`Timecode::with_rate`]
+    pub fn relabel(&self, rate: FrameRate) -> Self {
+        if rate.nominal_fps() != self.rate.nominal_fps() {
+            return self.with_rate(rate);
+        }
+
+        // Labels that drop-frame skips have to be moved on to one that exists
+        Self::try_new(self.hours, self.minutes, self.seconds, self.frames, rate)
+            .unwrap_or_else(|_| self.with_rate(rate))
+    }
+
+    /// Converts this timecode to another rate, keeping it at the same point in wall-clock time.
+    /// Switching between drop-frame and non drop-frame at the same speed keeps the frame count
+    pub fn with_rate(&self, rate: FrameRate) -> Self {
+        if rate == self.rate {
+            return *self;
+        }
+
+        let (from_numerator, from_denominator) = self.rate.as_ratio();
+        let (to_numerator, to_denominator) = rate.as_ratio();
+
+        // Round to the nearest frame rather than truncating, so converting back and forth is stable
+        let scaled =
+            u64::from(self.frame_count()) * u64::from(to_numerator) * u64::from(from_denominator);
+        let divisor = u64::from(from_numerator) * u64::from(to_denominator);
+        let frame_count = (scaled + divisor / 2) / divisor % u64::from(rate.frames_per_day());
+
+        Self::from_frame_count(frame_count as u32, rate)
+    }
+
+    /// The number of frames from `other` to this timecode, taking the shortest way around the 24 hour clock.
+    /// Positive if this timecode is ahead of `other`. `other` is converted to this timecode's rate first
+    pub fn frame_offset(&self, other: &Self) -> i64 {
+        let frames_per_day = i64::from(self.rate.frames_per_day());
+        let difference = (i64::from(self.frame_count())
+            - i64::from(other.with_rate(self.rate).frame_count()))
+        .rem_euclid(frames_per_day);
+
+        if difference > frames_per_day / 2 {
+            difference - frames_per_day
+        } else {
+            difference
+        }
+    }
+}
+
+impl Add<u32> for Timecode {
+    type Output = Self;
+
+    /// Moves the timecode forward by a number of frames
+    fn add(self, frames: u32) -> Self {
+        let frames_per_day = self.rate.frames_per_day();
+        let frame_count =
+            (self.frame_count() % frames_per_day + frames % frames_per_day) % frames_per_day;
+
+        Self::from_frame_count(frame_count, self.rate)
+    }
+}
+
+impl Sub<u32> for Timecode {
+    type Output = Self;
+
+    /// Moves the timecode back by a number of frames
+    fn sub(self, frames: u32) -> Self {
+        let frames_per_day = self.rate.frames_per_day();
+        let frame_count = (self.frame_count() % frames_per_day + frames_per_day
+            - frames % frames_per_day)
+            % frames_per_day;
+
+        Self::from_frame_count(frame_count, self.rate)
+    }
+}
+
+impl Add for Timecode {
+    type Output = Self;
+
+    /// Adds the length of `other` to this timecode, the result keeps this timecode's rate
+    fn add(self, other: Self) -> Self {
+        self + other.with_rate(self.rate).frame_count()
+    }
+}
+
+impl Sub for Timecode {
+    type Output = Self;
+
+    /// Takes the length of `other` away from this timecode, the result keeps this timecode's rate
+    fn sub(self, other: Self) -> Self {
+        self - other.with_rate(self.rate).frame_count()
+    }
+}
+
+impl AddAssign<u32> for Timecode {
+    fn add_assign(&mut self, frames: u32) {
+        *self = *self + frames;
+    }
+}
+
+impl SubAssign<u32> for Timecode {
+    fn sub_assign(&mut self, frames: u32) {
+        *self = *self - frames;
+    }
+}
+
+impl AddAssign for Timecode {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Timecode {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
 }
 
 impl Display for Timecode {
+    /// Writes the timecode zero padded with its rate, E.g: `01:02:03;04 @ 29.97fps DF`.
+    /// Use [`Timecode::format`] for other layouts
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}:{}:{}:{} @ {}fps",
-            self.hours, self.minutes, self.seconds, self.frames, self.fps
-        )
+        let format = TimecodeFormat {
+            show_rate: true,
+            ..Default::default()
+        };
+
+        write!(f, "{}", self.format(format))
     }
 }
 
-impl From<midi_msg::TimeCode> for Timecode {
-    fn from(value: midi_msg::TimeCode) -> Self {
-        let fps = match value.code_type {
-            midi_msg::TimeCodeType::FPS24 => 24,
-            midi_msg::TimeCodeType::FPS25 => 25,
-            midi_msg::TimeCodeType::DF30 => 30,
-            midi_msg::TimeCodeType::NDF30 => 30,
-        };
+impl From<Timecode> for midi_msg::TimeCode {
+    /// Converts to the closest of the four rates MTC can carry, keeping the labels where it can
+    fn from(value: Timecode) -> Self {
+        let code_type = midi_msg::TimeCodeType::from(value.rate);
+        let value = value.relabel(code_type.into());
 
         Self {
-            hours: value.hours,
-            minutes: value.minutes,
-            seconds: value.seconds,
             frames: value.frames,
-            fps,
+            seconds: value.seconds,
+            minutes: value.minutes,
+            hours: value.hours,
+            code_type,
         }
     }
 }
 
+impl From<midi_msg::TimeCode> for Timecode {
+    fn from(value: midi_msg::TimeCode) -> Self {
+        // MTC has room for values that are out of range, so wrap them rather than trusting the sender
+        Self::new(
+            value.hours,
+            value.minutes,
+            value.seconds,
+            value.frames,
+            value.code_type.into(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -90,63 +422,281 @@ mod tests {
 
     #[test]
     fn test_boundary_hours_unchanged() {
-        let timecode = Timecode::new(100, 59, 59, 23, 24);
+        let timecode = Timecode::new(23, 59, 59, 23, FrameRate::Fps24);
 
-        assert_eq!(timecode.hours, 100)
+        assert_eq!(timecode.hours, 23);
     }
 
     #[test]
     fn test_boundary_minutes_unchanged() {
-        let timecode = Timecode::new(100, 59, 59, 23, 24);
+        let timecode = Timecode::new(23, 59, 59, 23, FrameRate::Fps24);
 
-        assert_eq!(timecode.minutes, 59)
+        assert_eq!(timecode.minutes, 59);
     }
 
     #[test]
     fn test_boundary_seconds_unchanged() {
-        let timecode = Timecode::new(100, 59, 59, 23, 24);
+        let timecode = Timecode::new(23, 59, 59, 23, FrameRate::Fps24);
 
-        assert_eq!(timecode.seconds, 59)
+        assert_eq!(timecode.seconds, 59);
     }
 
     #[test]
     fn test_boundary_frames_unchanged() {
-        let timecode = Timecode::new(100, 59, 59, 23, 24);
+        let timecode = Timecode::new(23, 59, 59, 23, FrameRate::Fps24);
 
-        assert_eq!(timecode.frames, 23)
+        assert_eq!(timecode.frames, 23);
     }
 
     // Tests for when the frames value is too high
 
     #[test]
     fn test_boundary_over_change_frames() {
-        let timecode = Timecode::new(100, 59, 59, 25, 24);
-
-        println!("Hours unchanged timecode: {}", timecode);
+        let timecode = Timecode::new(22, 59, 59, 25, FrameRate::Fps24);
 
-        assert_eq!(timecode.frames, 1)
+        assert_eq!(timecode.frames, 1);
     }
 
     #[test]
     fn test_boundary_over_change_seconds() {
-        let timecode = Timecode::new(100, 59, 59, 25, 24);
+        let timecode = Timecode::new(22, 59, 59, 25, FrameRate::Fps24);
 
-        assert_eq!(timecode.seconds, 0)
+        assert_eq!(timecode.seconds, 0);
     }
 
     #[test]
     fn test_boundary_over_change_minutes() {
-        let timecode = Timecode::new(100, 59, 59, 25, 24);
+        let timecode = Timecode::new(22, 59, 59, 25, FrameRate::Fps24);
 
-        assert_eq!(timecode.minutes, 0)
+        assert_eq!(timecode.minutes, 0);
     }
 
     #[test]
     fn test_boundary_over_change_hours() {
-        let timecode = Timecode::new(100, 59, 59, 25, 24);
+        let timecode = Timecode::new(22, 59, 59, 25, FrameRate::Fps24);
+
+        assert_eq!(timecode.hours, 23);
+    }
+
+    // Tests for wrapping at 24 hours and validation
+
+    #[test]
+    fn test_boundary_over_wraps_hours() {
+        let timecode = Timecode::new(23, 59, 59, 25, FrameRate::Fps24);
+
+        assert_eq!(timecode, Timecode::new(0, 0, 0, 1, FrameRate::Fps24));
+    }
+
+    #[test]
+    fn test_large_values_do_not_overflow() {
+        let timecode = Timecode::new(255, 255, 255, 255, FrameRate::Fps24);
+
+        assert_eq!(timecode, Timecode::new(19, 19, 25, 15, FrameRate::Fps24));
+    }
+
+    #[test]
+    fn test_try_new_rejects_24_hours() {
+        let timecode = Timecode::try_new(24, 0, 0, 0, FrameRate::Fps25);
+
+        assert_eq!(
+            timecode,
+            Err(TimecodeError::OutOfRange {
+                field: "Hours",
+                value: 24,
+                max: 23
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_new_rejects_frames_over_rate() {
+        let timecode = Timecode::try_new(0, 0, 0, 25, FrameRate::Fps25);
+
+        assert_eq!(
+            timecode,
+            Err(TimecodeError::OutOfRange {
+                field: "Frames",
+                value: 25,
+                max: 24
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_new_rejects_dropped_frame() {
+        let timecode = Timecode::try_new(0, 1, 0, 1, FrameRate::Fps29_97Df);
 
-        println!("Hours unchanged timecode: {}", timecode);
+        assert_eq!(timecode, Err(TimecodeError::DroppedFrame));
+    }
+
+    #[test]
+    fn test_mtc_out_of_range_hours_wrap() {
+        let timecode = Timecode::from(midi_msg::TimeCode {
+            frames: 0,
+            seconds: 0,
+            minutes: 0,
+            hours: 25,
+            code_type: midi_msg::TimeCodeType::FPS25,
+        });
+
+        assert_eq!(timecode.hours, 1);
+    }
+
+    // Tests for drop-frame conversion to and from a frame count
+
+    #[test]
+    fn test_drop_frame_skips_first_frames_of_minute() {
+        let timecode = Timecode::from_frame_count(1800, FrameRate::Fps29_97Df);
+
+        assert_eq!(timecode, Timecode::new(0, 1, 0, 2, FrameRate::Fps29_97Df));
+    }
+
+    #[test]
+    fn test_drop_frame_keeps_tenth_minute() {
+        let timecode = Timecode::from_frame_count(17982, FrameRate::Fps29_97Df);
+
+        assert_eq!(timecode, Timecode::new(0, 10, 0, 0, FrameRate::Fps29_97Df));
+    }
+
+    #[test]
+    fn test_drop_frame_one_hour_frame_count() {
+        let timecode = Timecode::new(1, 0, 0, 0, FrameRate::Fps29_97Df);
+
+        assert_eq!(timecode.frame_count(), 107892);
+    }
+
+    #[test]
+    fn test_drop_frame_59_94_frame_count() {
+        let timecode = Timecode::new(0, 1, 0, 4, FrameRate::Fps59_94Df);
+
+        assert_eq!(timecode.frame_count(), 3600);
+    }
+
+    #[test]
+    fn test_drop_frame_round_trip() {
+        for frame_count in (0..FrameRate::Fps29_97Df.frames_per_day()).step_by(997) {
+            let timecode = Timecode::from_frame_count(frame_count, FrameRate::Fps29_97Df);
+
+            assert_eq!(timecode.frame_count(), frame_count);
+        }
+    }
+
+    #[test]
+    fn test_new_moves_dropped_label_forward() {
+        let timecode = Timecode::new(0, 1, 0, 0, FrameRate::Fps29_97Df);
+
+        assert_eq!(timecode.frames, 2);
+    }
+
+    #[test]
+    fn test_mtc_drop_frame_rate() {
+        let timecode = Timecode::from(midi_msg::TimeCode {
+            frames: 10,
+            seconds: 0,
+            minutes: 1,
+            hours: 0,
+            code_type: midi_msg::TimeCodeType::DF30,
+        });
+
+        assert_eq!(timecode.rate, FrameRate::Fps29_97Df);
+    }
+
+    // Tests for arithmetic
+
+    #[test]
+    fn test_add_frames_rolls_over() {
+        let timecode = Timecode::new(0, 0, 59, 24, FrameRate::Fps25) + 1;
+
+        assert_eq!(timecode, Timecode::new(0, 1, 0, 0, FrameRate::Fps25));
+    }
+
+    #[test]
+    fn test_add_frames_across_drop() {
+        let timecode = Timecode::new(0, 0, 59, 29, FrameRate::Fps29_97Df) + 1;
+
+        assert_eq!(timecode, Timecode::new(0, 1, 0, 2, FrameRate::Fps29_97Df));
+    }
+
+    #[test]
+    fn test_add_wraps_at_24_hours() {
+        let timecode = Timecode::new(23, 59, 59, 24, FrameRate::Fps25) + 2;
+
+        assert_eq!(timecode, Timecode::new(0, 0, 0, 1, FrameRate::Fps25));
+    }
+
+    #[test]
+    fn test_sub_wraps_below_zero() {
+        let timecode = Timecode::new(0, 0, 0, 0, FrameRate::Fps24) - 1;
+
+        assert_eq!(timecode, Timecode::new(23, 59, 59, 23, FrameRate::Fps24));
+    }
+
+    #[test]
+    fn test_sub_timecodes() {
+        let timecode = Timecode::new(1, 0, 0, 0, FrameRate::Fps25)
+            - Timecode::new(0, 30, 0, 0, FrameRate::Fps25);
+
+        assert_eq!(timecode, Timecode::new(0, 30, 0, 0, FrameRate::Fps25));
+    }
+
+    #[test]
+    fn test_ord_is_chronological() {
+        assert!(
+            Timecode::new(0, 59, 59, 24, FrameRate::Fps25)
+                < Timecode::new(1, 0, 0, 0, FrameRate::Fps25)
+        );
+    }
+
+    #[test]
+    fn test_duration_of_drop_frame_hour() {
+        // One hour of 29.97 DF is 3.6ms short of a real hour
+        let duration = Timecode::new(1, 0, 0, 0, FrameRate::Fps29_97Df).to_duration();
+
+        assert_eq!(duration, Duration::from_nanos(3_599_996_400_000));
+    }
+
+    #[test]
+    fn test_from_duration() {
+        let timecode = Timecode::from_duration(Duration::from_millis(61_040), FrameRate::Fps25);
+
+        assert_eq!(timecode, Timecode::new(0, 1, 1, 1, FrameRate::Fps25));
+    }
+
+    #[test]
+    fn test_with_rate_keeps_wall_clock_time() {
+        let timecode = Timecode::new(0, 0, 10, 12, FrameRate::Fps24).with_rate(FrameRate::Fps50);
+
+        assert_eq!(timecode, Timecode::new(0, 0, 10, 25, FrameRate::Fps50));
+    }
+
+    #[test]
+    fn test_relabel_keeps_labels_at_same_speed() {
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps23_976).relabel(FrameRate::Fps24);
+
+        assert_eq!(timecode, Timecode::new(1, 2, 3, 4, FrameRate::Fps24));
+    }
+
+    #[test]
+    fn test_to_mtc() {
+        let timecode = midi_msg::TimeCode::from(Timecode::new(1, 2, 3, 4, FrameRate::Fps29_97Ndf));
+
+        assert_eq!(timecode.code_type, midi_msg::TimeCodeType::NDF30);
+        assert_eq!(timecode.to_bytes(), [4, 3, 2, 0x61]);
+    }
+
+    #[test]
+    fn test_frame_offset_across_midnight() {
+        let ahead = Timecode::new(0, 0, 0, 2, FrameRate::Fps25);
+        let behind = Timecode::new(23, 59, 59, 23, FrameRate::Fps25);
+
+        assert_eq!(ahead.frame_offset(&behind), 4);
+        assert_eq!(behind.frame_offset(&ahead), -4);
+    }
+
+    #[test]
+    fn test_display_is_padded_with_rate() {
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps25);
 
-        assert_eq!(timecode.hours, 101)
+        assert_eq!(timecode.to_string(), "01:02:03:04 @ 25fps");
     }
 }
@@ -1,5 +1,128 @@
-use crate::backend::timecode::Timecode;
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::backend::{flywheel::SignalState, frame_rate::FrameRate, timecode::Timecode};
+
+/// How many frames an output counts on by itself after the last position from its source, a source that has gone
+/// quiet shouldn't run away
+pub const MAX_EXTRAPOLATION_FRAMES: f64 = 5.0;
+
+/// Which way the timecode source is playing
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PlaybackDirection {
+    Forward,
+    Reverse,
+}
+
+/// Whether a decoder is attached to its input, whether or not anything is arriving on it
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ConnectionState {
+    #[default]
+    Disconnected,
+    Connected,
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Disconnected => write!(f, "Disconnected"),
+            Self::Connected => write!(f, "Connected"),
+        }
+    }
+}
+
+/// Counts of what has gone wrong since the decoder connected
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ErrorCounters {
+    /// Input that could not be understood at all
+    pub malformed: u32,
+    /// Times part of the timecode went missing, E.g: a lost MTC quarter frame
+    pub dropouts: u32,
+}
+
+/// How healthy a timecode source is, the same for every kind of decoder
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct DecoderStatus {
+    pub connection: ConnectionState,
+    pub signal: SignalState,
+    /// When the last value arrived from the source
+    pub last_received: Option<Instant>,
+    /// The frame rate the source says it is sending
+    pub rate: Option<FrameRate>,
+    /// The frame rate values are actually arriving at, which can drift from `rate` if the source is
+    /// varispeeding or running from the wrong clock
+    pub measured_fps: Option<f64>,
+    pub direction: Option<PlaybackDirection>,
+    pub errors: ErrorCounters,
+}
+
+/// A timecode along with how far through that frame the source is
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct TimecodePosition {
+    pub timecode: Timecode,
+    /// From 0.0 up to (but not including) 1.0
+    pub subframe: f64,
+}
+
+impl From<Timecode> for TimecodePosition {
+    /// The start of the frame
+    fn from(timecode: Timecode) -> Self {
+        Self {
+            timecode,
+            subframe: 0.0,
+        }
+    }
+}
+
+impl TimecodePosition {
+    /// Moves the position on by a (possibly fractional or negative) number of frames, wrapping at 24 hours
+    pub fn offset_by_frames(&self, frames: f64) -> Self {
+        let rate = self.timecode.rate();
+        let total = (f64::from(self.timecode.frame_count()) + self.subframe + frames)
+            .rem_euclid(f64::from(rate.frames_per_day()));
+
+        Self {
+            timecode: Timecode::from_frame_count(total.floor() as u32, rate),
+            subframe: total.fract(),
+        }
+    }
+
+    /// Moves the position on by `frames` the way the source is playing, or not at all if it is parked
+    pub fn advance(&self, direction: Option<PlaybackDirection>, frames: f64) -> Self {
+        match direction {
+            Some(PlaybackDirection::Forward) => self.offset_by_frames(frames),
+            Some(PlaybackDirection::Reverse) => self.offset_by_frames(-frames),
+            None => *self,
+        }
+    }
+
+    /// Where the source should have got to `elapsed` after sending this position, counting on for no more than
+    /// [`MAX_EXTRAPOLATION_FRAMES`]
+    pub fn extrapolate(&self, direction: Option<PlaybackDirection>, elapsed: Duration) -> Self {
+        let elapsed_frames =
+            (elapsed.as_secs_f64() * self.timecode.rate().as_f64()).min(MAX_EXTRAPOLATION_FRAMES);
+
+        self.advance(direction, elapsed_frames)
+    }
+}
 
 pub trait TimecodeDecoder {
-    fn get_current_timecode(&mut self) -> Option<Timecode>;
+    /// Gets the most recent position from the source. If no new value is available then it returns None
+    fn get_current_position(&mut self) -> Option<TimecodePosition>;
+
+    /// Like [`TimecodeDecoder::get_current_position`], but only the whole frame
+    fn get_current_timecode(&mut self) -> Option<Timecode> {
+        self.get_current_position()
+            .map(|position| position.timecode)
+    }
+
+    /// How the source is doing, see [`DecoderStatus`]
+    fn status(&self) -> DecoderStatus;
+
+    /// Whether the source is currently locked, freewheeling over a dropout or has stopped
+    fn signal_state(&self) -> SignalState {
+        self.status().signal
+    }
 }
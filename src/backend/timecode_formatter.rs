@@ -0,0 +1,242 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::timecode::Timecode;
+
+/// What a formatted timecode is shown as
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum TimecodeStyle {
+    /// `HH:MM:SS:FF`
+    #[default]
+    Smpte,
+    /// The number of frames since 00:00:00:00
+    TotalFrames,
+    /// The number of seconds since 00:00:00:00, to the millisecond
+    Seconds,
+}
+
+impl Display for TimecodeStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Smpte => write!(f, "SMPTE"),
+            Self::TotalFrames => write!(f, "Total frames"),
+            Self::Seconds => write!(f, "Seconds"),
+        }
+    }
+}
+
+/// How the position within the current frame is shown after the frames field in SMPTE style
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum SubframeStyle {
+    #[default]
+    Hidden,
+    /// 0 to 3, matching MTC quarter frames
+    QuarterFrames,
+    /// 0 to 99, matching MTC and MMC fractional frames
+    Hundredths,
+}
+
+impl Display for SubframeStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Hidden => write!(f, "Hidden"),
+            Self::QuarterFrames => write!(f, "Quarter frames"),
+            Self::Hundredths => write!(f, "Hundredths"),
+        }
+    }
+}
+
+/// Options for turning a [`Timecode`] into text, see [`Timecode::format`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimecodeFormat {
+    pub style: TimecodeStyle,
+    /// Separate the frames with `;` when the rate is drop-frame, as is the convention
+    pub drop_frame_separator: bool,
+    /// Add the frame rate to the end, E.g: `01:02:03:04 @ 25fps`
+    pub show_rate: bool,
+    pub subframes: SubframeStyle,
+}
+
+impl Default for TimecodeFormat {
+    fn default() -> Self {
+        Self {
+            style: TimecodeStyle::Smpte,
+            drop_frame_separator: true,
+            show_rate: false,
+            subframes: SubframeStyle::Hidden,
+        }
+    }
+}
+
+/// A [`Timecode`] paired with the options to display it with, created by [`Timecode::format`]
+#[derive(Debug, Clone, Copy)]
+pub struct FormattedTimecode {
+    timecode: Timecode,
+    format: TimecodeFormat,
+    subframe: f64,
+}
+
+impl FormattedTimecode {
+    /// Sets how far through the current frame we are, from 0.0 up to (but not including) 1.0
+    pub fn with_subframe(mut self, subframe: f64) -> Self {
+        self.subframe = subframe.clamp(0.0, 1.0 - f64::EPSILON);
+        self
+    }
+}
+
+impl Timecode {
+    /// Prepares this timecode to be displayed with the given options, E.g:
+    /// `format!("{}", timecode.format(TimecodeFormat::default()))` gives `01:02:03:04`
+    pub fn format(&self, format: TimecodeFormat) -> FormattedTimecode {
+        FormattedTimecode {
+            timecode: *self,
+            format,
+            subframe: 0.0,
+        }
+    }
+}
+
+impl Display for FormattedTimecode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timecode = self.timecode;
+        let rate = timecode.rate();
+
+        match self.format.style {
+            TimecodeStyle::Smpte => {
+                let separator = if rate.is_drop_frame() && self.format.drop_frame_separator {
+                    ';'
+                } else {
+                    ':'
+                };
+
+                write!(
+                    f,
+                    "{:02}:{:02}:{:02}{separator}{:02}",
+                    timecode.hours(),
+                    timecode.minutes(),
+                    timecode.seconds(),
+                    timecode.frames()
+                )?;
+
+                // The subframe is clamped below 1.0 so these never reach 4 or 100
+                match self.format.subframes {
+                    SubframeStyle::Hidden => {}
+                    SubframeStyle::QuarterFrames => {
+                        write!(f, ".{}", (self.subframe * 4.0).floor() as u8)?;
+                    }
+                    SubframeStyle::Hundredths => {
+                        write!(f, ".{:02}", (self.subframe * 100.0).floor() as u8)?;
+                    }
+                }
+            }
+            TimecodeStyle::TotalFrames => write!(f, "{}", timecode.frame_count())?,
+            TimecodeStyle::Seconds => {
+                let seconds = timecode.to_duration().as_secs_f64() + self.subframe / rate.as_f64();
+                write!(f, "{seconds:.3}")?;
+            }
+        }
+
+        if self.format.show_rate {
+            write!(f, " @ {rate}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::frame_rate::FrameRate;
+
+    #[test]
+    fn test_default_format_is_padded() {
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps25);
+
+        assert_eq!(
+            timecode.format(TimecodeFormat::default()).to_string(),
+            "01:02:03:04"
+        );
+    }
+
+    #[test]
+    fn test_drop_frame_separator() {
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps29_97Df);
+
+        assert_eq!(
+            timecode.format(TimecodeFormat::default()).to_string(),
+            "01:02:03;04"
+        );
+    }
+
+    #[test]
+    fn test_drop_frame_separator_disabled() {
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps29_97Df);
+        let format = TimecodeFormat {
+            drop_frame_separator: false,
+            show_rate: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            timecode.format(format).to_string(),
+            "01:02:03:04 @ 29.97fps DF"
+        );
+    }
+
+    #[test]
+    fn test_quarter_frames() {
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps25);
+        let format = TimecodeFormat {
+            subframes: SubframeStyle::QuarterFrames,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            timecode.format(format).with_subframe(0.75).to_string(),
+            "01:02:03:04.3"
+        );
+    }
+
+    #[test]
+    fn test_hundredths_never_reach_100() {
+        let timecode = Timecode::new(1, 2, 3, 4, FrameRate::Fps25);
+        let format = TimecodeFormat {
+            subframes: SubframeStyle::Hundredths,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            timecode.format(format).with_subframe(1.0).to_string(),
+            "01:02:03:04.99"
+        );
+    }
+
+    #[test]
+    fn test_total_frames() {
+        let timecode = Timecode::new(0, 1, 0, 2, FrameRate::Fps29_97Df);
+        let format = TimecodeFormat {
+            style: TimecodeStyle::TotalFrames,
+            ..Default::default()
+        };
+
+        assert_eq!(timecode.format(format).to_string(), "1800");
+    }
+
+    #[test]
+    fn test_seconds() {
+        let timecode = Timecode::new(0, 1, 1, 12, FrameRate::Fps24);
+        let format = TimecodeFormat {
+            style: TimecodeStyle::Seconds,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            timecode.format(format).with_subframe(0.5).to_string(),
+            "61.521"
+        );
+    }
+}
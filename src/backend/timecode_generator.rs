@@ -0,0 +1,386 @@
+use std::{fmt::Display, time::Instant};
+
+use crate::backend::{
+    flywheel::SignalState,
+    frame_rate::FrameRate,
+    timecode::Timecode,
+    timecode_decoder::{
+        ConnectionState, DecoderStatus, PlaybackDirection, TimecodeDecoder, TimecodePosition,
+    },
+};
+
+/// What the generator's transport is doing
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TransportState {
+    /// Parked at the start time
+    #[default]
+    Stopped,
+    /// Parked wherever it was when paused
+    Paused,
+    Playing,
+}
+
+impl Display for TransportState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Stopped => write!(f, "Stopped"),
+            Self::Paused => write!(f, "Paused"),
+            Self::Playing => write!(f, "Playing"),
+        }
+    }
+}
+
+/// A stretch of timecode to play round and round, E.g: a scene that is being rehearsed
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LoopRegion {
+    pub start: Timecode,
+    /// The first frame that is not played, playing forwards reaches this and goes back to `start`
+    pub end: Timecode,
+}
+
+/// A timecode source that runs from its own clock, for when there is nothing playing timecode to read
+pub struct TimecodeGenerator {
+    /// Where stopping goes back to, also sets the rate the generator counts in
+    start: Timecode,
+    loop_region: Option<LoopRegion>,
+    /// 1.0 is real time, negative plays backwards
+    speed: f64,
+    state: TransportState,
+    /// Where the playhead was in frames and when, it moves on from here while playing
+    anchor: (f64, Instant),
+    /// The playhead has moved while parked and nothing has read it yet
+    changed: bool,
+}
+
+impl TimecodeGenerator {
+    /// Creates a new generator, stopped at `start`
+    pub fn new(start: Timecode) -> Self {
+        Self {
+            start,
+            loop_region: None,
+            speed: 1.0,
+            state: TransportState::Stopped,
+            anchor: (f64::from(start.frame_count()), Instant::now()),
+            changed: true,
+        }
+    }
+
+    pub fn start(&self) -> Timecode {
+        self.start
+    }
+
+    /// Sets where stopping goes back to, moving there straight away if the generator is stopped
+    pub fn set_start(&mut self, start: Timecode, now: Instant) {
+        self.start = start.relabel(self.rate());
+
+        if self.state == TransportState::Stopped {
+            self.locate(self.start, now);
+        }
+    }
+
+    pub fn rate(&self) -> FrameRate {
+        self.start.rate()
+    }
+
+    /// Changes the rate the generator counts in, moving the playhead, start and loop region across with
+    /// [`Timecode::relabel`]
+    pub fn set_rate(&mut self, rate: FrameRate, now: Instant) {
+        let position = self.position_at(now);
+
+        self.start = self.start.relabel(rate);
+        self.loop_region = self.loop_region.map(|region| LoopRegion {
+            start: region.start.relabel(rate),
+            end: region.end.relabel(rate),
+        });
+        self.anchor = (
+            f64::from(position.timecode.relabel(rate).frame_count()) + position.subframe,
+            now,
+        );
+        self.changed = true;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Sets how fast the generator plays, 1.0 is real time and negative plays backwards
+    pub fn set_speed(&mut self, speed: f64, now: Instant) {
+        self.anchor = (self.frames_at(now), now);
+        self.speed = speed;
+    }
+
+    pub fn loop_region(&self) -> Option<LoopRegion> {
+        self.loop_region
+    }
+
+    /// Sets the region to loop round, None to play straight through. Regions that end before they start are ignored
+    pub fn set_loop_region(&mut self, loop_region: Option<LoopRegion>, now: Instant) {
+        self.anchor = (self.frames_at(now), now);
+        self.loop_region = loop_region.map(|region| LoopRegion {
+            start: region.start.relabel(self.rate()),
+            end: region.end.relabel(self.rate()),
+        });
+    }
+
+    pub fn transport_state(&self) -> TransportState {
+        self.state
+    }
+
+    /// Plays on from wherever the playhead is
+    pub fn play(&mut self, now: Instant) {
+        if self.state == TransportState::Playing {
+            return;
+        }
+
+        self.anchor = (self.frames_at(now), now);
+        self.state = TransportState::Playing;
+    }
+
+    /// Holds the playhead where it is
+    pub fn pause(&mut self, now: Instant) {
+        self.anchor = (self.frames_at(now), now);
+        self.state = TransportState::Paused;
+        self.changed = true;
+    }
+
+    /// Parks the playhead back at the start time
+    pub fn stop(&mut self, now: Instant) {
+        self.state = TransportState::Stopped;
+        self.locate(self.start, now);
+    }
+
+    /// Jumps the playhead to a timecode, carrying on playing from there if it was already
+    pub fn locate(&mut self, timecode: Timecode, now: Instant) {
+        self.anchor = (f64::from(timecode.relabel(self.rate()).frame_count()), now);
+        self.changed = true;
+    }
+
+    /// Where the playhead is at `now`
+    pub fn position_at(&self, now: Instant) -> TimecodePosition {
+        let frames = self.frames_at(now);
+
+        TimecodePosition {
+            timecode: Timecode::from_frame_count(frames.floor() as u32, self.rate()),
+            subframe: frames.fract(),
+        }
+    }
+
+    /// Where the playhead is at `now` as a count of frames, wrapped into the loop region and the 24 hour clock
+    fn frames_at(&self, now: Instant) -> f64 {
+        let (anchor_frames, anchor_time) = self.anchor;
+        let rate = self.rate();
+
+        if self.state != TransportState::Playing {
+            return anchor_frames;
+        }
+
+        let elapsed = now.saturating_duration_since(anchor_time).as_secs_f64();
+        let mut frames = anchor_frames + elapsed * rate.as_f64() * self.speed;
+
+        if let Some(region) = self.loop_region {
+            let start = f64::from(region.start.frame_count());
+            let end = f64::from(region.end.frame_count());
+
+            // Only loop when running off the far end in the direction of play, so locating outside the region
+            // plays on as normal until the playhead gets into it
+            let crossed_end = self.speed > 0.0 && anchor_frames < end && frames >= end;
+            let crossed_start = self.speed < 0.0 && anchor_frames >= start && frames < start;
+
+            if end > start && (crossed_end || crossed_start) {
+                frames = start + (frames - start).rem_euclid(end - start);
+            }
+        }
+
+        frames.rem_euclid(f64::from(rate.frames_per_day()))
+    }
+}
+
+impl Default for TimecodeGenerator {
+    /// Stopped at 01:00:00:00, 25fps
+    fn default() -> Self {
+        Self::new(Timecode::new(1, 0, 0, 0, FrameRate::Fps25))
+    }
+}
+
+impl TimecodeDecoder for TimecodeGenerator {
+    /// Gets where the playhead is. While parked this only returns a position when it has moved
+    fn get_current_position(&mut self) -> Option<TimecodePosition> {
+        if self.state != TransportState::Playing && !self.changed {
+            return None;
+        }
+
+        self.changed = false;
+        Some(self.position_at(Instant::now()))
+    }
+
+    fn status(&self) -> DecoderStatus {
+        let playing = self.state == TransportState::Playing;
+
+        let direction = if !playing || self.speed == 0.0 {
+            None
+        } else if self.speed > 0.0 {
+            Some(PlaybackDirection::Forward)
+        } else {
+            Some(PlaybackDirection::Reverse)
+        };
+
+        DecoderStatus {
+            connection: ConnectionState::Connected,
+            signal: if playing {
+                SignalState::Locked
+            } else {
+                SignalState::Stopped
+            },
+            last_received: None,
+            rate: Some(self.rate()),
+            measured_fps: playing.then(|| self.rate().as_f64() * self.speed.abs()),
+            direction,
+            errors: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn generator_at(timecode: Timecode) -> (TimecodeGenerator, Instant) {
+        let now = Instant::now();
+        let mut generator = TimecodeGenerator::new(timecode);
+        generator.locate(timecode, now);
+
+        (generator, now)
+    }
+
+    #[test]
+    fn test_transport() {
+        let start = Timecode::new(1, 0, 0, 0, FrameRate::Fps25);
+        let (mut generator, now) = generator_at(start);
+
+        generator.play(now);
+        let playing = generator.position_at(now + Duration::from_millis(1020));
+        assert_eq!(
+            playing.timecode,
+            Timecode::new(1, 0, 1, 0, FrameRate::Fps25)
+        );
+        assert!((playing.subframe - 0.5).abs() < 1e-6);
+
+        // Pausing holds the playhead where it got to
+        generator.pause(now + Duration::from_secs(2));
+        assert_eq!(
+            generator
+                .position_at(now + Duration::from_secs(10))
+                .timecode,
+            Timecode::new(1, 0, 2, 0, FrameRate::Fps25)
+        );
+
+        // Playing again carries on from there
+        generator.play(now + Duration::from_secs(10));
+        assert_eq!(
+            generator
+                .position_at(now + Duration::from_secs(11))
+                .timecode,
+            Timecode::new(1, 0, 3, 0, FrameRate::Fps25)
+        );
+
+        // Stopping goes back to the start
+        generator.stop(now + Duration::from_secs(12));
+        assert_eq!(
+            generator
+                .position_at(now + Duration::from_secs(20))
+                .timecode,
+            start
+        );
+        assert_eq!(generator.transport_state(), TransportState::Stopped);
+    }
+
+    #[test]
+    fn test_speed() {
+        let (mut generator, now) = generator_at(Timecode::new(1, 0, 0, 0, FrameRate::Fps30));
+
+        generator.set_speed(2.0, now);
+        generator.play(now);
+        assert_eq!(
+            generator.position_at(now + Duration::from_secs(1)).timecode,
+            Timecode::new(1, 0, 2, 0, FrameRate::Fps30)
+        );
+        assert_eq!(generator.status().measured_fps, Some(60.0));
+
+        // Changing speed while playing carries on from where the playhead is
+        generator.set_speed(-1.0, now + Duration::from_secs(1));
+        assert_eq!(
+            generator.position_at(now + Duration::from_secs(3)).timecode,
+            Timecode::new(1, 0, 0, 0, FrameRate::Fps30)
+        );
+        assert_eq!(
+            generator.status().direction,
+            Some(PlaybackDirection::Reverse)
+        );
+    }
+
+    #[test]
+    fn test_loop_region() {
+        let (mut generator, now) = generator_at(Timecode::new(0, 59, 59, 0, FrameRate::Fps25));
+
+        generator.set_loop_region(
+            Some(LoopRegion {
+                start: Timecode::new(1, 0, 0, 0, FrameRate::Fps25),
+                end: Timecode::new(1, 0, 10, 0, FrameRate::Fps25),
+            }),
+            now,
+        );
+        generator.play(now);
+
+        // Plays into the region from before it as normal
+        assert_eq!(
+            generator.position_at(now + Duration::from_secs(5)).timecode,
+            Timecode::new(1, 0, 4, 0, FrameRate::Fps25)
+        );
+
+        // Then goes back round when it reaches the end
+        assert_eq!(
+            generator
+                .position_at(now + Duration::from_secs(13))
+                .timecode,
+            Timecode::new(1, 0, 2, 0, FrameRate::Fps25)
+        );
+    }
+
+    #[test]
+    fn test_only_reports_parked_position_once() {
+        let (mut generator, now) = generator_at(Timecode::new(1, 0, 0, 0, FrameRate::Fps25));
+
+        assert!(generator.get_current_position().is_some());
+        assert!(generator.get_current_position().is_none());
+        assert_eq!(generator.signal_state(), SignalState::Stopped);
+
+        generator.locate(Timecode::new(2, 0, 0, 0, FrameRate::Fps25), now);
+        assert_eq!(
+            generator.get_current_timecode(),
+            Some(Timecode::new(2, 0, 0, 0, FrameRate::Fps25))
+        );
+
+        // Always has a new position while playing
+        generator.play(now);
+        assert!(generator.get_current_position().is_some());
+        assert!(generator.get_current_position().is_some());
+        assert_eq!(generator.signal_state(), SignalState::Locked);
+    }
+
+    #[test]
+    fn test_set_rate_keeps_labels() {
+        let (mut generator, now) = generator_at(Timecode::new(1, 0, 0, 12, FrameRate::Fps30));
+
+        generator.set_rate(FrameRate::Fps29_97Ndf, now);
+        assert_eq!(
+            generator.position_at(now).timecode,
+            Timecode::new(1, 0, 0, 12, FrameRate::Fps29_97Ndf)
+        );
+        assert_eq!(
+            generator.start(),
+            Timecode::new(1, 0, 0, 12, FrameRate::Fps29_97Ndf)
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    timecode::{Timecode, TimecodeError},
+};
+
+/// The number of frames in one foot of 35mm 4-perf film, used for feet+frames notation
+const FRAMES_PER_FOOT: u32 = 16;
+
+/// Everything that can go wrong when turning a string into a [`Timecode`] or [`FrameRate`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseTimecodeError {
+    /// There was nothing to parse
+    Empty,
+    /// A field wasn't a whole, positive number (or a decimal for seconds)
+    InvalidNumber(String),
+    /// SMPTE notation needs exactly hours, minutes, seconds and frames
+    WrongFieldCount(usize),
+    /// A field was too large, E.g: 61 minutes or frame 25 at 25fps
+    OutOfRange {
+        field: &'static str,
+        value: u32,
+        max: u32,
+    },
+    /// The label is one of the frame numbers that drop-frame skips, E.g: 00:01:00;00
+    DroppedFrame,
+    /// A drop-frame separator was used with a rate that has no drop-frame variant
+    DropFrameNotSupported(FrameRate),
+    /// The frame rate wasn't one we know about
+    UnknownFrameRate(String),
+}
+
+impl Display for ParseTimecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "No timecode was given"),
+            Self::InvalidNumber(value) => write!(f, "\"{value}\" is not a valid number"),
+            Self::WrongFieldCount(count) => {
+                write!(f, "Expected 4 fields (HH:MM:SS:FF) but found {count}")
+            }
+            Self::OutOfRange { field, value, max } => {
+                write!(f, "{field} must be at most {max}, but was {value}")
+            }
+            Self::DroppedFrame => write!(f, "This frame number is skipped by drop-frame timecode"),
+            Self::DropFrameNotSupported(rate) => write!(f, "{rate} has no drop-frame variant"),
+            Self::UnknownFrameRate(value) => write!(f, "\"{value}\" is not a known frame rate"),
+        }
+    }
+}
+
+impl std::error::Error for ParseTimecodeError {}
+
+impl From<TimecodeError> for ParseTimecodeError {
+    fn from(value: TimecodeError) -> Self {
+        match value {
+            TimecodeError::OutOfRange { field, value, max } => Self::OutOfRange {
+                field,
+                value: u32::from(value),
+                max: u32::from(max),
+            },
+            TimecodeError::DroppedFrame => Self::DroppedFrame,
+        }
+    }
+}
+
+impl Timecode {
+    /// Parses a timecode typed by a user, in any of these forms:
+    ///     `HH:MM:SS:FF`  SMPTE timecode
+    ///     `HH:MM:SS;FF`  drop-frame timecode, `HH:MM:SS.FF` is also accepted
+    ///     `FEET+FF`      35mm 4-perf feet and frames
+    ///     `SS.sss`       seconds with decimals
+    ///     `FFFF`         a plain frame count
+    ///
+    /// A drop-frame separator switches `rate` to its drop-frame variant, E.g: 30fps becomes 29.97fps DF.
+    /// `.` only counts as one at rates that have a drop-frame variant, at 25fps it is just a separator.
+    /// Every form has to be within a day, anything later is rejected rather than wrapped around to midnight.
+    ///
+    /// # Errors
+    /// Returns a [`ParseTimecodeError`] describing the first problem found in `text`
+    pub fn parse(text: &str, rate: FrameRate) -> Result<Self, ParseTimecodeError> {
+        let text = text.trim();
+
+        if text.is_empty() {
+            Err(ParseTimecodeError::Empty)
+        } else if let Some((feet, frames)) = text.split_once('+') {
+            let feet = parse_number(feet)?;
+            let frames = check_range("Frames", parse_number(frames)?, FRAMES_PER_FOOT - 1)?;
+            let feet = check_range(
+                "Feet",
+                feet,
+                (rate.frames_per_day() - 1 - frames) / FRAMES_PER_FOOT,
+            )?;
+
+            Ok(Self::from_frame_count(
+                feet * FRAMES_PER_FOOT + frames,
+                rate,
+            ))
+        } else if text.contains([':', ';']) {
+            parse_smpte(text, rate)
+        } else if text.contains('.') {
+            let seconds = text
+                .parse::<f64>()
+                .ok()
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .ok_or_else(|| ParseTimecodeError::InvalidNumber(text.to_owned()))?;
+
+            let day = day_length(rate);
+            if seconds >= day {
+                return Err(ParseTimecodeError::OutOfRange {
+                    field: "Seconds",
+                    value: u32::try_from(seconds.as_secs()).unwrap_or(u32::MAX),
+                    max: Self::from_frame_count(rate.frames_per_day() - 1, rate)
+                        .to_duration()
+                        .as_secs() as u32,
+                });
+            }
+
+            Ok(Self::from_duration(seconds, rate))
+        } else {
+            let frame_count = check_range(
+                "Frame count",
+                parse_number(text)?,
+                rate.frames_per_day() - 1,
+            )?;
+
+            Ok(Self::from_frame_count(frame_count, rate))
+        }
+    }
+}
+
+impl FromStr for Timecode {
+    type Err = ParseTimecodeError;
+
+    /// Parses anything [`Timecode::parse`] accepts, optionally followed by a rate as printed by
+    /// [`Display`], E.g: `01:00:00:00 @ 25fps`. Without a rate the default of 30fps is used
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.split_once('@') {
+            Some((timecode, rate)) => Self::parse(timecode, rate.parse()?),
+            None => Self::parse(text, FrameRate::default()),
+        }
+    }
+}
+
+/// Parses the `HH:MM:SS:FF` family of notations
+fn parse_smpte(text: &str, rate: FrameRate) -> Result<Timecode, ParseTimecodeError> {
+    let fields = text.split([':', ';', '.']).collect::<Vec<_>>();
+    let [hours, minutes, seconds, frames] = fields[..] else {
+        return Err(ParseTimecodeError::WrongFieldCount(fields.len()));
+    };
+
+    // `;` anywhere marks drop-frame. So does `.` before the frames, but only at rates that have a drop-frame
+    // variant, otherwise it is just a separator, E.g: 01:00:00.10 at 25fps
+    let period_before_frames = text
+        .rfind([':', ';', '.'])
+        .and_then(|index| text.get(index..))
+        .is_some_and(|rest| rest.starts_with('.'));
+
+    let rate = if text.contains(';') {
+        rate.drop_frame_variant()
+            .ok_or(ParseTimecodeError::DropFrameNotSupported(rate))?
+    } else if period_before_frames {
+        rate.drop_frame_variant().unwrap_or(rate)
+    } else {
+        rate
+    };
+
+    let hours = check_range("Hours", parse_number(hours)?, 23)?;
+    let minutes = check_range("Minutes", parse_number(minutes)?, 59)?;
+    let seconds = check_range("Seconds", parse_number(seconds)?, 59)?;
+    let frames = check_range(
+        "Frames",
+        parse_number(frames)?,
+        u32::from(rate.nominal_fps()) - 1,
+    )?;
+
+    // All the fields have been range checked so they fit in a u8, this catches dropped frames
+    Ok(Timecode::try_new(
+        hours as u8,
+        minutes as u8,
+        seconds as u8,
+        frames as u8,
+        rate,
+    )?)
+}
+
+/// How long a day of timecode lasts at `rate`, longer than 24 hours at fractional rates
+fn day_length(rate: FrameRate) -> Duration {
+    let (numerator, denominator) = rate.as_ratio();
+
+    Duration::from_nanos(
+        u64::from(rate.frames_per_day()) * u64::from(denominator) * 1_000_000_000
+            / u64::from(numerator),
+    )
+}
+
+fn parse_number(text: &str) -> Result<u32, ParseTimecodeError> {
+    let text = text.trim();
+
+    text.parse()
+        .map_err(|_err| ParseTimecodeError::InvalidNumber(text.to_owned()))
+}
+
+fn check_range(field: &'static str, value: u32, max: u32) -> Result<u32, ParseTimecodeError> {
+    if value > max {
+        Err(ParseTimecodeError::OutOfRange { field, value, max })
+    } else {
+        Ok(value)
+    }
+}
+
+impl FromStr for FrameRate {
+    type Err = ParseTimecodeError;
+
+    /// Parses a rate such as `25`, `25fps`, `29.97 DF` or `29.97fps NDF`. A fractional rate without
+    /// DF or NDF is taken to be non drop-frame
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let normalised = text
+            .to_lowercase()
+            .replace("fps", "")
+            .replace(char::is_whitespace, "");
+
+        match normalised.as_str() {
+            "23.976" | "23.98" => Ok(Self::Fps23_976),
+            "24" => Ok(Self::Fps24),
+            "25" => Ok(Self::Fps25),
+            "29.97df" | "30df" => Ok(Self::Fps29_97Df),
+            "29.97" | "29.97ndf" => Ok(Self::Fps29_97Ndf),
+            "30" => Ok(Self::Fps30),
+            "48" => Ok(Self::Fps48),
+            "50" => Ok(Self::Fps50),
+            "59.94df" | "60df" => Ok(Self::Fps59_94Df),
+            "59.94" | "59.94ndf" => Ok(Self::Fps59_94Ndf),
+            "60" => Ok(Self::Fps60),
+            _ => Err(ParseTimecodeError::UnknownFrameRate(text.trim().to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_smpte() {
+        let timecode = Timecode::parse("01:02:03:04", FrameRate::Fps25);
+
+        assert_eq!(timecode, Ok(Timecode::new(1, 2, 3, 4, FrameRate::Fps25)));
+    }
+
+    #[test]
+    fn test_parse_drop_frame_separator() {
+        let timecode = Timecode::parse("00:01:00;02", FrameRate::Fps30);
+
+        assert_eq!(
+            timecode,
+            Ok(Timecode::new(0, 1, 0, 2, FrameRate::Fps29_97Df))
+        );
+    }
+
+    #[test]
+    fn test_parse_drop_frame_period() {
+        let timecode = Timecode::parse("00:00:10.15", FrameRate::Fps29_97Ndf);
+
+        assert_eq!(
+            timecode,
+            Ok(Timecode::new(0, 0, 10, 15, FrameRate::Fps29_97Df))
+        );
+    }
+
+    #[test]
+    fn test_parse_period_without_drop_frame() {
+        let timecode = Timecode::parse("01:00:00.10", FrameRate::Fps25);
+
+        assert_eq!(timecode, Ok(Timecode::new(1, 0, 0, 10, FrameRate::Fps25)));
+    }
+
+    #[test]
+    fn test_parse_dropped_label() {
+        let timecode = Timecode::parse("00:01:00;00", FrameRate::Fps29_97Df);
+
+        assert_eq!(timecode, Err(ParseTimecodeError::DroppedFrame));
+    }
+
+    #[test]
+    fn test_parse_drop_frame_unsupported() {
+        let timecode = Timecode::parse("00:01:00;02", FrameRate::Fps25);
+
+        assert_eq!(
+            timecode,
+            Err(ParseTimecodeError::DropFrameNotSupported(FrameRate::Fps25))
+        );
+    }
+
+    #[test]
+    fn test_parse_frames_out_of_range() {
+        let timecode = Timecode::parse("00:00:00:25", FrameRate::Fps25);
+
+        assert_eq!(
+            timecode,
+            Err(ParseTimecodeError::OutOfRange {
+                field: "Frames",
+                value: 25,
+                max: 24
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_wrong_field_count() {
+        let timecode = Timecode::parse("00:00:00", FrameRate::Fps25);
+
+        assert_eq!(timecode, Err(ParseTimecodeError::WrongFieldCount(3)));
+    }
+
+    #[test]
+    fn test_parse_feet_and_frames() {
+        let timecode = Timecode::parse("90+00", FrameRate::Fps24);
+
+        assert_eq!(timecode, Ok(Timecode::new(0, 1, 0, 0, FrameRate::Fps24)));
+    }
+
+    #[test]
+    fn test_parse_feet_out_of_range() {
+        let timecode = Timecode::parse("300000000+0", FrameRate::Fps24);
+
+        assert_eq!(
+            timecode,
+            Err(ParseTimecodeError::OutOfRange {
+                field: "Feet",
+                value: 300_000_000,
+                max: 129_599
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_feet_past_a_day() {
+        assert_eq!(
+            Timecode::parse("129599+15", FrameRate::Fps24),
+            Ok(Timecode::new(23, 59, 59, 23, FrameRate::Fps24))
+        );
+        assert_eq!(
+            Timecode::parse("129600+00", FrameRate::Fps24),
+            Err(ParseTimecodeError::OutOfRange {
+                field: "Feet",
+                value: 129_600,
+                max: 129_599
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_seconds() {
+        let timecode = Timecode::parse("61.5", FrameRate::Fps24);
+
+        assert_eq!(timecode, Ok(Timecode::new(0, 1, 1, 12, FrameRate::Fps24)));
+    }
+
+    #[test]
+    fn test_parse_frame_count() {
+        let timecode = Timecode::parse("1800", FrameRate::Fps29_97Df);
+
+        assert_eq!(
+            timecode,
+            Ok(Timecode::new(0, 1, 0, 2, FrameRate::Fps29_97Df))
+        );
+    }
+
+    #[test]
+    fn test_parse_seconds_past_a_day() {
+        assert_eq!(
+            Timecode::parse("86399.99", FrameRate::Fps25),
+            Ok(Timecode::new(23, 59, 59, 24, FrameRate::Fps25))
+        );
+        assert_eq!(
+            Timecode::parse("86400.5", FrameRate::Fps25),
+            Err(ParseTimecodeError::OutOfRange {
+                field: "Seconds",
+                value: 86_400,
+                max: 86_399
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_frame_count_past_a_day() {
+        assert_eq!(
+            Timecode::parse("2159999", FrameRate::Fps25),
+            Ok(Timecode::new(23, 59, 59, 24, FrameRate::Fps25))
+        );
+        assert_eq!(
+            Timecode::parse("2160000", FrameRate::Fps25),
+            Err(ParseTimecodeError::OutOfRange {
+                field: "Frame count",
+                value: 2_160_000,
+                max: 2_159_999
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_number() {
+        let timecode = Timecode::parse("00:aa:00:00", FrameRate::Fps25);
+
+        assert_eq!(
+            timecode,
+            Err(ParseTimecodeError::InvalidNumber("aa".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_from_str_round_trips_display() {
+        let timecode = Timecode::new(10, 20, 30, 12, FrameRate::Fps29_97Df);
+
+        assert_eq!(timecode.to_string().parse(), Ok(timecode));
+    }
+
+    #[test]
+    fn test_frame_rate_from_str() {
+        assert_eq!("23.976".parse(), Ok(FrameRate::Fps23_976));
+        assert_eq!("59.94 DF".parse(), Ok(FrameRate::Fps59_94Df));
+        assert_eq!(
+            "12fps".parse::<FrameRate>(),
+            Err(ParseTimecodeError::UnknownFrameRate("12fps".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,9 @@
+#![warn(clippy::all, rust_2018_idioms)]
+// Reads timecode on the command line. A binary of its own, as the app has no console on Windows.
+// No windows_subsystem here, so Windows gives this its own console to print to
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+
+    technus_timecode::headless::run(std::env::args().skip(1))
+}
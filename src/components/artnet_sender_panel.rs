@@ -0,0 +1,137 @@
+use std::net::IpAddr;
+
+use egui::DragValue;
+use egui_notify::Toasts;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{
+        artnet_sender::{ArtnetSenderSettings, ArtnetTimecodeSender},
+        artnet_timecode::ArtnetTimecodeType,
+    },
+    devices::DeviceLists,
+};
+
+/// Broadcast addresses that reach every Art-Net device, whatever interface they are on
+const ARTNET_BROADCASTS: [(&str, &str); 3] = [
+    ("2.255.255.255", "Art-Net primary"),
+    ("10.255.255.255", "Art-Net secondary"),
+    ("255.255.255.255", "Everything on the network"),
+];
+
+/// Sends timecode out as Art-Net, from whichever source is routed to it
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtnetSenderPanel {
+    settings: ArtnetSenderSettings,
+    /// The address to send to as typed, only parsed when sending starts
+    target_text: String,
+}
+
+impl Default for ArtnetSenderPanel {
+    fn default() -> Self {
+        let settings = ArtnetSenderSettings::default();
+
+        Self {
+            target_text: settings.target.to_string(),
+            settings,
+        }
+    }
+}
+
+impl ArtnetSenderPanel {
+    pub fn add(
+        &mut self,
+        ui: &mut egui::Ui,
+        sender: &mut ArtnetTimecodeSender,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+    ) {
+        ui.add_enabled_ui(!sender.running(), |ui| {
+            self.add_settings(ui, devices);
+        });
+
+        ui.horizontal(|ui| {
+            let start_button_text: &str = if sender.running() { "Stop" } else { "Start" };
+
+            if ui.button(start_button_text).clicked() {
+                self.toggle(sender, toasts);
+            }
+        });
+    }
+
+    /// Displays the UI elements to choose the target, rate and stream
+    fn add_settings(&mut self, ui: &mut egui::Ui, devices: &mut DeviceLists) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Rate")
+                .selected_text(
+                    self.settings
+                        .rate
+                        .map_or_else(|| "Same as source".to_owned(), |rate| format!("{rate}")),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.settings.rate, None, "Same as source");
+
+                    for rate in ArtnetTimecodeType::ALL {
+                        ui.selectable_value(&mut self.settings.rate, Some(rate), format!("{rate}"));
+                    }
+                });
+
+            ui.label("Stream ID:")
+                .on_hover_text("0 is the master stream, use others to send several timecodes");
+            ui.add(DragValue::new(&mut self.settings.stream_id));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Send to:").on_hover_text(
+                "A broadcast address to reach a whole subnet, or a single desk's address",
+            );
+            ui.text_edit_singleline(&mut self.target_text);
+
+            egui::ComboBox::from_id_salt("artnet_target")
+                .selected_text("Broadcast to…")
+                .show_ui(ui, |ui| {
+                    for (address, description) in ARTNET_BROADCASTS {
+                        if ui.button(format!("{address} ({description})")).clicked() {
+                            address.clone_into(&mut self.target_text);
+                        }
+                    }
+
+                    for interface in devices.interfaces() {
+                        if let Some(broadcast) = interface.broadcast
+                            && ui
+                                .button(format!("{broadcast} ({} subnet)", interface.name))
+                                .clicked()
+                        {
+                            self.target_text = broadcast.to_string();
+                        }
+                    }
+                });
+        });
+    }
+
+    /// Starts or stops sending, toasting if that didn't work
+    fn toggle(&mut self, sender: &mut ArtnetTimecodeSender, toasts: &mut Toasts) {
+        if sender.running() {
+            if let Err(e) = sender.stop() {
+                toasts.error(format!("Failed to stop sending Art-Net: {e}"));
+            }
+            return;
+        }
+
+        let Ok(target) = self.target_text.trim().parse::<IpAddr>() else {
+            toasts.error(format!("{} is not an IP address", self.target_text));
+            return;
+        };
+        self.settings.target = target;
+
+        match sender.start(self.settings) {
+            Ok(()) => {
+                toasts.success(format!("Sending Art-Net timecode to {target}"));
+            }
+            Err(e) => {
+                toasts.error(format!("Failed to start sending Art-Net: {e}"));
+            }
+        }
+    }
+}
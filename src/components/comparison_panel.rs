@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::GlobalState,
+    backend::{
+        flywheel::SignalState,
+        timecode_decoder::{TimecodeDecoder as _, TimecodePosition},
+    },
+    input::InputId,
+};
+
+/// Shows how far apart two inputs are, E.g: to check main and backup timecode agree
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComparisonPanel {
+    first: Option<InputId>,
+    second: Option<InputId>,
+}
+
+impl ComparisonPanel {
+    pub fn add(&mut self, ui: &mut egui::Ui, global_state: &GlobalState) {
+        ui.collapsing("Compare inputs", |ui| {
+            ui.horizontal(|ui| {
+                Self::add_input_picker(ui, global_state, "compare_first", &mut self.first);
+                ui.label("against");
+                Self::add_input_picker(ui, global_state, "compare_second", &mut self.second);
+            });
+
+            let (Some(first), Some(second)) = (self.first, self.second) else {
+                ui.label("Pick two inputs to compare");
+                return;
+            };
+            let (Some(first_input), Some(second_input)) = (
+                global_state.inputs.get(&first),
+                global_state.inputs.get(&second),
+            ) else {
+                ui.label("Pick two inputs to compare");
+                return;
+            };
+            let first_name = &first_input.name;
+            let second_name = &second_input.name;
+            let first_signal = first_input.decoder.signal_state();
+            let second_signal = second_input.decoder.signal_state();
+
+            ui.label(format!(
+                "{first_name} is {first_signal}, {second_name} is {second_signal}"
+            ));
+
+            let first_position = global_state.latest_position(first);
+            let second_position = global_state.latest_position(second);
+            let Some(offset) =
+                comparable_offset(first_position, first_signal, second_position, second_signal)
+            else {
+                ui.label("Waiting for both inputs to be running, or both to be stopped");
+                return;
+            };
+
+            let description = match offset {
+                0 => format!("{first_name} and {second_name} are on the same frame"),
+                ..0 => format!(
+                    "{first_name} is {} frames behind {second_name}",
+                    offset.unsigned_abs()
+                ),
+                _ => format!("{first_name} is {offset} frames ahead of {second_name}"),
+            };
+            ui.label(description);
+
+            // Frames are counted at the first input's rate
+            if let (Some(first_position), Some(second_position)) = (first_position, second_position)
+                && first_position.timecode.rate() != second_position.timecode.rate()
+            {
+                ui.label(format!(
+                    "Counted in {}, {} is running at {}",
+                    first_position.timecode.rate(),
+                    second_name,
+                    second_position.timecode.rate()
+                ));
+            }
+        });
+    }
+
+    /// Displays a combo box to choose one of the inputs
+    fn add_input_picker(
+        ui: &mut egui::Ui,
+        global_state: &GlobalState,
+        id_salt: &str,
+        selected: &mut Option<InputId>,
+    ) {
+        let selected_name = selected
+            .and_then(|id| global_state.inputs.get(&id))
+            .map_or("Select an input", |input| &input.name);
+
+        egui::ComboBox::from_id_salt(id_salt)
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                for (id, input) in &global_state.inputs {
+                    ui.selectable_value(selected, Some(*id), &input.name);
+                }
+            });
+    }
+}
+
+/// How many frames the first position is ahead of the second, None unless both inputs are running or both are
+/// stopped. Two parked machines can be checked against each other, E.g: in a pause in the show, but a running input
+/// against one that has stopped or lost its signal would compare against a stale position
+fn comparable_offset(
+    first: Option<TimecodePosition>,
+    first_signal: SignalState,
+    second: Option<TimecodePosition>,
+    second_signal: SignalState,
+) -> Option<i64> {
+    let live = |signal| matches!(signal, SignalState::Locked | SignalState::Freewheeling);
+    let comparable = (live(first_signal) && live(second_signal))
+        || (first_signal == SignalState::Stopped && second_signal == SignalState::Stopped);
+    if !comparable {
+        return None;
+    }
+
+    Some(first?.timecode.frame_offset(&second?.timecode))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{frame_rate::FrameRate, timecode::Timecode};
+
+    fn timecode(frames: u8) -> Timecode {
+        Timecode::new(1, 0, 0, frames, FrameRate::Fps25)
+    }
+
+    #[test]
+    fn test_comparable_offset() {
+        assert_eq!(
+            comparable_offset(
+                Some(timecode(10).into()),
+                SignalState::Locked,
+                Some(timecode(8).into()),
+                SignalState::Freewheeling
+            ),
+            Some(2)
+        );
+        assert_eq!(
+            comparable_offset(
+                Some(timecode(8).into()),
+                SignalState::Locked,
+                Some(timecode(10).into()),
+                SignalState::Locked
+            ),
+            Some(-2)
+        );
+
+        // Two parked inputs can be compared, E.g: to check they agree in a pause
+        assert_eq!(
+            comparable_offset(
+                Some(timecode(10).into()),
+                SignalState::Stopped,
+                Some(timecode(8).into()),
+                SignalState::Stopped
+            ),
+            Some(2)
+        );
+        assert_eq!(
+            comparable_offset(
+                Some(timecode(10).into()),
+                SignalState::Lost,
+                Some(timecode(8).into()),
+                SignalState::Lost
+            ),
+            None
+        );
+
+        // Against a running input, one that isn't running only has its last position, which is stale
+        for signal in [SignalState::Stopped, SignalState::Lost] {
+            assert_eq!(
+                comparable_offset(
+                    Some(timecode(10).into()),
+                    SignalState::Locked,
+                    Some(timecode(8).into()),
+                    signal
+                ),
+                None
+            );
+            assert_eq!(
+                comparable_offset(
+                    Some(timecode(10).into()),
+                    signal,
+                    Some(timecode(8).into()),
+                    SignalState::Locked
+                ),
+                None
+            );
+        }
+
+        assert_eq!(
+            comparable_offset(
+                None,
+                SignalState::Locked,
+                Some(timecode(8).into()),
+                SignalState::Locked
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::{Duration, Instant};
+
+use egui::DragValue;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::GlobalState,
+    backend::timecode_decoder::TimecodeDecoder as _,
+    failover::{FailoverSettings, FailoverSide, SwitchBack},
+    input::InputId,
+};
+
+/// How often to show the failover's status when nothing else is redrawing
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Chooses the primary and backup inputs the failover follows, and shows which it is on
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FailoverPanel {
+    pub settings: FailoverSettings,
+}
+
+impl FailoverPanel {
+    pub fn add(&mut self, ui: &mut egui::Ui, global_state: &mut GlobalState) {
+        ui.collapsing("Failover", |ui| {
+            ui.horizontal(|ui| {
+                Self::add_input_picker(ui, global_state, "Primary", &mut self.settings.primary);
+                Self::add_input_picker(ui, global_state, "Backup", &mut self.settings.backup);
+            });
+
+            self.add_settings(ui);
+            self.add_status(ui, global_state);
+        });
+
+        // The failover switches without anything redrawing, so keep the status up to date
+        if self.settings.primary.is_some() && self.settings.backup.is_some() {
+            ui.ctx().request_repaint_after(CHECK_INTERVAL);
+        }
+    }
+
+    /// Stops using an input that is being removed
+    pub fn forget_input(&mut self, id: InputId) {
+        if self.settings.primary == Some(id) {
+            self.settings.primary = None;
+        }
+        if self.settings.backup == Some(id) {
+            self.settings.backup = None;
+        }
+    }
+
+    /// Displays the UI elements to choose when to switch
+    fn add_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Switch after (ms):")
+                .on_hover_text("How long the input being followed can be without lock");
+            ui.add(DragValue::new(&mut self.settings.threshold_millis).range(0..=60_000));
+
+            egui::ComboBox::from_label("Switch back")
+                .selected_text(format!("{}", self.settings.switch_back))
+                .show_ui(ui, |ui| {
+                    for switch_back in SwitchBack::ALL {
+                        ui.selectable_value(
+                            &mut self.settings.switch_back,
+                            switch_back,
+                            format!("{switch_back}"),
+                        );
+                    }
+                });
+
+            if self.settings.switch_back == SwitchBack::Automatic {
+                ui.label("once the primary is back for (ms):");
+                ui.add(
+                    DragValue::new(&mut self.settings.switch_back_after_millis).range(0..=600_000),
+                );
+            }
+        });
+
+        ui.checkbox(&mut self.settings.stopped_is_loss, "Switch if stopped")
+            .on_hover_text(
+                "Count an input that has stopped as lost. Leave this off if the show pauses, \
+                 an input that drops out while playing or sends nothing for 10 seconds is lost either way",
+            );
+    }
+
+    /// Displays which side is being followed, buttons to switch by hand and the recent switches
+    fn add_status(&self, ui: &mut egui::Ui, global_state: &mut GlobalState) {
+        let side = global_state.failover.side();
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Following: {side}"));
+
+            for (label, input) in [
+                (FailoverSide::Primary, self.settings.primary),
+                (FailoverSide::Backup, self.settings.backup),
+            ] {
+                let signal = input
+                    .and_then(|id| global_state.inputs.get(&id))
+                    .map(|input| input.decoder.signal_state());
+
+                if let Some(signal) = signal {
+                    ui.label(format!("{label}: {signal}"));
+                }
+            }
+
+            let other = match side {
+                FailoverSide::Primary => FailoverSide::Backup,
+                FailoverSide::Backup => FailoverSide::Primary,
+            };
+            if ui
+                .button(format!("Switch to {}", other.to_string().to_lowercase()))
+                .clicked()
+            {
+                log::info!("Failover switched to {other} by hand");
+                global_state.failover.switch_to(other, Instant::now());
+            }
+        });
+
+        let now = Instant::now();
+        for (at, side) in global_state.failover.history().rev() {
+            ui.label(format!(
+                "Switched to {} {}s ago",
+                side.to_string().to_lowercase(),
+                now.saturating_duration_since(*at).as_secs()
+            ));
+        }
+    }
+
+    /// Displays a combo box to choose one of the inputs
+    fn add_input_picker(
+        ui: &mut egui::Ui,
+        global_state: &GlobalState,
+        label: &str,
+        selected: &mut Option<InputId>,
+    ) {
+        let selected_name = selected
+            .and_then(|id| global_state.inputs.get(&id))
+            .map_or("Select an input", |input| &input.name);
+
+        egui::ComboBox::from_label(label)
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                for (id, input) in &global_state.inputs {
+                    ui.selectable_value(selected, Some(*id), &input.name);
+                }
+            });
+    }
+}
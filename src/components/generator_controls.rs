@@ -0,0 +1,199 @@
+use std::time::Instant;
+
+use egui::DragValue;
+use egui_notify::Toasts;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    timecode::Timecode,
+    timecode_generator::{LoopRegion, TimecodeGenerator, TransportState},
+};
+
+/// The transport for the internal generator
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorControls {
+    /// Where stopping goes back to as typed, only parsed when it is set
+    start_text: String,
+    locate_text: String,
+    loop_start_text: String,
+    loop_end_text: String,
+}
+
+impl Default for GeneratorControls {
+    fn default() -> Self {
+        Self {
+            start_text: "01:00:00:00".to_owned(),
+            locate_text: "01:00:00:00".to_owned(),
+            loop_start_text: "01:00:00:00".to_owned(),
+            loop_end_text: "01:01:00:00".to_owned(),
+        }
+    }
+}
+
+impl GeneratorControls {
+    pub fn add(
+        &mut self,
+        ui: &mut egui::Ui,
+        generator: &mut TimecodeGenerator,
+        toasts: &mut Toasts,
+    ) {
+        self.add_settings(ui, generator, toasts);
+        Self::add_transport(ui, generator);
+        self.add_locate(ui, generator, toasts);
+        self.add_loop(ui, generator, toasts);
+    }
+
+    /// Displays the UI elements to set the rate, start time and speed
+    fn add_settings(
+        &mut self,
+        ui: &mut egui::Ui,
+        generator: &mut TimecodeGenerator,
+        toasts: &mut Toasts,
+    ) {
+        ui.horizontal(|ui| {
+            let mut rate = generator.rate();
+            egui::ComboBox::from_label("Rate")
+                .selected_text(format!("{rate}"))
+                .show_ui(ui, |ui| {
+                    for option in FrameRate::ALL {
+                        ui.selectable_value(&mut rate, option, format!("{option}"));
+                    }
+                });
+            if rate != generator.rate() {
+                generator.set_rate(rate, Instant::now());
+            }
+
+            ui.label("Start:")
+                .on_hover_text("Where stopping goes back to");
+            ui.add(egui::TextEdit::singleline(&mut self.start_text).desired_width(100.0));
+            if ui.button("Set").clicked() {
+                match Timecode::parse(&self.start_text, generator.rate()) {
+                    Ok(start) => generator.set_start(start, Instant::now()),
+                    Err(e) => {
+                        toasts.error(format!("Can't start at {}: {e}", self.start_text));
+                    }
+                }
+            }
+
+            ui.label("Speed:")
+                .on_hover_text("1 is real time, negative plays backwards");
+            let mut speed = generator.speed();
+            if ui
+                .add(DragValue::new(&mut speed).speed(0.01).range(-4.0..=4.0))
+                .changed()
+            {
+                generator.set_speed(speed, Instant::now());
+            }
+        });
+    }
+
+    /// Displays the play, pause & stop buttons
+    fn add_transport(ui: &mut egui::Ui, generator: &mut TimecodeGenerator) {
+        let state = generator.transport_state();
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    state != TransportState::Playing,
+                    egui::Button::new("▶ Play"),
+                )
+                .clicked()
+            {
+                generator.play(Instant::now());
+            }
+
+            if ui
+                .add_enabled(
+                    state == TransportState::Playing,
+                    egui::Button::new("⏸ Pause"),
+                )
+                .clicked()
+            {
+                generator.pause(Instant::now());
+            }
+
+            if ui
+                .add_enabled(
+                    state != TransportState::Stopped,
+                    egui::Button::new("⏹ Stop"),
+                )
+                .clicked()
+            {
+                generator.stop(Instant::now());
+            }
+
+            ui.label(format!("{state}"));
+        });
+    }
+
+    /// Displays the UI elements to jump the playhead somewhere
+    fn add_locate(
+        &mut self,
+        ui: &mut egui::Ui,
+        generator: &mut TimecodeGenerator,
+        toasts: &mut Toasts,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Locate to:");
+            ui.add(egui::TextEdit::singleline(&mut self.locate_text).desired_width(100.0));
+
+            if ui.button("Locate").clicked() {
+                match Timecode::parse(&self.locate_text, generator.rate()) {
+                    Ok(timecode) => generator.locate(timecode, Instant::now()),
+                    Err(e) => {
+                        toasts.error(format!("Can't locate to {}: {e}", self.locate_text));
+                    }
+                }
+            }
+        });
+    }
+
+    /// Displays the UI elements to set the loop region
+    fn add_loop(
+        &mut self,
+        ui: &mut egui::Ui,
+        generator: &mut TimecodeGenerator,
+        toasts: &mut Toasts,
+    ) {
+        let mut looping = generator.loop_region().is_some();
+
+        ui.horizontal(|ui| {
+            let toggled = ui.checkbox(&mut looping, "Loop from").changed();
+
+            // The region can only be edited while it isn't in use
+            ui.add_enabled_ui(!looping, |ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.loop_start_text).desired_width(100.0));
+                ui.label("to");
+                ui.add(egui::TextEdit::singleline(&mut self.loop_end_text).desired_width(100.0));
+            });
+
+            if !toggled {
+                return;
+            }
+
+            if !looping {
+                generator.set_loop_region(None, Instant::now());
+                return;
+            }
+
+            let rate = generator.rate();
+            let region = Timecode::parse(&self.loop_start_text, rate).and_then(|start| {
+                Timecode::parse(&self.loop_end_text, rate).map(|end| LoopRegion { start, end })
+            });
+
+            match region {
+                Ok(region) if region.end > region.start => {
+                    generator.set_loop_region(Some(region), Instant::now());
+                }
+                Ok(_) => {
+                    toasts.error("The loop has to end after it starts");
+                }
+                Err(e) => {
+                    toasts.error(format!("Can't loop there: {e}"));
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::GlobalState,
+    backend::timecode_decoder::TimecodeDecoder as _,
+    components::{select_timecode_type::SelectTimecodeType, timecode_display::TimecodeDisplay},
+    devices::DeviceLists,
+    input::InputId,
+    timecode_type::TimecodeType,
+};
+
+/// Everything about one input: what it is called, where it reads from and what it is reading
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputPanel {
+    pub id: InputId,
+    name: String,
+    settings: SelectTimecodeType,
+    display: TimecodeDisplay,
+}
+
+impl Default for InputPanel {
+    fn default() -> Self {
+        Self::new(InputId::default(), TimecodeType::default())
+    }
+}
+
+impl InputPanel {
+    pub fn new(id: InputId, timecode_type: TimecodeType) -> Self {
+        let mut settings = SelectTimecodeType::default();
+        settings.current_type = timecode_type;
+
+        Self {
+            id,
+            name: format!("Input {}", id.0 + 1),
+            settings,
+            display: TimecodeDisplay::default(),
+        }
+    }
+
+    /// The input's ID, name and type, for [`GlobalState::sync_inputs`]
+    pub fn config(&self) -> (InputId, &str, TimecodeType) {
+        (self.id, &self.name, self.settings.current_type)
+    }
+
+    /// Displays the input, returns true if it should be removed
+    pub fn add(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        global_state: &mut GlobalState,
+        devices: &mut DeviceLists,
+    ) -> bool {
+        let mut remove = false;
+
+        egui::CollapsingHeader::new(&self.name)
+            .id_salt(("input", self.id))
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.add(egui::TextEdit::singleline(&mut self.name).desired_width(120.0));
+
+                    remove = ui.button("Remove").clicked();
+                });
+
+                let position = global_state.latest_position(self.id);
+                let GlobalState { inputs, toasts, .. } = global_state;
+                let Some(input) = inputs.get_mut(&self.id) else {
+                    return;
+                };
+
+                self.settings.add(ui, &mut input.decoder, devices, toasts);
+                self.display.add(ctx, ui, position, input.decoder.status());
+            });
+
+        remove
+    }
+}
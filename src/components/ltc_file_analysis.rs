@@ -0,0 +1,133 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use anyhow::Result;
+use egui::DragValue;
+use serde::{Deserialize, Serialize};
+
+use crate::{app::GlobalState, backend::ltc_decoder::file_analysis::LtcFileAnalysis};
+
+/// Reads the LTC track out of a recording and shows anything wrong with it
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LtcFileAnalysisPanel {
+    path: String,
+    /// Which channel of the file carries the timecode, counting from 0
+    channel: usize,
+    #[serde(skip)]
+    analysis: Option<LtcFileAnalysis>,
+    /// The analysis running in the background, reading a long recording takes a while
+    #[serde(skip)]
+    pending: Option<Receiver<Result<LtcFileAnalysis>>>,
+}
+
+impl LtcFileAnalysisPanel {
+    pub fn add(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, global_state: &mut GlobalState) {
+        ui.collapsing("Analyse an LTC recording", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("WAV, BWF or FLAC file:");
+                ui.text_edit_singleline(&mut self.path);
+
+                ui.label("Channel:");
+                ui.add(DragValue::new(&mut self.channel));
+
+                let analysing = self.pending.is_some();
+                if ui
+                    .add_enabled(!analysing, egui::Button::new("Analyse"))
+                    .clicked()
+                {
+                    self.start_analysis();
+                }
+
+                if analysing {
+                    ui.spinner();
+                }
+            });
+
+            if let Some(analysis) = &self.analysis {
+                Self::add_results(ui, analysis);
+            }
+        });
+
+        // Pick up the result once the background thread has finished
+        if let Some(rx) = &self.pending {
+            match rx.try_recv() {
+                Ok(Ok(analysis)) => {
+                    self.analysis = Some(analysis);
+                    self.pending = None;
+                }
+                Ok(Err(e)) => {
+                    global_state
+                        .toasts
+                        .error(format!("Failed to analyse recording: {e}"));
+                    self.pending = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => ctx.request_repaint(),
+                Err(mpsc::TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+    }
+
+    fn start_analysis(&mut self) {
+        let path = PathBuf::from(&self.path);
+        let channel = self.channel;
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            tx.send(LtcFileAnalysis::from_file(&path, channel)).ok();
+        });
+
+        self.analysis = None;
+        self.pending = Some(rx);
+    }
+
+    /// Displays the rate, extent and every problem found in the recording
+    fn add_results(ui: &mut egui::Ui, analysis: &LtcFileAnalysis) {
+        let (Some(rate), Some(first), Some(last)) = (
+            analysis.rate,
+            analysis.frames.first(),
+            analysis.frames.last(),
+        ) else {
+            ui.label("No LTC found");
+            return;
+        };
+
+        let seconds = |sample_offset: u64| sample_offset as f64 / f64::from(analysis.sample_rate);
+
+        ui.label(format!(
+            "{} frames at {rate}, from {} to {}",
+            analysis.frames.len(),
+            first.timecode.format(Default::default()),
+            last.timecode.format(Default::default())
+        ));
+        ui.label(format!(
+            "{} discontinuities, {} dropouts",
+            analysis.discontinuities.len(),
+            analysis.dropouts.len()
+        ));
+
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for discontinuity in &analysis.discontinuities {
+                    ui.label(format!(
+                        "{:.3}s: jumped to {} instead of {}",
+                        seconds(discontinuity.sample_offset),
+                        discontinuity.found.format(Default::default()),
+                        discontinuity.expected.format(Default::default())
+                    ));
+                }
+
+                for dropout in &analysis.dropouts {
+                    ui.label(format!(
+                        "{:.3}s: no timecode for {:.3}s",
+                        seconds(dropout.sample_offset),
+                        seconds(dropout.length_samples)
+                    ));
+                }
+            });
+    }
+}
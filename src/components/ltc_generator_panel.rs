@@ -0,0 +1,207 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use anyhow::Result;
+use egui::DragValue;
+use egui_notify::Toasts;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{
+        ltc_frame::LTC_RATES,
+        ltc_generator::{LtcGenerator, LtcGeneratorSettings, render_wav},
+        timecode::Timecode,
+    },
+    devices::DeviceLists,
+};
+
+/// Sample rate of rendered files, the usual one for video work
+const RENDER_SAMPLE_RATE: u32 = 48_000;
+
+/// Sends LTC to an audio output or renders it to a file
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct LtcGeneratorPanel {
+    settings: LtcGeneratorSettings,
+    /// The start time as typed, only parsed when it is used
+    start_text: String,
+    render_path: String,
+    render_seconds: u32,
+    /// The file being rendered in the background
+    #[serde(skip)]
+    pending_render: Option<Receiver<Result<()>>>,
+}
+
+impl Default for LtcGeneratorPanel {
+    fn default() -> Self {
+        Self {
+            settings: LtcGeneratorSettings::default(),
+            start_text: "00:00:00:00".to_owned(),
+            render_path: String::new(),
+            render_seconds: 60,
+            pending_render: None,
+        }
+    }
+}
+
+impl LtcGeneratorPanel {
+    pub fn add(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        generator: &mut LtcGenerator,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+    ) {
+        self.add_settings(ui, generator);
+        self.add_output(ctx, ui, generator, devices, toasts);
+        self.add_render(ui, toasts);
+    }
+
+    /// Picks up the result once the background thread has finished, call this even while the panel is hidden
+    pub fn check_render(&mut self, ctx: &egui::Context, toasts: &mut Toasts) {
+        if let Some(rx) = &self.pending_render {
+            match rx.try_recv() {
+                Ok(Ok(())) => {
+                    toasts.success("Rendered LTC");
+                    self.pending_render = None;
+                }
+                Ok(Err(e)) => {
+                    toasts.error(format!("Failed to render LTC: {e}"));
+                    self.pending_render = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => ctx.request_repaint(),
+                Err(mpsc::TryRecvError::Disconnected) => self.pending_render = None,
+            }
+        }
+    }
+
+    /// Displays the UI elements to choose the rate, start time and shape of the signal
+    fn add_settings(&mut self, ui: &mut egui::Ui, generator: &LtcGenerator) {
+        ui.horizontal(|ui| {
+            let mut rate = self.settings.start.rate();
+            egui::ComboBox::from_label("Rate")
+                .selected_text(format!("{rate}"))
+                .show_ui(ui, |ui| {
+                    for ltc_rate in LTC_RATES {
+                        ui.selectable_value(&mut rate, ltc_rate, format!("{ltc_rate}"));
+                    }
+                });
+
+            ui.label("Start:");
+            ui.text_edit_singleline(&mut self.start_text);
+
+            // Re-read the start time whenever it or the rate might have changed
+            match Timecode::parse(&self.start_text, rate) {
+                Ok(start) => self.settings.start = start,
+                Err(e) => {
+                    ui.colored_label(egui::Color32::RED, format!("{e}"));
+                }
+            }
+
+            ui.label("Level (dBFS):");
+            ui.add(DragValue::new(&mut self.settings.level_dbfs).range(-60.0..=0.0));
+
+            ui.label("Rise time (µs):");
+            ui.add(DragValue::new(&mut self.settings.rise_time_micros).range(1..=500));
+
+            if generator.running() {
+                ui.label("Restart to apply changes");
+            }
+        });
+    }
+
+    /// Displays the UI elements to send LTC to an audio output
+    fn add_output(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        generator: &mut LtcGenerator,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+    ) {
+        let currently_selected_device_name = self
+            .settings
+            .device_name
+            .clone()
+            .unwrap_or_else(|| "Select an output".to_owned());
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Select audio output")
+                .selected_text(currently_selected_device_name)
+                .show_ui(ui, |ui| {
+                    for device_name in devices.audio_outputs() {
+                        ui.selectable_value(
+                            &mut self.settings.device_name,
+                            Some(device_name.clone()),
+                            device_name,
+                        );
+                    }
+                });
+
+            ui.label("Channel:");
+            ui.add(DragValue::new(&mut self.settings.channel));
+
+            let start_button_text: &str = if generator.running() { "Stop" } else { "Start" };
+
+            if ui.button(start_button_text).clicked() {
+                let result = if generator.running() {
+                    generator.stop()
+                } else {
+                    generator.start(&self.settings)
+                };
+
+                if let Err(e) = result {
+                    toasts.error(format!("Failed to {start_button_text} LTC: {e}"));
+                }
+            }
+
+            if let Some(timecode) = generator.current_timecode() {
+                ui.label(format!("{}", timecode.format(Default::default())));
+                ctx.request_repaint(); // Keep the timecode updating even when there is no user input
+            }
+        });
+    }
+
+    /// Displays the UI elements to render LTC to a WAV file
+    fn add_render(&mut self, ui: &mut egui::Ui, toasts: &mut Toasts) {
+        ui.horizontal(|ui| {
+            ui.label("WAV file:");
+            ui.text_edit_singleline(&mut self.render_path);
+
+            ui.label("Length (s):");
+            ui.add(DragValue::new(&mut self.render_seconds).range(1..=86_400));
+
+            let rendering = self.pending_render.is_some();
+            if ui
+                .add_enabled(!rendering, egui::Button::new("Render"))
+                .clicked()
+            {
+                if self.render_path.is_empty() {
+                    toasts.error("Choose a file to render to");
+                    return;
+                }
+
+                let settings = self.settings.clone();
+                let path = PathBuf::from(&self.render_path);
+                let length = Duration::from_secs(u64::from(self.render_seconds));
+                let (tx, rx) = mpsc::channel();
+
+                thread::spawn(move || {
+                    tx.send(render_wav(&settings, RENDER_SAMPLE_RATE, length, &path))
+                        .ok();
+                });
+
+                self.pending_render = Some(rx);
+            }
+
+            if rendering {
+                ui.spinner();
+            }
+        });
+    }
+}
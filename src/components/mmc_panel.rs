@@ -0,0 +1,280 @@
+use egui::{DragValue, Key, Modifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::GlobalState,
+    backend::{
+        cue_list::{Cue, CueList},
+        frame_rate::FrameRate,
+        mmc_sender::{ALL_CALL, MmcCommand, MmcSender},
+        timecode::Timecode,
+    },
+    components::mtc_generator_panel::{MTC_RATES, add_midi_output},
+    devices::DeviceLists,
+    timecode_type::TimecodeSource,
+};
+
+/// The keys that send each transport command when hotkeys are on
+const HOTKEYS: [(Key, MmcCommand); 5] = [
+    (Key::F5, MmcCommand::Play),
+    (Key::F6, MmcCommand::Stop),
+    (Key::F7, MmcCommand::DeferredPlay),
+    (Key::F8, MmcCommand::RecordStrobe),
+    (Key::F9, MmcCommand::RecordExit),
+];
+
+/// Drives another machine's transport with MIDI Machine Control, by hand or from cues on a source
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct MmcPanel {
+    /// Whether the function keys send commands
+    hotkeys: bool,
+    /// The rate locates and cues are typed at
+    rate: FrameRate,
+    /// Where to locate to as typed, only parsed when it is used
+    locate_text: String,
+    /// The cues and the source that plays them, as they were last saved.
+    /// The engine plays them from [`GlobalState`], these are only here to be saved and restored
+    cue_source: Option<TimecodeSource>,
+    cues: CueList<MmcCommand>,
+    /// The cue being added, as typed
+    new_cue_text: String,
+    new_cue_command: MmcCommand,
+}
+
+impl Default for MmcPanel {
+    fn default() -> Self {
+        Self {
+            hotkeys: false,
+            rate: FrameRate::Fps25,
+            locate_text: "00:00:00:00".to_owned(),
+            cue_source: None,
+            cues: CueList::default(),
+            new_cue_text: "00:00:00:00".to_owned(),
+            new_cue_command: MmcCommand::Play,
+        }
+    }
+}
+
+impl MmcPanel {
+    pub fn add(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        global_state: &mut GlobalState,
+        devices: &mut DeviceLists,
+    ) {
+        let connected = global_state
+            .mmc_sender
+            .as_ref()
+            .is_some_and(MmcSender::connected);
+
+        ui.collapsing("MIDI Machine Control", |ui| {
+            if global_state.mmc_sender.is_some() {
+                ui.add_enabled_ui(!connected, |ui| {
+                    Self::add_settings(ui, global_state, devices);
+                });
+                Self::add_connect(ui, global_state);
+            } else {
+                ui.label("MIDI couldn't be initialised, so MMC can't be sent");
+            }
+
+            ui.add_enabled_ui(connected, |ui| {
+                self.add_transport(ui, global_state);
+            });
+
+            ui.separator();
+            self.add_cues(ui, global_state);
+        });
+
+        // Hotkeys work even when the panel is collapsed
+        if connected && self.hotkeys {
+            for (key, command) in HOTKEYS {
+                if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, key)) {
+                    global_state.send_mmc(command);
+                }
+            }
+        }
+    }
+
+    /// Hands the saved cues to the engine to play, call once at startup
+    pub fn restore_cues(&self, global_state: &mut GlobalState) {
+        global_state.cue_source = self.cue_source;
+        global_state.mmc_cues.clone_from(&self.cues);
+    }
+
+    /// Copies the cues back from the engine so they are saved
+    pub fn store_cues(&mut self, global_state: &GlobalState) {
+        self.cue_source = global_state.cue_source;
+        self.cues.clone_from(&global_state.mmc_cues);
+    }
+
+    /// Displays the UI elements to select the MIDI port and device
+    fn add_settings(ui: &mut egui::Ui, global_state: &mut GlobalState, devices: &mut DeviceLists) {
+        let Some(sender) = &mut global_state.mmc_sender else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            add_midi_output(ui, &mut sender.port, devices);
+
+            ui.label("Device ID:")
+                .on_hover_text("127 talks to every machine on the port");
+            ui.add(DragValue::new(&mut sender.device_id).range(0..=ALL_CALL));
+        });
+    }
+
+    /// Displays the button to open and close the port
+    fn add_connect(ui: &mut egui::Ui, global_state: &mut GlobalState) {
+        let GlobalState {
+            mmc_sender: Some(sender),
+            toasts,
+            ..
+        } = global_state
+        else {
+            return;
+        };
+
+        let button_text: &str = if sender.connected() {
+            "Disconnect"
+        } else {
+            "Connect"
+        };
+
+        if ui.button(button_text).clicked() {
+            let result = if sender.connected() {
+                sender.disconnect()
+            } else {
+                sender.connect()
+            };
+
+            if let Err(e) = result {
+                toasts.error(format!("Failed to {button_text} MMC: {e}"));
+            }
+        }
+    }
+
+    /// Displays a button for each transport command and the locate controls
+    fn add_transport(&mut self, ui: &mut egui::Ui, global_state: &mut GlobalState) {
+        ui.horizontal(|ui| {
+            for (key, command) in HOTKEYS {
+                let mut button = ui.button(format!("{command}"));
+                if self.hotkeys {
+                    button = button.on_hover_text(format!("{key:?}"));
+                }
+
+                if button.clicked() {
+                    global_state.send_mmc(command);
+                }
+            }
+
+            ui.checkbox(&mut self.hotkeys, "Hotkeys")
+                .on_hover_text("Use F5 to F9 for the buttons");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Locate to:");
+            ui.text_edit_singleline(&mut self.locate_text);
+
+            egui::ComboBox::from_label("Rate")
+                .selected_text(format!("{}", self.rate))
+                .show_ui(ui, |ui| {
+                    for rate in MTC_RATES {
+                        ui.selectable_value(&mut self.rate, rate, format!("{rate}"));
+                    }
+                })
+                .response
+                .on_hover_text("The rate locates and cues are typed at");
+
+            if ui.button("Locate").clicked() {
+                match Timecode::parse(&self.locate_text, self.rate) {
+                    Ok(timecode) => global_state.send_mmc(MmcCommand::Locate(timecode)),
+                    Err(e) => {
+                        global_state
+                            .toasts
+                            .error(format!("Can't locate to {}: {e}", self.locate_text));
+                    }
+                }
+            }
+        });
+    }
+
+    /// Displays the source the cues follow, the cues and the controls to add another
+    fn add_cues(&mut self, ui: &mut egui::Ui, global_state: &mut GlobalState) {
+        let selected_name = global_state.cue_source.map_or_else(
+            || "None".to_owned(),
+            |source| global_state.source_name(source),
+        );
+
+        let mut cue_source = global_state.cue_source;
+        egui::ComboBox::from_label("Cues follow")
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut cue_source, None, "None");
+                for source in global_state.sources() {
+                    ui.selectable_value(
+                        &mut cue_source,
+                        Some(source),
+                        global_state.source_name(source),
+                    );
+                }
+            });
+        if cue_source != global_state.cue_source {
+            global_state.cue_source = cue_source;
+            global_state.mmc_cues.reset();
+        }
+
+        let mut removed = None;
+        for (index, cue) in global_state.mmc_cues.cues.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{}: {}",
+                    cue.at.format(Default::default()),
+                    cue.action
+                ));
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            global_state.mmc_cues.cues.remove(index);
+        }
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_cue_text);
+
+            egui::ComboBox::from_id_salt("new_mmc_cue_command")
+                .selected_text(format!("{}", self.new_cue_command))
+                .show_ui(ui, |ui| {
+                    for command in MmcCommand::TRANSPORT {
+                        ui.selectable_value(
+                            &mut self.new_cue_command,
+                            command,
+                            format!("{command}"),
+                        );
+                    }
+                });
+
+            if ui.button("Add cue").clicked() {
+                match Timecode::parse(&self.new_cue_text, self.rate) {
+                    Ok(at) => {
+                        global_state.mmc_cues.cues.push(Cue {
+                            at,
+                            action: self.new_cue_command,
+                        });
+                        global_state
+                            .mmc_cues
+                            .cues
+                            .sort_by_key(|cue| cue.at.to_duration());
+                    }
+                    Err(e) => {
+                        global_state
+                            .toasts
+                            .error(format!("Can't add a cue at {}: {e}", self.new_cue_text));
+                    }
+                }
+            }
+        });
+    }
+}
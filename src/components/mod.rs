@@ -1,2 +1,16 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod artnet_sender_panel;
+pub mod comparison_panel;
+pub mod failover_panel;
+pub mod generator_controls;
+pub mod input_panel;
+pub mod ltc_file_analysis;
+pub mod ltc_generator_panel;
+pub mod mmc_panel;
+pub mod mtc_generator_panel;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod osc_sender_panel;
+pub mod output_panel;
+pub mod routing_panel;
 pub mod select_timecode_type;
 pub mod timecode_display;
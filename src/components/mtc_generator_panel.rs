@@ -0,0 +1,149 @@
+use egui_notify::Toasts;
+use serde::{Deserialize, Serialize};
+
+use midir::MidiOutputPort;
+
+use crate::{
+    backend::{frame_rate::FrameRate, mtc_generator::MtcGenerator, timecode::Timecode},
+    devices::DeviceLists,
+};
+
+/// The rates MTC can carry
+pub const MTC_RATES: [FrameRate; 4] = [
+    FrameRate::Fps24,
+    FrameRate::Fps25,
+    FrameRate::Fps29_97Df,
+    FrameRate::Fps30,
+];
+
+/// Sends MTC out of a MIDI port, from whichever source is routed to it or its own clock if nothing is
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct MtcGeneratorPanel {
+    rate: FrameRate,
+    /// Where to locate the internal clock to as typed, only parsed when it is used
+    locate_text: String,
+}
+
+impl Default for MtcGeneratorPanel {
+    fn default() -> Self {
+        Self {
+            rate: FrameRate::Fps25,
+            locate_text: "00:00:00:00".to_owned(),
+        }
+    }
+}
+
+impl MtcGeneratorPanel {
+    /// Displays the generator. `following` is the name of the source routed to it, None if it is its own clock
+    pub fn add(
+        &mut self,
+        ui: &mut egui::Ui,
+        generator: &mut MtcGenerator,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+        following: Option<&str>,
+    ) {
+        ui.add_enabled_ui(!generator.running(), |ui| {
+            self.add_settings(ui, generator, devices);
+        });
+
+        ui.horizontal(|ui| {
+            self.add_start_stop(ui, generator, toasts, following.is_some());
+
+            if let Some(source_name) = following {
+                ui.label(format!("Following {source_name}"));
+            } else {
+                self.add_locate(ui, generator, toasts);
+            }
+        });
+    }
+
+    /// Displays the UI elements to select the MIDI port and rate
+    fn add_settings(
+        &mut self,
+        ui: &mut egui::Ui,
+        generator: &mut MtcGenerator,
+        devices: &mut DeviceLists,
+    ) {
+        ui.horizontal(|ui| {
+            add_midi_output(ui, &mut generator.port, devices);
+
+            egui::ComboBox::from_label("Rate")
+                .selected_text(format!("{}", self.rate))
+                .show_ui(ui, |ui| {
+                    for rate in MTC_RATES {
+                        ui.selectable_value(&mut self.rate, rate, format!("{rate}"));
+                    }
+                })
+                .response
+                .on_hover_text("Sources at other rates are converted to this one");
+        });
+    }
+
+    /// Displays the button to start and stop sending. Without a source `routed` to it the generator is its own clock
+    fn add_start_stop(
+        &self,
+        ui: &mut egui::Ui,
+        generator: &mut MtcGenerator,
+        toasts: &mut Toasts,
+        routed: bool,
+    ) {
+        let start_button_text: &str = if generator.running() { "Stop" } else { "Start" };
+
+        if ui.button(start_button_text).clicked() {
+            let result = if generator.running() {
+                generator.stop()
+            } else {
+                generator.start(self.rate, !routed)
+            };
+
+            if let Err(e) = result {
+                toasts.error(format!("Failed to {start_button_text} MTC: {e}"));
+            }
+        }
+    }
+
+    /// Displays the UI elements to move the generator's own clock around
+    fn add_locate(&mut self, ui: &mut egui::Ui, generator: &mut MtcGenerator, toasts: &mut Toasts) {
+        let rate = generator.rate().unwrap_or(self.rate);
+
+        ui.add_enabled_ui(generator.running(), |ui| {
+            ui.label("Locate to:");
+            ui.text_edit_singleline(&mut self.locate_text);
+
+            let play = ui.button("Play from").clicked();
+            let park = ui.button("Park at").clicked();
+
+            if play || park {
+                match Timecode::parse(&self.locate_text, rate) {
+                    Ok(timecode) => generator.locate(timecode, play),
+                    Err(e) => {
+                        toasts.error(format!("Can't locate to {}: {e}", self.locate_text));
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Displays a combo box to choose the MIDI port something is sent out of
+pub fn add_midi_output(
+    ui: &mut egui::Ui,
+    selected: &mut Option<MidiOutputPort>,
+    devices: &mut DeviceLists,
+) {
+    let ports = devices.midi_outputs();
+    let currently_selected_port_name = selected
+        .as_ref()
+        .and_then(|selected| ports.iter().find(|(port, _)| port == selected))
+        .map_or("Select a port", |(_, name)| name);
+
+    egui::ComboBox::from_label("Select MIDI output")
+        .selected_text(currently_selected_port_name)
+        .show_ui(ui, |ui| {
+            for (port, port_name) in ports {
+                ui.selectable_value(selected, Some(port.clone()), port_name);
+            }
+        });
+}
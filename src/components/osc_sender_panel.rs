@@ -0,0 +1,117 @@
+use std::net::SocketAddr;
+
+use egui_notify::Toasts;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+    frame_rate::FrameRate,
+    osc::OscTimecodeFormat,
+    osc_sender::{OscSenderSettings, OscTimecodeSender},
+};
+
+/// Sends timecode out as OSC, from whichever source is routed to it
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct OscSenderPanel {
+    settings: OscSenderSettings,
+    /// The address and port to send to as typed, only parsed when sending starts
+    target_text: String,
+}
+
+impl Default for OscSenderPanel {
+    fn default() -> Self {
+        let settings = OscSenderSettings::default();
+
+        Self {
+            target_text: settings.target.to_string(),
+            settings,
+        }
+    }
+}
+
+impl OscSenderPanel {
+    pub fn add(&mut self, ui: &mut egui::Ui, sender: &mut OscTimecodeSender, toasts: &mut Toasts) {
+        ui.add_enabled_ui(!sender.running(), |ui| {
+            self.add_settings(ui);
+        });
+
+        ui.horizontal(|ui| {
+            let start_button_text: &str = if sender.running() { "Stop" } else { "Start" };
+
+            if ui.button(start_button_text).clicked() {
+                self.toggle(sender, toasts);
+            }
+        });
+    }
+
+    /// Displays the UI elements to choose the target, address, format and rate
+    fn add_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Send to:")
+                .on_hover_text("The address and port of the software to send to, E.g: 127.0.0.1:53000");
+            ui.add(egui::TextEdit::singleline(&mut self.target_text).desired_width(150.0));
+
+            ui.label("OSC address:").on_hover_text(
+                "Timecode is sent here, and whether it is playing is sent to the same address with /status on the end",
+            );
+            ui.add(egui::TextEdit::singleline(&mut self.settings.address).desired_width(120.0));
+        });
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Format")
+                .selected_text(format!("{}", self.settings.format))
+                .show_ui(ui, |ui| {
+                    for format in OscTimecodeFormat::ALL {
+                        ui.selectable_value(&mut self.settings.format, format, format!("{format}"));
+                    }
+                });
+
+            egui::ComboBox::from_label("Rate")
+                .selected_text(
+                    self.settings
+                        .rate
+                        .map_or_else(|| "Same as source".to_owned(), |rate| format!("{rate}")),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.settings.rate, None, "Same as source");
+
+                    for rate in FrameRate::ALL {
+                        ui.selectable_value(&mut self.settings.rate, Some(rate), format!("{rate}"));
+                    }
+                });
+        });
+    }
+
+    /// Starts or stops sending, toasting if that didn't work
+    fn toggle(&mut self, sender: &mut OscTimecodeSender, toasts: &mut Toasts) {
+        if sender.running() {
+            if let Err(e) = sender.stop() {
+                toasts.error(format!("Failed to stop sending OSC: {e}"));
+            }
+            return;
+        }
+
+        let Ok(target) = self.target_text.trim().parse::<SocketAddr>() else {
+            toasts.error(format!(
+                "{} is not an address and port, E.g: 127.0.0.1:53000",
+                self.target_text
+            ));
+            return;
+        };
+        self.settings.target = target;
+
+        if !self.settings.address.starts_with('/') {
+            toasts.error("OSC addresses have to start with a /");
+            return;
+        }
+
+        match sender.start(self.settings.clone()) {
+            Ok(()) => {
+                toasts.success(format!("Sending OSC timecode to {target}"));
+            }
+            Err(e) => {
+                toasts.error(format!("Failed to start sending OSC: {e}"));
+            }
+        }
+    }
+}
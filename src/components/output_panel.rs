@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::GlobalState,
+    components::{ltc_generator_panel::LtcGeneratorPanel, mtc_generator_panel::MtcGeneratorPanel},
+    devices::DeviceLists,
+    output::{OutputId, OutputSender},
+    timecode_type::TimecodeOutput,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::components::{artnet_sender_panel::ArtnetSenderPanel, osc_sender_panel::OscSenderPanel};
+
+/// Everything about one output: what it is called, what it sends and where to
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputPanel {
+    pub id: OutputId,
+    name: String,
+    output_type: TimecodeOutput,
+    #[cfg(not(target_arch = "wasm32"))]
+    artnet: ArtnetSenderPanel,
+    ltc: LtcGeneratorPanel,
+    mtc: MtcGeneratorPanel,
+    #[cfg(not(target_arch = "wasm32"))]
+    osc: OscSenderPanel,
+}
+
+impl Default for OutputPanel {
+    fn default() -> Self {
+        Self::new(OutputId::default(), TimecodeOutput::default())
+    }
+}
+
+impl OutputPanel {
+    pub fn new(id: OutputId, output_type: TimecodeOutput) -> Self {
+        Self {
+            id,
+            name: format!("Output {}", id.0 + 1),
+            output_type,
+            #[cfg(not(target_arch = "wasm32"))]
+            artnet: ArtnetSenderPanel::default(),
+            ltc: LtcGeneratorPanel::default(),
+            mtc: MtcGeneratorPanel::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            osc: OscSenderPanel::default(),
+        }
+    }
+
+    /// The output's ID, name and type, for [`GlobalState::sync_outputs`]
+    pub fn config(&self) -> (OutputId, &str, TimecodeOutput) {
+        (self.id, &self.name, self.output_type)
+    }
+
+    /// Displays the output, returns true if it should be removed
+    pub fn add(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        global_state: &mut GlobalState,
+        devices: &mut DeviceLists,
+    ) -> bool {
+        let mut remove = false;
+
+        egui::CollapsingHeader::new(&self.name)
+            .id_salt(("output", self.id))
+            .default_open(true)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.add(egui::TextEdit::singleline(&mut self.name).desired_width(120.0));
+
+                    remove = ui.button("Remove").clicked();
+                });
+
+                // Changing the type swaps the sender for a new one, which would stop it without saying
+                ui.add_enabled_ui(!global_state.output_running(self.id), |ui| {
+                    egui::ComboBox::from_id_salt(("output_type", self.id))
+                        .selected_text(format!("{}", self.output_type))
+                        .show_ui(ui, |ui| {
+                            for &output_type in TimecodeOutput::ALL {
+                                ui.selectable_value(
+                                    &mut self.output_type,
+                                    output_type,
+                                    format!("{output_type}"),
+                                );
+                            }
+                        });
+                });
+
+                let following = global_state
+                    .routing
+                    .route(self.id)
+                    .map(|route| global_state.source_name(route.source));
+                let GlobalState {
+                    outputs, toasts, ..
+                } = global_state;
+                let Some(output) = outputs.get_mut(&self.id) else {
+                    return;
+                };
+
+                match &mut output.sender {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    OutputSender::Artnet(sender) => self.artnet.add(ui, sender, devices, toasts),
+                    OutputSender::Ltc(generator) => {
+                        self.ltc.add(ctx, ui, generator, devices, toasts);
+                    }
+                    OutputSender::Mtc(generator) => {
+                        self.mtc
+                            .add(ui, generator, devices, toasts, following.as_deref());
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    OutputSender::Osc(sender) => self.osc.add(ui, sender, toasts),
+                }
+            });
+
+        self.ltc.check_render(ctx, &mut global_state.toasts);
+
+        remove
+    }
+}
@@ -0,0 +1,89 @@
+use egui::DragValue;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::GlobalState,
+    output::OutputId,
+    routing::{RateConversion, RoutingMatrix},
+    timecode_type::TimecodeSource,
+};
+
+/// Chooses which source each output sends, as a grid of outputs against sources
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingPanel {
+    pub routing: RoutingMatrix,
+}
+
+impl RoutingPanel {
+    pub fn add(&mut self, ui: &mut egui::Ui, global_state: &GlobalState) {
+        ui.collapsing("Routing", |ui| {
+            egui::Grid::new("routing_matrix")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("");
+                    for source in global_state.sources() {
+                        ui.label(global_state.source_name(source));
+                    }
+                    ui.label("Offset (frames)");
+                    ui.label("Rate conversion");
+                    ui.end_row();
+
+                    for id in global_state.outputs.keys() {
+                        self.add_output_row(ui, global_state, *id);
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    /// Displays which source goes to an output and how it is converted
+    fn add_output_row(&mut self, ui: &mut egui::Ui, global_state: &GlobalState, output: OutputId) {
+        let output_name = global_state.source_name(TimecodeSource::Output(output));
+        ui.label(&output_name);
+
+        // Some outputs only decide how to run when they start, so the source can't change under them
+        let running = global_state.output_running(output);
+        let current_source = self.routing.route(output).map(|route| route.source);
+
+        for source in global_state.sources() {
+            if source == TimecodeSource::Output(output) {
+                ui.label("");
+                continue;
+            }
+
+            let selected = current_source == Some(source);
+            let response = ui
+                .add_enabled(!running, egui::RadioButton::new(selected, ""))
+                .on_hover_text(format!(
+                    "Send {} to {output_name}",
+                    global_state.source_name(source)
+                ))
+                .on_disabled_hover_text(format!(
+                    "Stop {output_name} to change where it comes from"
+                ));
+
+            if response.clicked() {
+                if selected {
+                    self.routing.disconnect(output);
+                } else {
+                    self.routing.connect(source, output);
+                }
+            }
+        }
+
+        let Some(route) = self.routing.route_mut(output) else {
+            return;
+        };
+
+        ui.add(DragValue::new(&mut route.offset_frames));
+
+        egui::ComboBox::from_id_salt(("rate_conversion", output))
+            .selected_text(format!("{}", route.conversion))
+            .show_ui(ui, |ui| {
+                for conversion in RateConversion::ALL {
+                    ui.selectable_value(&mut route.conversion, conversion, format!("{conversion}"));
+                }
+            });
+    }
+}
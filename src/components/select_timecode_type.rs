@@ -1,140 +1,487 @@
 use core::fmt;
-use std::fmt::format;
+use std::time::Instant;
 
 use egui::DragValue;
+use egui_notify::Toasts;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    app::GlobalState, backend::mtc_decoder::MtcTimecodeDecoder, timecode_type::TimecodeType,
+    backend::{
+        beat_clock_decoder::BeatClockDecoder, frame_rate::FrameRate,
+        ltc_decoder::LtcTimecodeDecoder, midi_input::MidiInputListener,
+        mtc_decoder::MtcTimecodeDecoder, timecode::Timecode,
+    },
+    components::generator_controls::GeneratorControls,
+    devices::DeviceLists,
+    input::Decoder,
+    timecode_type::TimecodeType,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::{artnet_decoder::ArtnetTimecodeDecoder, osc_decoder::OscTimecodeDecoder};
+
 impl fmt::Display for TimecodeType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
+            #[cfg(not(target_arch = "wasm32"))]
             Self::Artnet => write!(f, "Art-Net"),
+            Self::BeatClock => write!(f, "MIDI clock"),
+            Self::Generator => write!(f, "Generator"),
             Self::Ltc => write!(f, "LTC"),
             Self::Mtc => write!(f, "MTC"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc => write!(f, "OSC"),
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SelectTimecodeType {
     pub current_type: TimecodeType,
+    /// The address Art-Net is only taken from as typed, empty to take it from anywhere
+    #[cfg(not(target_arch = "wasm32"))]
+    artnet_source_filter: String,
+    /// Where the song starts as typed, only parsed when it is set
+    beat_clock_start_text: String,
+    generator_controls: GeneratorControls,
 }
 
 impl Default for SelectTimecodeType {
     fn default() -> Self {
         Self {
             current_type: TimecodeType::Mtc,
+            #[cfg(not(target_arch = "wasm32"))]
+            artnet_source_filter: String::new(),
+            beat_clock_start_text: "01:00:00:00".to_owned(),
+            generator_controls: GeneratorControls::default(),
         }
     }
 }
 
 impl SelectTimecodeType {
-    pub fn add(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, global_state: &mut GlobalState) {
+    /// Displays the type picker and the settings for `decoder`. Changing the type only changes `current_type`,
+    /// the decoder is swapped for one of the new type after this
+    pub fn add(
+        &mut self,
+        ui: &mut egui::Ui,
+        decoder: &mut Decoder,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+    ) {
         egui::ComboBox::from_label("Timecode type")
             .selected_text(format!("{}", self.current_type))
             .show_ui(ui, |ui| {
-                ui.selectable_value(
-                    &mut self.current_type,
-                    TimecodeType::Artnet,
-                    format!("{}", TimecodeType::Artnet),
-                );
-                ui.selectable_value(
-                    &mut self.current_type,
-                    TimecodeType::Ltc,
-                    format!("{}", TimecodeType::Ltc),
+                for &timecode_type in TimecodeType::ALL {
+                    ui.selectable_value(
+                        &mut self.current_type,
+                        timecode_type,
+                        format!("{timecode_type}"),
+                    );
+                }
+            });
+
+        match decoder {
+            Decoder::Mtc(decoder) => Self::add_mtc(ui, decoder, devices, toasts),
+            #[cfg(not(target_arch = "wasm32"))]
+            Decoder::Osc(decoder) => Self::add_osc(ui, decoder, toasts),
+            Decoder::Ltc(decoder) => Self::add_ltc(ui, decoder, devices, toasts),
+            #[cfg(not(target_arch = "wasm32"))]
+            Decoder::Artnet(decoder) => self.add_artnet(ui, decoder, devices, toasts),
+            Decoder::BeatClock(decoder) => self.add_beat_clock(ui, decoder, devices, toasts),
+            Decoder::Generator(generator) => self.generator_controls.add(ui, generator, toasts),
+        }
+    }
+
+    /// Displays the UI elements to select the network interface & source Art-Net is read from
+    #[cfg(not(target_arch = "wasm32"))]
+    fn add_artnet(
+        &mut self,
+        ui: &mut egui::Ui,
+        decoder: &mut ArtnetTimecodeDecoder,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+    ) {
+        let currently_selected_interface_name = decoder.interface.as_ref().map_or_else(
+            || "All interfaces".to_owned(),
+            |interface| format!("{} ({})", interface.name, interface.address),
+        );
+
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!decoder.connected(), |ui| {
+                egui::ComboBox::from_label("Listen on")
+                    .selected_text(currently_selected_interface_name)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut decoder.interface, None, "All interfaces");
+
+                        // Only looked up while the list is open
+                        for interface in devices.interfaces() {
+                            let text = format!("{} ({})", interface.name, interface.address);
+                            ui.selectable_value(
+                                &mut decoder.interface,
+                                Some(interface.clone()),
+                                text,
+                            );
+                        }
+                    });
+
+                ui.label("Only from:").on_hover_text(
+                    "Ignore Art-Net from every other address, leave empty to take it from anywhere",
                 );
-                ui.selectable_value(
-                    &mut self.current_type,
-                    TimecodeType::Mtc,
-                    format!("{}", TimecodeType::Mtc),
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.artnet_source_filter)
+                        .hint_text("Any address")
+                        .desired_width(120.0),
                 );
             });
 
-        match self.current_type {
-            TimecodeType::Mtc => self.add_mtc(ctx, ui, global_state),
-            _ => println!("Art-Net and LTC are not implemented yet"),
-        };
+            ui.label("Freewheel frames:").on_hover_text(
+                "How long to keep counting if the timecode drops out, 0 to stop straight away",
+            );
+            ui.add(DragValue::new(&mut decoder.flywheel.freewheel_frames));
+
+            let connect_button_text: &str = if decoder.connected() {
+                "Disconnect"
+            } else {
+                "Connect"
+            };
+
+            if ui.button(connect_button_text).clicked() {
+                if !decoder.connected() {
+                    let source_filter = self.artnet_source_filter.trim();
+
+                    decoder.source_filter = if source_filter.is_empty() {
+                        None
+                    } else if let Ok(address) = source_filter.parse() {
+                        Some(address)
+                    } else {
+                        toasts.error(format!("{source_filter} is not an IP address"));
+                        return;
+                    };
+
+                    match decoder.connect() {
+                        Ok(_) => {
+                            toasts.success("Connected");
+                        }
+                        Err(e) => {
+                            toasts.error(format!("Failed to connect to Art-Net timecode: {e}"));
+                        }
+                    }
+                } else {
+                    match decoder.disconnect() {
+                        Ok(_) => {
+                            toasts.info("Disconnected");
+                        }
+                        Err(e) => {
+                            toasts.error(format!("Failed to disconnect: {e}"));
+                        }
+                    }
+                }
+            }
+        });
+
+        if let Some(stream_id) = decoder.stream_id() {
+            ui.label(format!("Stream ID: {stream_id}"));
+        }
     }
 
-    /// Displays the UI elements to select the MIDI device & FPS
-    fn add_mtc(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui, global_state: &mut GlobalState) {
-        // Gets the name of the currently selected port. Will display appropriately if there are no available ports,
-        // and will throw a toast if initialising MIDI support failed
-        let currently_selected_port_name = match &global_state.mtc_decoder.port {
-            Some(port) => match global_state.mtc_decoder.port_name(port) {
-                Ok(p) => p,
-                Err(e) => {
-                    global_state
-                        .toasts
-                        .error(format!("Failed to initialise MIDI to get port names: {e}"));
-                    return;
+    /// Displays the UI elements to choose the port and address OSC timecode is read from
+    #[cfg(not(target_arch = "wasm32"))]
+    fn add_osc(ui: &mut egui::Ui, decoder: &mut OscTimecodeDecoder, toasts: &mut Toasts) {
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!decoder.connected(), |ui| {
+                ui.label("Port:");
+                ui.add(DragValue::new(&mut decoder.port));
+
+                ui.label("Address:").on_hover_text(
+                    "The address timecode is sent to, ? and * match any one or any number of characters",
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut decoder.address_pattern).desired_width(120.0),
+                );
+
+                egui::ComboBox::from_label("Rate")
+                    .selected_text(format!("{}", decoder.rate))
+                    .show_ui(ui, |ui| {
+                        for rate in FrameRate::ALL {
+                            ui.selectable_value(&mut decoder.rate, rate, format!("{rate}"));
+                        }
+                    })
+                    .response
+                    .on_hover_text("OSC doesn't say what rate it is sending");
+            });
+
+            ui.label("Freewheel frames:").on_hover_text(
+                "How long to keep counting if the timecode drops out, 0 to stop straight away",
+            );
+            ui.add(DragValue::new(&mut decoder.flywheel.freewheel_frames));
+
+            let connect_button_text: &str = if decoder.connected() {
+                "Disconnect"
+            } else {
+                "Connect"
+            };
+
+            if ui.button(connect_button_text).clicked() {
+                if !decoder.connected() {
+                    match decoder.connect() {
+                        Ok(_) => {
+                            toasts.success("Connected");
+                        }
+                        Err(e) => {
+                            toasts.error(format!("Failed to connect to OSC timecode: {e}"));
+                        }
+                    }
+                } else {
+                    match decoder.disconnect() {
+                        Ok(_) => {
+                            toasts.info("Disconnected");
+                        }
+                        Err(e) => {
+                            toasts.error(format!("Failed to disconnect: {e}"));
+                        }
+                    }
                 }
-            },
-            None => "Select a port".to_string(),
-        };
+            }
+        });
+    }
+
+    /// Displays the UI elements to select the audio input & channel LTC is read from
+    fn add_ltc(
+        ui: &mut egui::Ui,
+        decoder: &mut LtcTimecodeDecoder,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+    ) {
+        let currently_selected_device_name = decoder
+            .device_name
+            .clone()
+            .unwrap_or_else(|| "Select an input".to_owned());
 
         ui.horizontal(|ui| {
-            egui::ComboBox::from_label("Select MIDI Device")
-                .selected_text(currently_selected_port_name)
+            egui::ComboBox::from_label("Select audio input")
+                .selected_text(currently_selected_device_name)
                 .show_ui(ui, |ui| {
-                    for current_port in global_state.mtc_decoder.get_ports() {
-                        let current_port_name =
-                            match global_state.mtc_decoder.port_name(&current_port) {
-                                Ok(p) => p,
-                                Err(e) => {
-                                    global_state.toasts.error(format!(
-                                        "Failed to initialise MIDI to get port names: {e}"
-                                    ));
-                                    return;
-                                }
-                            };
-
+                    for device_name in devices.audio_inputs() {
                         ui.selectable_value(
-                            &mut global_state.mtc_decoder.port,
-                            Some(current_port),
-                            current_port_name,
+                            &mut decoder.device_name,
+                            Some(device_name.clone()),
+                            device_name,
                         );
                     }
                 });
 
-            ui.label("FPS:");
-            ui.add(DragValue::new(&mut global_state.mtc_decoder.fps));
+            ui.label("Channel:");
+            ui.add(DragValue::new(&mut decoder.channel));
+
+            ui.label("Freewheel frames:").on_hover_text(
+                "How long to keep counting if the timecode drops out, 0 to stop straight away",
+            );
+            ui.add(DragValue::new(&mut decoder.flywheel.freewheel_frames));
 
-            let connect_button_text: &str = match global_state.mtc_decoder.connected() {
-                true => "Disconnect",
-                false => "Connect",
+            let connect_button_text: &str = if decoder.connected() {
+                "Disconnect"
+            } else {
+                "Connect"
+            };
+
+            if ui.button(connect_button_text).clicked() {
+                if !decoder.connected() {
+                    match decoder.connect() {
+                        Ok(_) => {
+                            toasts.success("Connected");
+                        }
+                        Err(e) => {
+                            toasts.error(format!("Failed to connect to LTC timecode: {e}"));
+                        }
+                    }
+                } else {
+                    match decoder.disconnect() {
+                        Ok(_) => {
+                            toasts.info("Disconnected");
+                        }
+                        Err(e) => {
+                            toasts.error(format!("Failed to disconnect: {e}"));
+                        }
+                    }
+                }
+            }
+        });
+
+        if let Some(user_bits) = decoder.user_bits() {
+            ui.label(format!("User bits: {user_bits:08X}"));
+        }
+    }
+
+    /// Displays the UI elements to select the MIDI device & FPS
+    fn add_mtc(
+        ui: &mut egui::Ui,
+        decoder: &mut MtcTimecodeDecoder,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+    ) {
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!decoder.connected(), |ui| {
+                Self::add_midi_port(ui, &mut decoder.input, devices);
+            });
+
+            ui.label("Freewheel frames:").on_hover_text(
+                "How long to keep counting if the timecode drops out, 0 to stop straight away",
+            );
+            ui.add(DragValue::new(&mut decoder.flywheel.freewheel_frames));
+
+            let connect_button_text: &str = if decoder.connected() {
+                "Disconnect"
+            } else {
+                "Connect"
             };
 
             if ui.button(connect_button_text).clicked() {
                 // Connect to the aforementioned MIDI ports, or throw a toast error
-                if !global_state.mtc_decoder.connected() {
-                    match global_state.mtc_decoder.connect() {
+                if !decoder.connected() {
+                    match decoder.connect() {
                         Ok(_) => {
-                            global_state.toasts.success(format!("Connected"));
+                            toasts.success("Connected");
                         }
                         Err(e) => {
-                            global_state
-                                .toasts
-                                .error(format!("Failed to connect to MTC timecode: {e}"));
+                            toasts.error(format!("Failed to connect to MTC timecode: {e}"));
                         }
                     }
                 // Disconnect from the aforementioned MIDI ports, error *should* be unreachable
                 } else {
-                    match global_state.mtc_decoder.disconnect() {
+                    match decoder.disconnect() {
                         Ok(_) => {
-                            global_state.toasts.info("Disconnected");
+                            toasts.info("Disconnected");
                         }
                         Err(e) => {
-                            global_state
-                                .toasts
-                                .error(format!("Failed to disconnect: {e}"));
+                            toasts.error(format!("Failed to disconnect: {e}"));
                         }
                     }
                 }
             }
         });
+
+        if decoder.connected() {
+            Self::add_mtc_transport(ui, decoder);
+        }
+    }
+
+    /// Displays the UI elements to select the MIDI port beat clock is read from and where the song starts
+    fn add_beat_clock(
+        &mut self,
+        ui: &mut egui::Ui,
+        decoder: &mut BeatClockDecoder,
+        devices: &mut DeviceLists,
+        toasts: &mut Toasts,
+    ) {
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!decoder.connected(), |ui| {
+                Self::add_midi_port(ui, &mut decoder.input, devices);
+            });
+
+            let connect_button_text: &str = if decoder.connected() {
+                "Disconnect"
+            } else {
+                "Connect"
+            };
+
+            if ui.button(connect_button_text).clicked() {
+                let result = if decoder.connected() {
+                    decoder.disconnect()
+                } else {
+                    decoder.connect()
+                };
+
+                if let Err(e) = result {
+                    toasts.error(format!("Failed to {connect_button_text} MIDI clock: {e}"));
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut rate = decoder.start.rate();
+            egui::ComboBox::from_label("Rate")
+                .selected_text(format!("{rate}"))
+                .show_ui(ui, |ui| {
+                    for option in FrameRate::ALL {
+                        ui.selectable_value(&mut rate, option, format!("{option}"));
+                    }
+                });
+            if rate != decoder.start.rate() {
+                decoder.set_start(decoder.start.relabel(rate));
+            }
+
+            ui.label("Song starts at:")
+                .on_hover_text("The timecode at the very start of the song");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.beat_clock_start_text).desired_width(100.0),
+            );
+            if ui.button("Set").clicked() {
+                match Timecode::parse(&self.beat_clock_start_text, rate) {
+                    Ok(start) => decoder.set_start(start),
+                    Err(e) => {
+                        toasts.error(format!(
+                            "Can't start the song at {}: {e}",
+                            self.beat_clock_start_text
+                        ));
+                    }
+                }
+            }
+
+            ui.label("Tempo until measured:")
+                .on_hover_text("Used to place a song position sent before the clock has started");
+            ui.add(
+                DragValue::new(&mut decoder.fallback_bpm)
+                    .range(20.0..=300.0)
+                    .suffix(" BPM"),
+            );
+        });
+
+        if decoder.connected() {
+            let beat_clock = decoder.beat_clock();
+            match beat_clock.bpm() {
+                Some(bpm) => ui.label(format!(
+                    "Clock: {bpm:.1} BPM, beat {:.2}",
+                    beat_clock.beats() + 1.0
+                )),
+                None => ui.label("Clock: none"),
+            };
+        }
+    }
+
+    /// Displays a combo box to choose the MIDI port an input listens on
+    fn add_midi_port(ui: &mut egui::Ui, input: &mut MidiInputListener, devices: &mut DeviceLists) {
+        let ports = devices.midi_inputs();
+        let currently_selected_port_name = input
+            .port
+            .as_ref()
+            .and_then(|selected| ports.iter().find(|(port, _)| port == selected))
+            .map_or("Select a port", |(_, name)| name);
+
+        egui::ComboBox::from_label("Select MIDI Device")
+            .selected_text(currently_selected_port_name)
+            .show_ui(ui, |ui| {
+                for (port, port_name) in ports {
+                    ui.selectable_value(&mut input.port, Some(port.clone()), port_name);
+                }
+            });
+    }
+
+    /// Displays the beat clock and the transport commands that have come in alongside the timecode
+    fn add_mtc_transport(ui: &mut egui::Ui, decoder: &MtcTimecodeDecoder) {
+        ui.collapsing("Transport", |ui| {
+            match decoder.clock_tempo() {
+                Some(tempo) => ui.label(format!("Clock: {tempo:.1} BPM")),
+                None => ui.label("Clock: none"),
+            };
+
+            let now = Instant::now();
+            for (at, event) in decoder.transport_history().rev() {
+                ui.label(format!(
+                    "{event} {}s ago",
+                    now.saturating_duration_since(*at).as_secs()
+                ));
+            }
+        });
     }
 }
@@ -2,23 +2,135 @@ use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
-use crate::app::GlobalState;
+use crate::backend::flywheel::SignalState;
 use crate::backend::timecode::Timecode;
-use crate::backend::timecode_decoder::TimecodeDecoder;
+use crate::backend::timecode_decoder::{DecoderStatus, PlaybackDirection, TimecodePosition};
+use crate::backend::timecode_formatter::{SubframeStyle, TimecodeFormat, TimecodeStyle};
+
+/// How often to look at a stopped source, it only needs to be often enough to see it become lost
+const STOPPED_REPAINT_INTERVAL: Duration = Duration::from_millis(500);
 
 #[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TimecodeDisplay {
     current_timecode: Timecode,
+    /// How far through the current frame the source is, not worth keeping between runs
+    #[serde(skip)]
+    current_subframe: f64,
+    format: TimecodeFormat,
 }
 
 impl TimecodeDisplay {
-    pub fn add(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, global_state: &mut GlobalState) {
-        ui.label(format!("{}", self.current_timecode));
+    pub fn add(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        position: Option<TimecodePosition>,
+        status: DecoderStatus,
+    ) {
+        self.add_format_options(ui);
+
+        ui.label(format!(
+            "{}",
+            self.current_timecode
+                .format(self.format)
+                .with_subframe(self.current_subframe)
+        ));
+
+        // Update the cached timecode value if the source has given one
+        if let Some(position) = position {
+            self.current_timecode = position.timecode;
+            self.current_subframe = position.subframe;
+        }
+
+        Self::add_status(ui, &status);
 
-        // Update the cached timecode value if there is an update
-        if let Some(tc) = global_state.mtc_decoder.get_current_timecode() {
-            self.current_timecode = tc;
-            ctx.request_repaint(); // Keep the timecode updating even when there is no user input
+        // Keep checking while the source is live so a dropout is noticed without any user input
+        match status.signal {
+            SignalState::Locked | SignalState::Freewheeling => ctx.request_repaint(),
+            // A stopped source turns into a lost one after a while, which should show without any user input too
+            SignalState::Stopped => ctx.request_repaint_after(STOPPED_REPAINT_INTERVAL),
+            SignalState::Lost => {}
         }
     }
+
+    /// Displays how healthy the timecode source is
+    fn add_status(ui: &mut egui::Ui, status: &DecoderStatus) {
+        let colour = match status.signal {
+            SignalState::Locked => egui::Color32::GREEN,
+            SignalState::Freewheeling => egui::Color32::YELLOW,
+            SignalState::Stopped => egui::Color32::GRAY,
+            SignalState::Lost => egui::Color32::RED,
+        };
+
+        ui.horizontal(|ui| {
+            ui.colored_label(colour, format!("{}", status.signal));
+            ui.label(format!("{}", status.connection));
+
+            if let Some(rate) = status.rate {
+                ui.label(format!("{rate}"));
+            }
+
+            if let Some(measured_fps) = status.measured_fps {
+                ui.label(format!("(measured {measured_fps:.2}fps)"));
+            }
+
+            match status.direction {
+                Some(PlaybackDirection::Forward) => ui.label("Forward"),
+                Some(PlaybackDirection::Reverse) => ui.label("Reverse"),
+                None => ui.label("Not playing"),
+            };
+
+            if let Some(last_received) = status.last_received {
+                ui.label(format!(
+                    "Last received {}ms ago",
+                    last_received.elapsed().as_millis()
+                ));
+            }
+
+            ui.label(format!(
+                "Dropouts: {}, malformed: {}",
+                status.errors.dropouts, status.errors.malformed
+            ));
+        });
+    }
+
+    /// Displays the UI elements to choose how the timecode is written out
+    fn add_format_options(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Display as")
+                .selected_text(format!("{}", self.format.style))
+                .show_ui(ui, |ui| {
+                    for style in [
+                        TimecodeStyle::Smpte,
+                        TimecodeStyle::TotalFrames,
+                        TimecodeStyle::Seconds,
+                    ] {
+                        ui.selectable_value(&mut self.format.style, style, format!("{style}"));
+                    }
+                });
+
+            ui.checkbox(&mut self.format.show_rate, "Show rate");
+
+            if self.format.style == TimecodeStyle::Smpte {
+                ui.checkbox(&mut self.format.drop_frame_separator, "; for drop-frame");
+
+                egui::ComboBox::from_label("Subframes")
+                    .selected_text(format!("{}", self.format.subframes))
+                    .show_ui(ui, |ui| {
+                        for subframes in [
+                            SubframeStyle::Hidden,
+                            SubframeStyle::QuarterFrames,
+                            SubframeStyle::Hundredths,
+                        ] {
+                            ui.selectable_value(
+                                &mut self.format.subframes,
+                                subframes,
+                                format!("{subframes}"),
+                            );
+                        }
+                    });
+            }
+        });
+    }
 }
@@ -0,0 +1,175 @@
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait as _, HostTrait as _};
+use midir::{MidiInput, MidiInputPort, MidiOutput, MidiOutputPort};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::network_interface::{NetworkInterface, get_interfaces};
+
+/// How long a list is kept before it is looked up again, so devices plugged in while a list is open show up
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One list of things to pick from, and whether anything has looked at it since it was last refreshed
+struct DeviceList<T> {
+    items: Vec<T>,
+    listed: Option<Instant>,
+    wanted: bool,
+}
+
+impl<T> Default for DeviceList<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            listed: None,
+            wanted: false,
+        }
+    }
+}
+
+impl<T> DeviceList<T> {
+    fn get(&mut self) -> &[T] {
+        self.wanted = true;
+        &self.items
+    }
+
+    /// Looks the list up again if something wanted it and it is out of date, returns true if it did
+    fn refresh(&mut self, now: Instant, list: impl FnOnce() -> Vec<T>) -> bool {
+        let stale = self
+            .listed
+            .is_none_or(|listed| now.saturating_duration_since(listed) >= REFRESH_INTERVAL);
+        if !(self.wanted && stale) {
+            return false;
+        }
+
+        self.items = list();
+        self.listed = Some(now);
+        self.wanted = false;
+
+        true
+    }
+}
+
+/// The audio devices, MIDI ports and network interfaces the panels offer to pick from.
+///
+/// Asking the system for these can take long enough to hold up the timecode, so the panels read them from here
+/// while the engine is locked and [`Self::refresh`] looks them up once it has been let go. A list is only looked up
+/// after a panel has asked for it, so the first time it is opened it fills in on the next redraw
+#[derive(Default)]
+pub struct DeviceLists {
+    audio_inputs: DeviceList<String>,
+    audio_outputs: DeviceList<String>,
+    midi_inputs: DeviceList<(MidiInputPort, String)>,
+    midi_outputs: DeviceList<(MidiOutputPort, String)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    interfaces: DeviceList<NetworkInterface>,
+    /// Only used to look up ports, made the first time they are asked for
+    midi_in: Option<MidiInput>,
+    midi_out: Option<MidiOutput>,
+}
+
+impl DeviceLists {
+    /// The names of the audio inputs on the system
+    pub fn audio_inputs(&mut self) -> &[String] {
+        self.audio_inputs.get()
+    }
+
+    /// The names of the audio outputs on the system
+    pub fn audio_outputs(&mut self) -> &[String] {
+        self.audio_outputs.get()
+    }
+
+    /// The MIDI input ports on the system and their names
+    pub fn midi_inputs(&mut self) -> &[(MidiInputPort, String)] {
+        self.midi_inputs.get()
+    }
+
+    /// The MIDI output ports on the system and their names
+    pub fn midi_outputs(&mut self) -> &[(MidiOutputPort, String)] {
+        self.midi_outputs.get()
+    }
+
+    /// The IPv4 network interfaces on the system
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn interfaces(&mut self) -> &[NetworkInterface] {
+        self.interfaces.get()
+    }
+
+    /// Looks up the lists that have been asked for since they were last looked up, call this without the engine
+    /// locked. Returns true if any of them were, so they can be redrawn
+    pub fn refresh(&mut self) -> bool {
+        let now = Instant::now();
+        let host = cpal::default_host();
+
+        let mut refreshed = self.audio_inputs.refresh(now, || {
+            host.input_devices()
+                .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+                .unwrap_or_default()
+        });
+        refreshed |= self.audio_outputs.refresh(now, || {
+            host.output_devices()
+                .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+                .unwrap_or_default()
+        });
+        refreshed |= self.midi_inputs.refresh(now, || {
+            let Some(midi_in) = lazy_client(&mut self.midi_in, MidiInput::new) else {
+                return Vec::new();
+            };
+            // Ports that have gone away between listing and naming them can't be picked anyway
+            midi_in
+                .ports()
+                .into_iter()
+                .filter_map(|port| Some((port.clone(), midi_in.port_name(&port).ok()?)))
+                .collect()
+        });
+        refreshed |= self.midi_outputs.refresh(now, || {
+            let Some(midi_out) = lazy_client(&mut self.midi_out, MidiOutput::new) else {
+                return Vec::new();
+            };
+            midi_out
+                .ports()
+                .into_iter()
+                .filter_map(|port| Some((port.clone(), midi_out.port_name(&port).ok()?)))
+                .collect()
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            refreshed |= self.interfaces.refresh(now, get_interfaces);
+        }
+
+        refreshed
+    }
+}
+
+/// Makes the MIDI client used to look up ports if it hasn't been yet, None if the system has no MIDI
+fn lazy_client<T, E>(client: &mut Option<T>, new: impl FnOnce(&str) -> Result<T, E>) -> Option<&T> {
+    if client.is_none() {
+        *client = new("technus timecode check ports").ok();
+    }
+
+    client.as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_only_looks_up_wanted_lists() {
+        let mut list = DeviceList::default();
+        let now = Instant::now();
+
+        assert!(!list.refresh(now, || vec![1]), "Nothing has asked for it");
+        assert!(list.get().is_empty());
+
+        assert!(list.refresh(now, || vec![1]));
+        assert_eq!(list.get(), [1]);
+
+        assert!(
+            !list.refresh(now + REFRESH_INTERVAL / 2, || vec![2]),
+            "Looked up too recently"
+        );
+        assert!(list.refresh(now + REFRESH_INTERVAL, || vec![2]));
+        assert_eq!(list.get(), [2]);
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::app::GlobalState;
+
+/// How often the inputs are read and the outputs fed, well under a frame at any rate
+const TICK_INTERVAL: Duration = Duration::from_millis(2);
+
+/// Reads the inputs, runs the failover and feeds the outputs on a thread of its own,
+/// so timecode keeps flowing while the window is hidden or isn't redrawing.
+///
+/// Where there are no threads, E.g: in a browser, the UI has to tick the state itself instead, see [`Self::ticking`]
+pub struct Engine {
+    state: Arc<Mutex<GlobalState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Engine {
+    /// Starts running `state` straight away
+    pub fn new(state: GlobalState) -> Self {
+        let state = Arc::new(Mutex::new(state));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = Arc::clone(&state);
+            let stop = Arc::clone(&stop);
+            thread::Builder::new()
+                .name("Timecode engine".to_owned())
+                .spawn(move || run(&state, &stop))
+                .map_err(|e| {
+                    log::warn!(
+                        "Failed to start the timecode engine, it will only run while redrawing: {e}"
+                    );
+                })
+                .ok()
+        };
+
+        Self {
+            state,
+            stop,
+            thread,
+        }
+    }
+
+    /// The state the engine is running, for the UI to [`lock`]
+    pub fn state(&self) -> Arc<Mutex<GlobalState>> {
+        Arc::clone(&self.state)
+    }
+
+    /// Whether the engine's thread is ticking the state, if not the UI has to
+    pub fn ticking(&self) -> bool {
+        self.thread.is_some()
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new(GlobalState::default())
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            log::error!("The timecode engine panicked");
+        }
+    }
+}
+
+/// Locks the state, the engine waits while it is held so don't hold on to it for longer than a redraw.
+/// A panel that panicked half way through changing the state shouldn't stop timecode for good
+pub fn lock(state: &Mutex<GlobalState>) -> MutexGuard<'_, GlobalState> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Ticks the state until the engine is dropped, runs on its own thread
+fn run(state: &Mutex<GlobalState>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        lock(state).tick();
+        thread::sleep(TICK_INTERVAL);
+    }
+}
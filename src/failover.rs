@@ -0,0 +1,565 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{flywheel::SignalState, timecode_decoder::DecoderStatus},
+    input::InputId,
+};
+
+/// How many switches to remember for the UI
+const HISTORY_LENGTH: usize = 10;
+
+/// What to do once the primary comes back after a failover
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum SwitchBack {
+    /// Stay on the backup until someone switches back by hand
+    #[default]
+    Manual,
+    /// Go back to the primary once it has been locked for a while
+    Automatic,
+}
+
+impl SwitchBack {
+    pub const ALL: [Self; 2] = [Self::Manual, Self::Automatic];
+}
+
+impl fmt::Display for SwitchBack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Manual => write!(f, "By hand"),
+            Self::Automatic => write!(f, "Automatically"),
+        }
+    }
+}
+
+/// Which of the two inputs is being followed
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum FailoverSide {
+    #[default]
+    Primary,
+    Backup,
+}
+
+impl fmt::Display for FailoverSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Primary => write!(f, "Primary"),
+            Self::Backup => write!(f, "Backup"),
+        }
+    }
+}
+
+/// Which inputs to follow and when to switch between them
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FailoverSettings {
+    pub primary: Option<InputId>,
+    pub backup: Option<InputId>,
+    /// How long the input being followed has to be without lock before switching away from it
+    pub threshold_millis: u32,
+    pub switch_back: SwitchBack,
+    /// How long the primary has to be locked again before switching back to it automatically
+    pub switch_back_after_millis: u32,
+    /// Whether an input that has stopped counts as having lost lock. Off by default so pausing the show doesn't
+    /// fail over to a backup that is still running, though an input quiet for long enough is lost either way.
+    /// One that went quiet while it was playing has dropped out rather than stopped, so is a loss whatever this is
+    pub stopped_is_loss: bool,
+}
+
+impl Default for FailoverSettings {
+    fn default() -> Self {
+        Self {
+            primary: None,
+            backup: None,
+            threshold_millis: 500,
+            switch_back: SwitchBack::default(),
+            switch_back_after_millis: 5_000,
+            stopped_is_loss: false,
+        }
+    }
+}
+
+impl FailoverSettings {
+    /// The input on one side
+    pub fn input(&self, side: FailoverSide) -> Option<InputId> {
+        match side {
+            FailoverSide::Primary => self.primary,
+            FailoverSide::Backup => self.backup,
+        }
+    }
+
+    /// Whether an input doing this is good enough to follow
+    fn is_locked(&self, signal: InputSignal) -> bool {
+        match signal.state {
+            SignalState::Locked => true,
+            SignalState::Stopped => !signal.playing && !self.stopped_is_loss,
+            SignalState::Freewheeling | SignalState::Lost => false,
+        }
+    }
+}
+
+/// How an input is doing, as far as the failover is concerned
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct InputSignal {
+    pub state: SignalState,
+    /// Whether it was playing when it last sent anything, E.g: a pulled cable stops an input that was playing
+    pub playing: bool,
+}
+
+impl From<SignalState> for InputSignal {
+    fn from(state: SignalState) -> Self {
+        Self {
+            state,
+            playing: false,
+        }
+    }
+}
+
+impl From<DecoderStatus> for InputSignal {
+    fn from(status: DecoderStatus) -> Self {
+        Self {
+            state: status.signal,
+            playing: status.direction.is_some(),
+        }
+    }
+}
+
+impl fmt::Display for InputSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.state)
+    }
+}
+
+/// Follows a primary input, switching to a backup if the primary loses lock, like a show control system's chase
+#[derive(Debug, Default)]
+pub struct Failover {
+    /// The settings it was last updated with
+    settings: FailoverSettings,
+    side: FailoverSide,
+    /// When each input last changed between locked and not, and which way
+    primary: LockTracker,
+    backup: LockTracker,
+    /// The most recent switches and when they happened, newest last
+    history: VecDeque<(Instant, FailoverSide)>,
+}
+
+impl Failover {
+    /// The side being followed
+    pub fn side(&self) -> FailoverSide {
+        self.side
+    }
+
+    /// The input being followed, None if that side hasn't been chosen
+    pub fn active_input(&self) -> Option<InputId> {
+        self.settings.input(self.side)
+    }
+
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &(Instant, FailoverSide)> {
+        self.history.iter()
+    }
+
+    /// Switches to a side by hand
+    pub fn switch_to(&mut self, side: FailoverSide, now: Instant) {
+        if self.side == side {
+            return;
+        }
+
+        self.side = side;
+        self.history.push_back((now, side));
+        if self.history.len() > HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+    }
+
+    /// Takes in how both inputs are doing, switching sides if it is time to. Nothing happens until both inputs
+    /// have been chosen. Returns the side switched to, None if it stayed where it was
+    pub fn update(
+        &mut self,
+        settings: FailoverSettings,
+        primary: InputSignal,
+        backup: InputSignal,
+        now: Instant,
+    ) -> Option<FailoverSide> {
+        self.settings = settings;
+        if settings.primary.is_none() || settings.backup.is_none() {
+            return None;
+        }
+
+        self.primary.update(settings.is_locked(primary), now);
+        self.backup.update(settings.is_locked(backup), now);
+
+        let threshold = Duration::from_millis(u64::from(settings.threshold_millis));
+        let switch_back_after = Duration::from_millis(u64::from(settings.switch_back_after_millis));
+
+        let switch_to = match self.side {
+            // Only worth switching if the backup is any better
+            FailoverSide::Primary
+                if self.primary.unlocked_for(now) >= Some(threshold)
+                    && self.backup.locked_for(now).is_some() =>
+            {
+                Some(FailoverSide::Backup)
+            }
+            FailoverSide::Backup
+                if settings.switch_back == SwitchBack::Automatic
+                    && self.primary.locked_for(now) >= Some(switch_back_after) =>
+            {
+                Some(FailoverSide::Primary)
+            }
+            // The backup has gone too, so the primary is the best there is whatever the switch back setting
+            FailoverSide::Backup
+                if self.backup.unlocked_for(now) >= Some(threshold)
+                    && self.primary.locked_for(now).is_some() =>
+            {
+                Some(FailoverSide::Primary)
+            }
+            _ => None,
+        }?;
+
+        self.switch_to(switch_to, now);
+        Some(switch_to)
+    }
+}
+
+/// Remembers when an input last gained or lost lock
+#[derive(Debug, Default)]
+struct LockTracker {
+    /// Whether it is locked and since when
+    since: Option<(bool, Instant)>,
+}
+
+impl LockTracker {
+    fn update(&mut self, locked: bool, now: Instant) {
+        if self
+            .since
+            .is_none_or(|(was_locked, _)| was_locked != locked)
+        {
+            self.since = Some((locked, now));
+        }
+    }
+
+    /// How long it has been locked, None if it isn't
+    fn locked_for(&self, now: Instant) -> Option<Duration> {
+        match self.since {
+            Some((true, since)) => Some(now.saturating_duration_since(since)),
+            _ => None,
+        }
+    }
+
+    /// How long it has been without lock, None if it is locked
+    fn unlocked_for(&self, now: Instant) -> Option<Duration> {
+        match self.since {
+            Some((false, since)) => Some(now.saturating_duration_since(since)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn settings(switch_back: SwitchBack) -> FailoverSettings {
+        FailoverSettings {
+            primary: Some(InputId(0)),
+            backup: Some(InputId(1)),
+            threshold_millis: 500,
+            switch_back,
+            switch_back_after_millis: 2_000,
+            stopped_is_loss: false,
+        }
+    }
+
+    #[test]
+    fn test_fails_over_after_threshold() {
+        let settings = settings(SwitchBack::Manual);
+        let mut failover = Failover::default();
+        let now = Instant::now();
+
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Locked.into(),
+                SignalState::Locked.into(),
+                now
+            ),
+            None
+        );
+
+        // A short glitch is ridden out
+        failover.update(
+            settings,
+            SignalState::Freewheeling.into(),
+            SignalState::Locked.into(),
+            now,
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Freewheeling.into(),
+                SignalState::Locked.into(),
+                now + Duration::from_millis(400)
+            ),
+            None
+        );
+        failover.update(
+            settings,
+            SignalState::Locked.into(),
+            SignalState::Locked.into(),
+            now + Duration::from_millis(450),
+        );
+
+        // A longer one isn't
+        failover.update(
+            settings,
+            SignalState::Lost.into(),
+            SignalState::Locked.into(),
+            now + Duration::from_secs(1),
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Lost.into(),
+                SignalState::Locked.into(),
+                now + Duration::from_millis(1500)
+            ),
+            Some(FailoverSide::Backup)
+        );
+        assert_eq!(failover.active_input(), Some(InputId(1)));
+
+        // Switching back is left to the operator
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Locked.into(),
+                SignalState::Locked.into(),
+                now + Duration::from_secs(60)
+            ),
+            None
+        );
+        assert_eq!(failover.history().count(), 1);
+    }
+
+    #[test]
+    fn test_stays_put_if_backup_is_no_better() {
+        let settings = settings(SwitchBack::Manual);
+        let mut failover = Failover::default();
+        let now = Instant::now();
+
+        failover.update(
+            settings,
+            SignalState::Lost.into(),
+            SignalState::Lost.into(),
+            now,
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Lost.into(),
+                SignalState::Lost.into(),
+                now + Duration::from_secs(5)
+            ),
+            None
+        );
+        assert_eq!(failover.side(), FailoverSide::Primary);
+    }
+
+    #[test]
+    fn test_automatic_switch_back() {
+        let settings = settings(SwitchBack::Automatic);
+        let mut failover = Failover::default();
+        let now = Instant::now();
+
+        failover.update(
+            settings,
+            SignalState::Lost.into(),
+            SignalState::Locked.into(),
+            now,
+        );
+        failover.update(
+            settings,
+            SignalState::Lost.into(),
+            SignalState::Locked.into(),
+            now + Duration::from_secs(1),
+        );
+        assert_eq!(failover.side(), FailoverSide::Backup);
+
+        // The primary has to be back for a while first
+        failover.update(
+            settings,
+            SignalState::Locked.into(),
+            SignalState::Locked.into(),
+            now + Duration::from_secs(2),
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Locked.into(),
+                SignalState::Locked.into(),
+                now + Duration::from_secs(3)
+            ),
+            None
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Locked.into(),
+                SignalState::Locked.into(),
+                now + Duration::from_secs(4)
+            ),
+            Some(FailoverSide::Primary)
+        );
+    }
+
+    #[test]
+    fn test_returns_to_primary_if_backup_fails() {
+        let settings = settings(SwitchBack::Manual);
+        let mut failover = Failover::default();
+        let now = Instant::now();
+
+        failover.switch_to(FailoverSide::Backup, now);
+        failover.update(
+            settings,
+            SignalState::Locked.into(),
+            SignalState::Lost.into(),
+            now,
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Locked.into(),
+                SignalState::Lost.into(),
+                now + Duration::from_secs(1)
+            ),
+            Some(FailoverSide::Primary)
+        );
+    }
+
+    #[test]
+    fn test_stopped_primary_only_fails_over_if_set_to() {
+        let now = Instant::now();
+
+        for (stopped_is_loss, expected) in [(false, None), (true, Some(FailoverSide::Backup))] {
+            let settings = FailoverSettings {
+                stopped_is_loss,
+                ..settings(SwitchBack::Manual)
+            };
+            let mut failover = Failover::default();
+
+            failover.update(
+                settings,
+                SignalState::Stopped.into(),
+                SignalState::Locked.into(),
+                now,
+            );
+            assert_eq!(
+                failover.update(
+                    settings,
+                    SignalState::Stopped.into(),
+                    SignalState::Locked.into(),
+                    now + Duration::from_secs(1)
+                ),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_dropout_while_playing_fails_over_after_freewheeling() {
+        let settings = FailoverSettings {
+            threshold_millis: 2_000,
+            ..settings(SwitchBack::Manual)
+        };
+        let playing = |state| InputSignal {
+            state,
+            playing: true,
+        };
+        let now = Instant::now();
+
+        // Freewheeling runs out well before the threshold, but stopping afterwards doesn't reset it
+        let mut failover = Failover::default();
+        failover.update(
+            settings,
+            playing(SignalState::Locked),
+            SignalState::Locked.into(),
+            now,
+        );
+        failover.update(
+            settings,
+            playing(SignalState::Freewheeling),
+            SignalState::Locked.into(),
+            now,
+        );
+        failover.update(
+            settings,
+            playing(SignalState::Stopped),
+            SignalState::Locked.into(),
+            now + Duration::from_millis(1_100),
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                playing(SignalState::Stopped),
+                SignalState::Locked.into(),
+                now + Duration::from_millis(2_100)
+            ),
+            Some(FailoverSide::Backup)
+        );
+
+        // Without any freewheeling it goes straight to stopped
+        let mut failover = Failover::default();
+        failover.update(
+            settings,
+            playing(SignalState::Locked),
+            SignalState::Locked.into(),
+            now,
+        );
+        failover.update(
+            settings,
+            playing(SignalState::Stopped),
+            SignalState::Locked.into(),
+            now + Duration::from_millis(100),
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                playing(SignalState::Stopped),
+                SignalState::Locked.into(),
+                now + Duration::from_millis(2_100)
+            ),
+            Some(FailoverSide::Backup)
+        );
+    }
+
+    #[test]
+    fn test_does_nothing_until_both_inputs_are_chosen() {
+        let settings = FailoverSettings {
+            primary: None,
+            ..settings(SwitchBack::Manual)
+        };
+        let mut failover = Failover::default();
+        let now = Instant::now();
+
+        failover.update(
+            settings,
+            SignalState::Lost.into(),
+            SignalState::Locked.into(),
+            now,
+        );
+        assert_eq!(
+            failover.update(
+                settings,
+                SignalState::Lost.into(),
+                SignalState::Locked.into(),
+                now + Duration::from_secs(5)
+            ),
+            None
+        );
+        assert_eq!(failover.side(), FailoverSide::Primary);
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    io::{Write as _, stdout},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+
+use crate::backend::{
+    mtc_decoder::MtcTimecodeDecoder,
+    timecode_decoder::{DecoderStatus, PlaybackDirection, TimecodeDecoder as _, TimecodePosition},
+    timecode_formatter::TimecodeFormat,
+};
+
+/// How often to check the decoder for new timecode, well under a frame at any rate
+const POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+pub const USAGE: &str = "\
+Usage: technus_timecode_headless [options]
+       technus_timecode --headless [options]    (not on Windows)
+
+Reads MTC from a MIDI port and prints a line every time the timecode or its status changes.
+
+Options:
+  --list-ports        Print the available MIDI ports and exit
+  --port <port>       The port to read from, by index or by (part of its) name.
+                      Can be left out if there is only one port
+  --freewheel <n>     How many frames to keep counting for if the timecode drops out
+  --json              Print JSON lines instead of plain text
+  --help              Print this and exit";
+
+/// How lines are written to stdout
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum OutputFormat {
+    /// E.g: "01:02:03:04 25fps Locked Playing"
+    #[default]
+    Text,
+    /// One JSON object per line, for other programs to read
+    Json,
+}
+
+/// What was asked for on the command line
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Options {
+    pub help: bool,
+    pub list_ports: bool,
+    /// The index or name of the port to read from
+    pub port: Option<String>,
+    pub freewheel_frames: Option<u32>,
+    pub format: OutputFormat,
+}
+
+impl Options {
+    /// Reads the options out of the command line arguments, not including the program name.
+    ///
+    /// # Errors
+    /// Fails if an argument isn't known or is missing its value
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| anyhow!("{name} needs a value, see --help"))
+            };
+
+            match arg.as_str() {
+                "--help" | "-h" => options.help = true,
+                "--list-ports" => options.list_ports = true,
+                "--json" => options.format = OutputFormat::Json,
+                "--port" => options.port = Some(value("--port")?),
+                "--freewheel" => {
+                    let frames = value("--freewheel")?;
+                    options.freewheel_frames = Some(
+                        frames
+                            .parse()
+                            .map_err(|_err| anyhow!("{frames} is not a number of frames"))?,
+                    );
+                }
+                _ => return Err(anyhow!("Unknown argument {arg}, see --help")),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Picks a port by index, then by exact name, then by part of its name ignoring case.
+///
+/// # Errors
+/// Fails if no port matches, or more than one port has that in its name
+pub fn find_port(names: &[String], wanted: &str) -> Result<usize> {
+    if let Ok(index) = wanted.parse::<usize>() {
+        return if index < names.len() {
+            Ok(index)
+        } else {
+            Err(anyhow!("There is no port {index}, see --list-ports"))
+        };
+    }
+
+    if let Some(index) = names.iter().position(|name| name == wanted) {
+        return Ok(index);
+    }
+
+    let wanted_lower = wanted.to_lowercase();
+    let mut matches = names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| name.to_lowercase().contains(&wanted_lower));
+
+    match (matches.next(), matches.next()) {
+        (Some((index, _)), None) => Ok(index),
+        (None, _) => Err(anyhow!("No port is called {wanted}, see --list-ports")),
+        (Some(_), Some(_)) => Err(anyhow!(
+            "More than one port has {wanted} in its name, use its index instead"
+        )),
+    }
+}
+
+/// A port as printed by `--list-ports --json`
+#[derive(Serialize)]
+struct JsonPort<'a> {
+    index: usize,
+    name: &'a str,
+}
+
+/// The timecode and status as printed by `--json`
+#[derive(Serialize)]
+struct JsonStatus {
+    timecode: Option<String>,
+    rate: Option<String>,
+    measured_fps: Option<f64>,
+    signal: String,
+    direction: Option<&'static str>,
+    dropouts: u32,
+    malformed: u32,
+}
+
+/// The line to print for a port when listing them
+pub fn port_line(format: OutputFormat, index: usize, name: &str) -> String {
+    match format {
+        OutputFormat::Text => format!("{index}: {name}"),
+        OutputFormat::Json => serde_json::to_string(&JsonPort { index, name })
+            .expect("A port name can always be written as JSON"),
+    }
+}
+
+/// The line to print for the current timecode and status. The timecode is None until something has been received
+pub fn status_line(
+    format: OutputFormat,
+    position: Option<TimecodePosition>,
+    status: &DecoderStatus,
+) -> String {
+    let timecode = position.map(|position| {
+        position
+            .timecode
+            .format(TimecodeFormat::default())
+            .to_string()
+    });
+
+    match format {
+        OutputFormat::Text => {
+            let direction = match status.direction {
+                Some(PlaybackDirection::Forward) => "Playing",
+                Some(PlaybackDirection::Reverse) => "Reversing",
+                None => "Parked",
+            };
+
+            format!(
+                "{} {} {} {direction}",
+                timecode.as_deref().unwrap_or("--:--:--:--"),
+                status
+                    .rate
+                    .map_or_else(|| "?fps".to_owned(), |rate| rate.to_string()),
+                status.signal,
+            )
+        }
+        OutputFormat::Json => serde_json::to_string(&JsonStatus {
+            timecode,
+            rate: status.rate.map(|rate| rate.to_string()),
+            // Nobody needs the measured rate closer than this, and it keeps the lines short
+            measured_fps: status
+                .measured_fps
+                .filter(|fps| fps.is_finite())
+                .map(|fps| (fps * 1000.0).round() / 1000.0),
+            signal: status.signal.to_string(),
+            direction: status.direction.map(|direction| match direction {
+                PlaybackDirection::Forward => "forward",
+                PlaybackDirection::Reverse => "reverse",
+            }),
+            dropouts: status.errors.dropouts,
+            malformed: status.errors.malformed,
+        })
+        .expect("A status can always be written as JSON"),
+    }
+}
+
+/// Runs without a window, reading MTC and printing it to stdout until the process is stopped or stdout is closed.
+///
+/// # Errors
+/// Fails if the arguments are wrong, MIDI could not be initialised or the port could not be opened
+pub fn run(args: impl IntoIterator<Item = String>) -> Result<()> {
+    let options = Options::parse(args)?;
+    let mut out = stdout().lock();
+
+    if options.help {
+        writeln!(out, "{USAGE}")?;
+        return Ok(());
+    }
+
+    let mut decoder = MtcTimecodeDecoder::new()?;
+    let ports = decoder.input.get_ports();
+    let names = ports
+        .iter()
+        .map(|port| decoder.input.port_name(port))
+        .collect::<Result<Vec<_>>>()?;
+
+    if options.list_ports {
+        for (index, name) in names.iter().enumerate() {
+            writeln!(out, "{}", port_line(options.format, index, name))?;
+        }
+        return Ok(());
+    }
+
+    let index = match (&options.port, names.len()) {
+        (Some(wanted), _) => find_port(&names, wanted)?,
+        (None, 0) => return Err(anyhow!("No MIDI ports were found")),
+        (None, 1) => 0,
+        (None, _) => {
+            return Err(anyhow!(
+                "There is more than one MIDI port, pick one with --port. See --list-ports"
+            ));
+        }
+    };
+
+    decoder.input.port = ports.into_iter().nth(index);
+    if let Some(frames) = options.freewheel_frames {
+        decoder.flywheel.freewheel_frames = frames;
+    }
+    decoder.connect()?;
+
+    let mut position = None;
+    let mut last_printed = None;
+    loop {
+        if let Some(latest) = decoder.get_current_position() {
+            position = Some(latest);
+        }
+
+        // The measured rate wobbles all the time, so it doesn't count as a change on its own
+        let status = decoder.status();
+        let printed = Some((
+            position.map(|position| position.timecode),
+            status.signal,
+            status.rate,
+            status.direction,
+            status.errors,
+        ));
+        if printed != last_printed {
+            let line = status_line(options.format, position, &status);
+
+            // Whatever we were piped into has gone away, so there is nobody left to print for
+            if writeln!(out, "{line}").and_then(|()| out.flush()).is_err() {
+                return Ok(());
+            }
+            last_printed = printed;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::backend::{flywheel::SignalState, frame_rate::FrameRate, timecode::Timecode};
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(
+            Options::parse(args("--port 2 --json --freewheel 10")).ok(),
+            Some(Options {
+                port: Some("2".to_owned()),
+                freewheel_frames: Some(10),
+                format: OutputFormat::Json,
+                ..Default::default()
+            })
+        );
+
+        assert!(Options::parse(args("--port")).is_err());
+        assert!(Options::parse(args("--freewheel lots")).is_err());
+        assert!(Options::parse(args("--window")).is_err());
+    }
+
+    #[test]
+    fn test_find_port() {
+        let names = args("Midi-Through IAC-Bus IAC-Bus-2");
+
+        assert_eq!(find_port(&names, "0").ok(), Some(0));
+        assert!(find_port(&names, "3").is_err());
+        assert_eq!(find_port(&names, "IAC-Bus").ok(), Some(1));
+        assert_eq!(find_port(&names, "through").ok(), Some(0));
+        assert!(find_port(&names, "iac").is_err());
+        assert!(find_port(&names, "rtpMIDI").is_err());
+    }
+
+    #[test]
+    fn test_status_line() {
+        let position = TimecodePosition::from(Timecode::new(1, 2, 3, 4, FrameRate::Fps25));
+        let status = DecoderStatus {
+            signal: SignalState::Locked,
+            rate: Some(FrameRate::Fps25),
+            measured_fps: Some(25.0),
+            direction: Some(PlaybackDirection::Forward),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            status_line(OutputFormat::Text, Some(position), &status),
+            "01:02:03:04 25fps Locked Playing"
+        );
+        assert_eq!(
+            status_line(OutputFormat::Json, Some(position), &status),
+            r#"{"timecode":"01:02:03:04","rate":"25fps","measured_fps":25.0,"signal":"Locked","direction":"forward","dropouts":0,"malformed":0}"#
+        );
+
+        assert_eq!(
+            status_line(OutputFormat::Text, None, &DecoderStatus::default()),
+            "--:--:--:-- ?fps No signal Parked"
+        );
+    }
+
+    #[test]
+    fn test_port_line_escapes_names() {
+        assert_eq!(
+            port_line(OutputFormat::Json, 1, "Bus \"A\"\\1"),
+            r#"{"index":1,"name":"Bus \"A\"\\1"}"#
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{
+        beat_clock_decoder::BeatClockDecoder,
+        ltc_decoder::LtcTimecodeDecoder,
+        mtc_decoder::MtcTimecodeDecoder,
+        timecode_decoder::{DecoderStatus, TimecodeDecoder, TimecodePosition},
+        timecode_generator::TimecodeGenerator,
+    },
+    timecode_type::TimecodeType,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::{artnet_decoder::ArtnetTimecodeDecoder, osc_decoder::OscTimecodeDecoder};
+
+/// Tells inputs apart. It is saved with the app so routes still point at the same input next time
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, Serialize, Deserialize,
+)]
+pub struct InputId(pub u32);
+
+/// A decoder for any type of timecode
+pub enum Decoder {
+    #[cfg(not(target_arch = "wasm32"))]
+    Artnet(ArtnetTimecodeDecoder),
+    BeatClock(BeatClockDecoder),
+    Generator(TimecodeGenerator),
+    Ltc(LtcTimecodeDecoder),
+    Mtc(MtcTimecodeDecoder),
+    #[cfg(not(target_arch = "wasm32"))]
+    Osc(OscTimecodeDecoder),
+}
+
+impl Decoder {
+    /// Creates a new, disconnected, decoder for a type of timecode
+    pub fn new(timecode_type: TimecodeType) -> Self {
+        match timecode_type {
+            #[cfg(not(target_arch = "wasm32"))]
+            TimecodeType::Artnet => Self::Artnet(ArtnetTimecodeDecoder::new()),
+            TimecodeType::BeatClock => Self::BeatClock(
+                BeatClockDecoder::new().expect("Catastropically failed to initialise MIDI backend"),
+            ),
+            TimecodeType::Generator => Self::Generator(TimecodeGenerator::default()),
+            TimecodeType::Ltc => Self::Ltc(LtcTimecodeDecoder::new()),
+            TimecodeType::Mtc => Self::Mtc(
+                MtcTimecodeDecoder::new()
+                    .expect("Catastropically failed to initialise MIDI backend"),
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            TimecodeType::Osc => Self::Osc(OscTimecodeDecoder::new()),
+        }
+    }
+
+    pub fn timecode_type(&self) -> TimecodeType {
+        match *self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Artnet(_) => TimecodeType::Artnet,
+            Self::BeatClock(_) => TimecodeType::BeatClock,
+            Self::Generator(_) => TimecodeType::Generator,
+            Self::Ltc(_) => TimecodeType::Ltc,
+            Self::Mtc(_) => TimecodeType::Mtc,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc(_) => TimecodeType::Osc,
+        }
+    }
+
+    /// Closes the decoder's connection if it has one. Call this before dropping a decoder so its MIDI, audio or
+    /// network thread stops before the channel it sends on goes away
+    pub fn disconnect(&mut self) {
+        let result = match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Artnet(decoder) if decoder.connected() => decoder.disconnect(),
+            Self::BeatClock(decoder) if decoder.connected() => decoder.disconnect(),
+            Self::Ltc(decoder) if decoder.connected() => decoder.disconnect(),
+            Self::Mtc(decoder) if decoder.connected() => decoder.disconnect(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc(decoder) if decoder.connected() => decoder.disconnect(),
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            log::error!("Failed to disconnect {:?} input: {e}", self.timecode_type());
+        }
+    }
+
+    fn inner(&self) -> &dyn TimecodeDecoder {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Artnet(decoder) => decoder,
+            Self::BeatClock(decoder) => decoder,
+            Self::Generator(generator) => generator,
+            Self::Ltc(decoder) => decoder,
+            Self::Mtc(decoder) => decoder,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc(decoder) => decoder,
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut dyn TimecodeDecoder {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Artnet(decoder) => decoder,
+            Self::BeatClock(decoder) => decoder,
+            Self::Generator(generator) => generator,
+            Self::Ltc(decoder) => decoder,
+            Self::Mtc(decoder) => decoder,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc(decoder) => decoder,
+        }
+    }
+}
+
+impl TimecodeDecoder for Decoder {
+    fn get_current_position(&mut self) -> Option<TimecodePosition> {
+        self.inner_mut().get_current_position()
+    }
+
+    fn status(&self) -> DecoderStatus {
+        self.inner().status()
+    }
+}
+
+/// One of the timecode sources being read
+pub struct Input {
+    /// What the input is called, E.g: "Main" or "Backup"
+    pub name: String,
+    pub decoder: Decoder,
+}
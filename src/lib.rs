@@ -1,8 +1,15 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
-mod backend;
+pub mod backend;
 mod components;
+mod devices;
+mod engine;
+mod failover;
+pub mod headless;
+mod input;
+mod output;
+mod routing;
 mod timecode_type;
 
 pub use app::App;
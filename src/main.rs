@@ -1,9 +1,18 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-fn main() -> eframe::Result {
+fn main() -> anyhow::Result<()> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    // No window, just timecode on stdout. Windows gives the app no console to print to, so there it is only
+    // `technus_timecode_headless`
+    #[cfg(not(windows))]
+    if std::env::args().any(|arg| arg == "--headless") {
+        return technus_timecode::headless::run(
+            std::env::args().skip(1).filter(|arg| arg != "--headless"),
+        );
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
@@ -20,74 +29,5 @@ fn main() -> eframe::Result {
         native_options,
         Box::new(|cc| Ok(Box::new(technus_timecode::App::new(cc)))),
     )
+    .map_err(|e| anyhow::anyhow!("{e}"))
 }
-
-use std::error::Error;
-use std::io::{Write, stdin, stdout};
-
-use midir::{Ignore, MidiInput};
-
-// fn main() {
-//     env_logger::init();
-//     match run() {
-//         Ok(_) => (),
-//         Err(err) => println!("Error: {}", err),
-//     }
-// }
-
-// fn run() -> Result<(), Box<dyn Error>> {
-//     let mut input = String::new();
-
-//     let mut midi_in = MidiInput::new("midir reading input")?;
-//     midi_in.ignore(Ignore::None);
-
-//     // Get an input port (read from console if multiple are available)
-//     let in_ports = midi_in.ports();
-//     let in_port = match in_ports.len() {
-//         0 => return Err("no input port found".into()),
-//         1 => {
-//             println!(
-//                 "Choosing the only available input port: {}",
-//                 midi_in.port_name(&in_ports[0]).unwrap()
-//             );
-//             &in_ports[0]
-//         }
-//         _ => {
-//             println!("\nAvailable input ports:");
-//             for (i, p) in in_ports.iter().enumerate() {
-//                 println!("{}: {}", i, midi_in.port_name(p).unwrap());
-//             }
-//             print!("Please select input port: ");
-//             stdout().flush()?;
-//             let mut input = String::new();
-//             stdin().read_line(&mut input)?;
-//             in_ports
-//                 .get(input.trim().parse::<usize>()?)
-//                 .ok_or("invalid input port selected")?
-//         }
-//     };
-
-//     println!("\nOpening connection");
-//     let in_port_name = midi_in.port_name(in_port)?;
-
-//     // _conn_in needs to be a named parameter, because it needs to be kept alive until the end of the scope
-//     let _conn_in = midi_in.connect(
-//         in_port,
-//         "midir-read-input",
-//         move |stamp, message, _| {
-//             println!("{}: {:?} (len = {})", stamp, message, message.len());
-//         },
-//         (),
-//     )?;
-
-//     println!(
-//         "Connection open, reading input from '{}' (press enter to exit) ...",
-//         in_port_name
-//     );
-
-//     input.clear();
-//     stdin().read_line(&mut input)?; // wait for next enter key press
-
-//     println!("Closing connection");
-//     Ok(())
-// }
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{
+        frame_rate::FrameRate,
+        ltc_generator::LtcGenerator,
+        mtc_generator::MtcGenerator,
+        timecode_decoder::{PlaybackDirection, TimecodePosition},
+    },
+    timecode_type::TimecodeOutput,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::{artnet_sender::ArtnetTimecodeSender, osc_sender::OscTimecodeSender};
+
+/// Tells outputs apart. It is saved with the app so routes still point at the same output next time
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, Serialize, Deserialize,
+)]
+pub struct OutputId(pub u32);
+
+/// A sender for any type of output
+pub enum OutputSender {
+    #[cfg(not(target_arch = "wasm32"))]
+    Artnet(ArtnetTimecodeSender),
+    Ltc(LtcGenerator),
+    Mtc(MtcGenerator),
+    #[cfg(not(target_arch = "wasm32"))]
+    Osc(OscTimecodeSender),
+}
+
+impl OutputSender {
+    /// Creates a new, stopped, sender for a type of output
+    pub fn new(output_type: TimecodeOutput) -> Self {
+        match output_type {
+            #[cfg(not(target_arch = "wasm32"))]
+            TimecodeOutput::ArtnetSender => Self::Artnet(ArtnetTimecodeSender::new()),
+            TimecodeOutput::LtcGenerator => Self::Ltc(LtcGenerator::new()),
+            TimecodeOutput::MtcGenerator => Self::Mtc(
+                MtcGenerator::new().expect("Catastropically failed to initialise MIDI backend"),
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            TimecodeOutput::OscSender => Self::Osc(OscTimecodeSender::new()),
+        }
+    }
+
+    pub fn output_type(&self) -> TimecodeOutput {
+        match *self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Artnet(_) => TimecodeOutput::ArtnetSender,
+            Self::Ltc(_) => TimecodeOutput::LtcGenerator,
+            Self::Mtc(_) => TimecodeOutput::MtcGenerator,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc(_) => TimecodeOutput::OscSender,
+        }
+    }
+
+    /// Returns true if the output is sending
+    pub fn running(&self) -> bool {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Artnet(sender) => sender.running(),
+            Self::Ltc(generator) => generator.running(),
+            Self::Mtc(generator) => generator.running(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc(sender) => sender.running(),
+        }
+    }
+
+    /// The rate the output is sending at, None if it sends at whatever rate it is given or isn't running
+    pub fn rate(&self) -> Option<FrameRate> {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Artnet(sender) => sender.rate(),
+            Self::Ltc(generator) => generator.rate(),
+            Self::Mtc(generator) => generator.rate(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc(sender) => sender.rate(),
+        }
+    }
+
+    /// Give the output the latest position from the source routed to it
+    pub fn update(&mut self, position: TimecodePosition, direction: Option<PlaybackDirection>) {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Artnet(sender) => sender.update(position, direction),
+            Self::Ltc(generator) => generator.update(position, direction),
+            Self::Mtc(generator) => generator.update(position, direction),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Osc(sender) => sender.update(position, direction),
+        }
+    }
+}
+
+/// One of the places timecode is being sent
+pub struct Output {
+    /// What the output is called, E.g: "Lighting desk"
+    pub name: String,
+    pub sender: OutputSender,
+}
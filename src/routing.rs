@@ -0,0 +1,198 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::{frame_rate::FrameRate, timecode::Timecode, timecode_decoder::TimecodePosition},
+    output::OutputId,
+    timecode_type::TimecodeSource,
+};
+
+/// How to carry timecode across to an output running at a different rate
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum RateConversion {
+    /// Send the same labels where the rates count the same number of frames a second, E.g: 29.97fps NDF sent as 30fps.
+    /// What a bridge between two formats is usually expected to do
+    #[default]
+    KeepLabels,
+    /// Send whatever label is at the same point in wall-clock time
+    KeepTime,
+}
+
+impl RateConversion {
+    pub const ALL: [Self; 2] = [Self::KeepLabels, Self::KeepTime];
+
+    pub fn convert(self, timecode: Timecode, rate: FrameRate) -> Timecode {
+        match self {
+            Self::KeepLabels => timecode.relabel(rate),
+            Self::KeepTime => timecode.with_rate(rate),
+        }
+    }
+}
+
+impl fmt::Display for RateConversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::KeepLabels => write!(f, "Keep labels"),
+            Self::KeepTime => write!(f, "Keep real time"),
+        }
+    }
+}
+
+/// Sends the timecode from a source on to an output
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Route {
+    pub source: TimecodeSource,
+    pub output: OutputId,
+    /// Added on to the timecode after it has been converted to the output's rate, E.g: -25 to send
+    /// a second behind the source at 25fps
+    pub offset_frames: i32,
+    pub conversion: RateConversion,
+}
+
+impl Route {
+    pub fn new(source: TimecodeSource, output: OutputId) -> Self {
+        Self {
+            source,
+            output,
+            offset_frames: 0,
+            conversion: RateConversion::default(),
+        }
+    }
+
+    /// The position the output should send for a position from the source. `output_rate` is None if the output
+    /// sends at whatever rate it is given
+    pub fn apply(
+        &self,
+        position: TimecodePosition,
+        output_rate: Option<FrameRate>,
+    ) -> TimecodePosition {
+        let position = match output_rate {
+            Some(rate) => TimecodePosition {
+                timecode: self.conversion.convert(position.timecode, rate),
+                subframe: position.subframe,
+            },
+            None => position,
+        };
+
+        position.offset_by_frames(f64::from(self.offset_frames))
+    }
+}
+
+/// Which source each output takes its timecode from. An output only ever sends one timecode,
+/// but a source can go to as many outputs as you like
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingMatrix {
+    routes: Vec<Route>,
+}
+
+impl RoutingMatrix {
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    /// The route to an output, None if nothing is sent to it
+    pub fn route(&self, output: OutputId) -> Option<&Route> {
+        self.routes.iter().find(|route| route.output == output)
+    }
+
+    pub fn route_mut(&mut self, output: OutputId) -> Option<&mut Route> {
+        self.routes.iter_mut().find(|route| route.output == output)
+    }
+
+    /// Sends a source to an output in place of whatever was sent to it before, keeping the route's offset and
+    /// rate conversion. An output's own timecode can't be sent back to it, so that is ignored
+    pub fn connect(&mut self, source: TimecodeSource, output: OutputId) {
+        if source == TimecodeSource::Output(output) {
+            return;
+        }
+
+        match self.route_mut(output) {
+            Some(route) => route.source = source,
+            None => self.routes.push(Route::new(source, output)),
+        }
+    }
+
+    /// Stops sending anything to an output, E.g: when it is removed
+    pub fn disconnect(&mut self, output: OutputId) {
+        self.routes.retain(|route| route.output != output);
+    }
+
+    /// Stops sending a source anywhere, E.g: when it is removed
+    pub fn disconnect_source(&mut self, source: TimecodeSource) {
+        self.routes.retain(|route| route.source != source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::input::InputId;
+
+    #[test]
+    fn test_apply() {
+        let position = TimecodePosition {
+            timecode: Timecode::new(1, 0, 0, 12, FrameRate::Fps30),
+            subframe: 0.5,
+        };
+        let mut route = Route::new(TimecodeSource::Input(InputId(0)), OutputId(0));
+
+        assert_eq!(route.apply(position, None), position);
+
+        // 29.97 counts the same frames as 30, so the labels carry straight across
+        assert_eq!(
+            route.apply(position, Some(FrameRate::Fps29_97Ndf)).timecode,
+            Timecode::new(1, 0, 0, 12, FrameRate::Fps29_97Ndf)
+        );
+
+        route.conversion = RateConversion::KeepTime;
+        route.offset_frames = -2;
+        let converted = route.apply(position, Some(FrameRate::Fps25));
+        assert_eq!(
+            converted.timecode,
+            Timecode::new(1, 0, 0, 8, FrameRate::Fps25)
+        );
+        assert!((converted.subframe - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_one_route_per_output() {
+        let mut routing = RoutingMatrix::default();
+        let mtc = TimecodeSource::Input(InputId(0));
+        let ltc = TimecodeSource::Input(InputId(1));
+        let artnet_out = OutputId(0);
+        let ltc_out = OutputId(1);
+        let mtc_out = OutputId(2);
+
+        routing.connect(mtc, artnet_out);
+        routing.connect(mtc, ltc_out);
+        if let Some(route) = routing.route_mut(artnet_out) {
+            route.offset_frames = 10;
+        }
+
+        // Routing something else to an output replaces what was there, keeping its settings
+        routing.connect(ltc, artnet_out);
+        assert_eq!(routing.routes().len(), 2);
+        assert_eq!(
+            routing.route(artnet_out),
+            Some(&Route {
+                offset_frames: 10,
+                ..Route::new(ltc, artnet_out)
+            })
+        );
+
+        // An output can't take its own timecode
+        routing.connect(TimecodeSource::Output(ltc_out), ltc_out);
+        assert_eq!(routing.route(ltc_out).map(|route| route.source), Some(mtc));
+
+        routing.disconnect(ltc_out);
+        assert_eq!(routing.route(ltc_out), None);
+
+        // Removing a source takes it off every output
+        routing.connect(ltc, mtc_out);
+        routing.disconnect_source(ltc);
+        assert_eq!(routing.routes(), []);
+    }
+}
@@ -1,14 +1,85 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+use crate::{input::InputId, output::OutputId};
+
+/// The network types are only there natively, a browser can't open UDP sockets
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum TimecodeType {
+    #[cfg(not(target_arch = "wasm32"))]
     Artnet,
+    /// MIDI beat clock and song position, turned into timecode
+    BeatClock,
+    /// The internal generator, which runs from its own clock
+    Generator,
     Ltc,
+    #[default]
     Mtc,
+    #[cfg(not(target_arch = "wasm32"))]
+    Osc,
+}
+
+impl TimecodeType {
+    /// Every type of timecode that can be read or generated
+    pub const ALL: &[Self] = &[
+        #[cfg(not(target_arch = "wasm32"))]
+        Self::Artnet,
+        Self::BeatClock,
+        Self::Generator,
+        Self::Ltc,
+        Self::Mtc,
+        #[cfg(not(target_arch = "wasm32"))]
+        Self::Osc,
+    ];
+}
+
+/// Somewhere timecode can be taken from to be sent on elsewhere.
+/// Inputs have names, so see [`GlobalState::source_name`](crate::app::GlobalState::source_name) to display one
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub enum TimecodeSource {
+    /// One of the inputs, read from outside or generated internally
+    Input(InputId),
+    /// Whichever of the primary and backup inputs the failover is following
+    Failover,
+    /// The timecode an output is sending, only LTC outputs can say what that is
+    Output(OutputId),
+}
+
+/// A type of output timecode can be sent to, the network ones only natively like [`TimecodeType`]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum TimecodeOutput {
+    #[cfg(not(target_arch = "wasm32"))]
+    #[default]
+    ArtnetSender,
+    #[cfg_attr(target_arch = "wasm32", default)]
+    LtcGenerator,
+    MtcGenerator,
+    #[cfg(not(target_arch = "wasm32"))]
+    OscSender,
+}
+
+impl TimecodeOutput {
+    /// Every type of output timecode can be sent to
+    pub const ALL: &[Self] = &[
+        #[cfg(not(target_arch = "wasm32"))]
+        Self::ArtnetSender,
+        Self::LtcGenerator,
+        Self::MtcGenerator,
+        #[cfg(not(target_arch = "wasm32"))]
+        Self::OscSender,
+    ];
 }
 
-impl Default for TimecodeType {
-    fn default() -> Self {
-        TimecodeType::Mtc
+impl fmt::Display for TimecodeOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::ArtnetSender => write!(f, "Art-Net out"),
+            Self::LtcGenerator => write!(f, "LTC out"),
+            Self::MtcGenerator => write!(f, "MTC out"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::OscSender => write!(f, "OSC out"),
+        }
     }
 }